@@ -0,0 +1,43 @@
+use pg_helper::{gen_table, Json, PgTableExtension};
+use postgres::{types::Type, Client};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Address {
+    city: String,
+    zip: Option<String>,
+}
+
+gen_table!(
+    #[derive(Debug, PartialEq)]
+    struct Customer("json_customers") {
+        name: String = Type::TEXT; [primary_key()],
+        address: Json<Address> = Type::JSONB,
+        extra: Value = Type::JSONB; [default_value(json!({}))],
+    }
+);
+
+fn main() {
+    let db_url = std::env::var("DATABASE_URL").unwrap();
+    let mut client = Client::connect(&db_url, postgres::NoTls).unwrap();
+
+    client.create_table::<Customer, 3>().unwrap();
+
+    let customer = Customer {
+        name: "ann".into(),
+        address: Json(Address {
+            city: "Lisbon".into(),
+            zip: None,
+        }),
+        extra: json!({"vip": true}),
+    };
+    client.insert_row(&customer).unwrap();
+    println!("Customer inserted!");
+
+    let items: Vec<Customer> = client.select_all().unwrap();
+    println!("{} customers selected!", items.len());
+    assert_eq!(items[0], customer);
+
+    client.batch_execute("DROP TABLE json_customers").unwrap();
+}
@@ -0,0 +1,23 @@
+use std::process::ExitCode;
+
+use pg_helper::{cli, gen_table, Table, TableDef};
+use postgres::types::Type;
+
+gen_table!(
+    struct Author("authors") {
+        id: i32 = Type::INT4; [primary_key()],
+        name: String = Type::TEXT; [unique()],
+    }
+);
+
+gen_table!(
+    struct Book("books") {
+        id: i32 = Type::INT4; [primary_key()],
+        author_id: i32 = Type::INT4; [foreign_key(Author::name(), "id"), index()],
+        title: String = Type::TEXT,
+    }
+);
+
+fn main() -> ExitCode {
+    cli::main(&[TableDef::of::<Author, 2>(), TableDef::of::<Book, 3>()])
+}
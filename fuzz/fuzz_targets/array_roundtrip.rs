@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pg_helper::testing::codec_roundtrip;
+use postgres_types::Type;
+
+fuzz_target!(|data: (Vec<Option<i32>>, Vec<Option<String>>, Vec<i64>)| {
+    let (ints, texts, cents) = data;
+    assert_eq!(codec_roundtrip(&ints, &Type::INT4_ARRAY).unwrap(), ints);
+    assert_eq!(codec_roundtrip(&texts, &Type::TEXT_ARRAY).unwrap(), texts);
+
+    let cents: Vec<_> = cents.into_iter().map(pg_helper::Cents).collect();
+    assert_eq!(
+        codec_roundtrip(&cents, &Type::NUMERIC_ARRAY).unwrap(),
+        cents
+    );
+});
@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pg_helper::{array_type, struct_type, Cents};
+use postgres_types::{FromSql, Type};
+
+#[allow(dead_code)] // only decoded
+#[derive(FromSql)]
+#[postgres(name = "fuzz_pair")]
+struct Pair {
+    amount: Cents,
+    label: Option<String>,
+}
+
+// decoding the malformed binary data should fail instead of panicking
+fuzz_target!(|data: &[u8]| {
+    let ty = struct_type(
+        "fuzz_pair",
+        &[("amount", Type::NUMERIC), ("label", Type::TEXT)],
+    );
+    let _ = Pair::from_sql(&ty, data);
+    let _ = Vec::<Option<Pair>>::from_sql(&array_type(ty), data);
+    let _ = Cents::from_sql(&Type::NUMERIC, data);
+});
@@ -0,0 +1,36 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use pg_helper::{array_type, struct_type, testing::codec_roundtrip};
+use postgres_types::{FromSql, ToSql, Type};
+
+#[derive(Debug, Clone, PartialEq, Arbitrary, ToSql, FromSql)]
+#[postgres(name = "fuzz_item")]
+struct Item {
+    id: i64,
+    name: String,
+    tags: Option<Vec<Option<String>>>,
+    score: Option<f64>,
+}
+
+fuzz_target!(|items: Vec<Option<Item>>| {
+    let ty = struct_type(
+        "fuzz_item",
+        &[
+            ("id", Type::INT8),
+            ("name", Type::TEXT),
+            ("tags", Type::TEXT_ARRAY),
+            ("score", Type::FLOAT8),
+        ],
+    );
+    // NaN is not equal to itself
+    if items
+        .iter()
+        .flatten()
+        .any(|item| item.score.map_or(false, f64::is_nan))
+    {
+        return;
+    }
+    assert_eq!(codec_roundtrip(&items, &array_type(ty)).unwrap(), items);
+});
@@ -0,0 +1,401 @@
+//! Generic JSON CRUD endpoints for any [`Table`] (enabled with the `axum` feature).
+//!
+//! ```ignore
+//! let app = Router::new().nest("/notes", admin::router::<Note, i32, 2>(pool));
+//! ```
+//!
+//! | Method   | Path    | Action                                   |
+//! |----------|---------|------------------------------------------|
+//! | `GET`    | `/`     | list the rows (`?limit=&offset=`)        |
+//! | `POST`   | `/`     | insert the row and return it             |
+//! | `GET`    | `/:key` | get the row by its primary key           |
+//! | `PUT`    | `/:key` | replace all the columns of the row       |
+//! | `DELETE` | `/:key` | delete the row                           |
+
+use std::fmt;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use deadpool_postgres::{Pool, PoolError};
+use itertools::Itertools as _;
+use log::debug;
+use postgres_types::ToSql;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio_postgres::{Error as DbError, Row};
+
+use crate::{
+    cache::{self, RowChange},
+    error::Error,
+    ext::insert_returning_sql,
+    ext_async::PgTableExtension as _,
+    query::{Order, SelectOptions},
+    table::{self, FieldValue, InsertableValues, Table},
+};
+
+/// Build the router serving the rows of the table `T` with the primary key of the type `K`.
+///
+/// # Panics
+///
+/// If the table has no primary key column or the primary key is composite.
+pub fn router<T, K, const N: usize>(pool: Pool) -> Router
+where
+    T: Table<N>
+        + InsertableValues<N>
+        + TryFrom<Row, Error = DbError>
+        + Serialize
+        + DeserializeOwned
+        + Send
+        + Sync
+        + 'static,
+    K: DeserializeOwned + ToSql + Send + Sync + 'static,
+{
+    let _ = primary_key::<T, N>();
+    Router::new()
+        .route("/", get(list::<T, N>).post(create::<T, N>))
+        .route(
+            "/:key",
+            get(retrieve::<T, K, N>)
+                .put(update::<T, K, N>)
+                .delete(delete::<T, K, N>),
+        )
+        .with_state(pool)
+}
+
+/// The failure of the admin endpoint.
+#[derive(Debug)]
+pub enum AdminError {
+    NotFound,
+    Pool(PoolError),
+    Db(Error),
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Not found"),
+            Self::Pool(err) => write!(f, "No connection available: {}", err),
+            Self::Db(err) => write!(f, "Database error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AdminError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::NotFound => None,
+            Self::Pool(err) => Some(err),
+            Self::Db(err) => Some(err),
+        }
+    }
+}
+
+impl From<PoolError> for AdminError {
+    fn from(err: PoolError) -> Self {
+        Self::Pool(err)
+    }
+}
+
+impl From<Error> for AdminError {
+    fn from(err: Error) -> Self {
+        Self::Db(err)
+    }
+}
+
+impl From<DbError> for AdminError {
+    fn from(err: DbError) -> Self {
+        Self::Db(err.into())
+    }
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Pool(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Db(ref err) => err.status_code(),
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Page {
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+fn primary_key<T, const N: usize>() -> String
+where
+    T: Table<N>,
+{
+    let columns = T::columns();
+    let mut keys = columns.iter().filter(|col| col.is_primary_key());
+    let (Some(key), None) = (keys.next(), keys.next()) else {
+        panic!(
+            "Table {} should have a single primary key column",
+            T::name()
+        );
+    };
+    key.name().to_owned()
+}
+
+/// The cache key of the row with the primary `key`.
+fn cache_key<T, const N: usize>(key: &dyn ToSql) -> String
+where
+    T: Table<N>,
+{
+    let key: Vec<_> = cache::key_columns(&T::columns())
+        .iter()
+        .map(|(_, ty)| cache::value_text(ty, key))
+        .collect();
+    cache::cache_key(T::name(), &key)
+}
+
+fn update_sql<T, const N: usize>() -> String
+where
+    T: Table<N>,
+{
+    let assignments = T::columns()
+        .iter()
+        .enumerate()
+        .map(|(i, col)| format!("{} = ${}", col.name(), i + 1))
+        .join(", ");
+    format!(
+        "UPDATE {} SET {} WHERE {} = ${} RETURNING *",
+        T::name(),
+        assignments,
+        primary_key::<T, N>(),
+        N + 1
+    )
+}
+
+async fn list<T, const N: usize>(
+    State(pool): State<Pool>,
+    Query(page): Query<Page>,
+) -> Result<Json<Vec<T>>, AdminError>
+where
+    T: Table<N> + TryFrom<Row, Error = DbError> + Serialize + Send,
+{
+    let mut options = SelectOptions::new().order_by(primary_key::<T, N>(), Order::Asc);
+    if let Some(limit) = page.limit {
+        options = options.limit(limit);
+    }
+    if let Some(offset) = page.offset {
+        options = options.offset(offset);
+    }
+    let client = pool.get().await?;
+    let rows = client
+        .select_with_options::<T, _, N>(None::<String>, &[], &options)
+        .await?;
+    Ok(Json(rows))
+}
+
+async fn retrieve<T, K, const N: usize>(
+    State(pool): State<Pool>,
+    Path(key): Path<K>,
+) -> Result<Json<T>, AdminError>
+where
+    T: Table<N> + TryFrom<Row, Error = DbError> + Serialize + Send,
+    K: ToSql + Sync,
+{
+    let condition = format!("{} = $1", primary_key::<T, N>());
+    let client = pool.get().await?;
+    let rows = client.select::<T, _, N>(condition, &[&key]).await?;
+    rows.into_iter()
+        .next()
+        .map(Json)
+        .ok_or(AdminError::NotFound)
+}
+
+async fn create<T, const N: usize>(
+    State(pool): State<Pool>,
+    Json(row): Json<T>,
+) -> Result<(StatusCode, Json<T>), AdminError>
+where
+    T: Table<N> + InsertableValues<N> + TryFrom<Row, Error = DbError> + Serialize + Sync,
+{
+    let query = insert_returning_sql::<T, N>();
+    debug!("Admin INSERT for table {}: {}", T::name(), query);
+    let client = pool.get().await?;
+    let values = row.encoded_values();
+    let inserted = client.query_one(&query, &table::params(&values)).await?;
+    let inserted = T::try_from(inserted)?;
+    cache::notify_inserted([&inserted]);
+    Ok((StatusCode::CREATED, Json(inserted)))
+}
+
+async fn update<T, K, const N: usize>(
+    State(pool): State<Pool>,
+    Path(key): Path<K>,
+    Json(row): Json<T>,
+) -> Result<Json<T>, AdminError>
+where
+    T: Table<N> + InsertableValues<N> + TryFrom<Row, Error = DbError> + Serialize + Sync,
+    K: ToSql + Sync,
+{
+    let query = update_sql::<T, N>();
+    debug!("Admin UPDATE for table {}: {}", T::name(), query);
+    let values = row.encoded_values();
+    let params: Vec<_> = values
+        .iter()
+        .map(FieldValue::as_sql)
+        .chain([&key as &(dyn ToSql + Sync)])
+        .collect();
+    let client = pool.get().await?;
+    let updated = client.query_opt(&query, &params).await?;
+    let updated = updated.ok_or(AdminError::NotFound)?;
+    cache::notify(RowChange::Update, &cache_key::<T, N>(&key));
+    Ok(Json(T::try_from(updated)?))
+}
+
+async fn delete<T, K, const N: usize>(
+    State(pool): State<Pool>,
+    Path(key): Path<K>,
+) -> Result<StatusCode, AdminError>
+where
+    T: Table<N>,
+    K: ToSql + Sync,
+{
+    let query = format!(
+        "DELETE FROM {} WHERE {} = $1",
+        T::name(),
+        primary_key::<T, N>()
+    );
+    debug!("Admin DELETE for table {}: {}", T::name(), query);
+    let client = pool.get().await?;
+    match client.execute(&query, &[&key]).await? {
+        0 => Err(AdminError::NotFound),
+        _ => {
+            cache::notify(RowChange::Delete, &cache_key::<T, N>(&key));
+            Ok(StatusCode::NO_CONTENT)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Request},
+    };
+    use postgres_types::Type;
+    use serde_json::{json, Value};
+    use tower::ServiceExt as _;
+
+    use super::*;
+    use crate::{gen_table, pool::create_pool, test_helpers::db_url};
+
+    gen_table!(
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Note("admin_notes") {
+            id: i32 = Type::INT4; [primary_key()],
+            text: String = Type::TEXT,
+            pinned: Option<bool> = Type::BOOL; [nullable()],
+        }
+    );
+
+    #[test]
+    fn update_statement() {
+        assert_eq!(
+            update_sql::<Note, 3>(),
+            "UPDATE admin_notes SET id = $1, text = $2, pinned = $3 WHERE id = $4 RETURNING *"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Table admin_pairs should have a single primary key column")]
+    fn composite_key() {
+        gen_table!(
+            #[derive(Debug, Serialize, Deserialize)]
+            struct Pair("admin_pairs") {
+                a: i32 = Type::INT4; [primary_key()],
+                b: i32 = Type::INT4; [primary_key()],
+            }
+        );
+
+        let pool = create_pool("postgresql://localhost/admin", 1).unwrap();
+        let _ = router::<Pair, i32, 2>(pool);
+    }
+
+    async fn call(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder().method(method).uri(uri);
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        };
+        let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn crud() {
+        let Some(db_url) = db_url() else {
+            return;
+        };
+        let pool = create_pool(&db_url, 2).unwrap();
+        {
+            let client = pool.get().await.unwrap();
+            client
+                .batch_execute("DROP TABLE IF EXISTS admin_notes")
+                .await
+                .unwrap();
+            client.create_table::<Note, 3>().await.unwrap();
+        }
+        let app = Router::new().nest("/notes", router::<Note, i32, 3>(pool.clone()));
+
+        let note = json!({"id": 1, "text": "buy milk", "pinned": null});
+        assert_eq!(
+            call(&app, "POST", "/notes", Some(note.clone())).await,
+            (StatusCode::CREATED, note)
+        );
+        let second = json!({"id": 2, "text": "call mom", "pinned": true});
+        let _ = call(&app, "POST", "/notes", Some(second.clone())).await;
+
+        let (status, listed) = call(&app, "GET", "/notes?limit=1&offset=1", None).await;
+        assert_eq!((status, listed), (StatusCode::OK, json!([second])));
+
+        let updated = json!({"id": 1, "text": "buy oat milk", "pinned": false});
+        assert_eq!(
+            call(&app, "PUT", "/notes/1", Some(updated.clone())).await,
+            (StatusCode::OK, updated.clone())
+        );
+        assert_eq!(
+            call(&app, "GET", "/notes/1", None).await,
+            (StatusCode::OK, updated)
+        );
+
+        assert_eq!(
+            call(&app, "DELETE", "/notes/1", None).await.0,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            call(&app, "GET", "/notes/1", None).await.0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            call(&app, "DELETE", "/notes/1", None).await.0,
+            StatusCode::NOT_FOUND
+        );
+
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute("DROP TABLE admin_notes")
+            .await
+            .unwrap();
+    }
+}
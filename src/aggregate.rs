@@ -0,0 +1,289 @@
+use std::fmt::{self, Write as _};
+
+use crate::{error::Error, expr::Expr, table::Table};
+
+/// Grouped _SELECT_ returning the grouping columns followed by the aggregates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupBy {
+    columns: Vec<String>,
+    aggregates: Vec<Expr>,
+    condition: Option<Expr>,
+    having: Option<Expr>,
+}
+
+impl GroupBy {
+    pub fn new(columns: &[impl AsRef<str>]) -> Self {
+        Self {
+            columns: columns.iter().map(|c| c.as_ref().to_owned()).collect(),
+            aggregates: vec![],
+            condition: None,
+            having: None,
+        }
+    }
+
+    pub fn aggregate(mut self, aggregate: Expr) -> Self {
+        self.aggregates.push(aggregate);
+        self
+    }
+
+    /// The _WHERE_ condition applied before the grouping.
+    pub fn filter(mut self, condition: Expr) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// The condition applied to the groups.
+    pub fn having(mut self, condition: Expr) -> Self {
+        self.having = Some(condition);
+        self
+    }
+
+    /// Fails if any of the grouping columns or the expressions refers to unknown columns.
+    pub fn select_sql<T, const N: usize>(&self) -> Result<String, Error>
+    where
+        T: Table<N>,
+    {
+        let columns = T::columns();
+        let select_list = self
+            .columns
+            .iter()
+            .map(Expr::col)
+            .chain(self.aggregates.iter().cloned())
+            .map(|expr| expr.to_sql(&columns))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut query = format!("SELECT {} FROM {}", select_list.join(", "), T::name());
+        if let Some(condition) = &self.condition {
+            write!(query, " WHERE {}", condition.to_sql(&columns)?).unwrap();
+        }
+        if !self.columns.is_empty() {
+            write!(query, " GROUP BY {}", self.columns.join(", ")).unwrap();
+        }
+        if let Some(having) = &self.having {
+            write!(query, " HAVING {}", having.to_sql(&columns)?).unwrap();
+        }
+        Ok(query)
+    }
+}
+
+/// The width of the time bucket in the [`TimeBuckets`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bucket {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Bucket {
+    const fn unit(self) -> &'static str {
+        match self {
+            Self::Minute => "minute",
+            Self::Hour => "hour",
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Year => "year",
+        }
+    }
+
+    fn truncate(self, expr: impl fmt::Display) -> String {
+        format!("date_trunc('{}', {})", self.unit(), expr)
+    }
+}
+
+/// Aggregates over the rows grouped into the time buckets
+/// (`date_trunc` of the timestamp column) returning the bucket start
+/// followed by the aggregates in the chronological order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeBuckets {
+    column: String,
+    bucket: Bucket,
+    aggregates: Vec<Expr>,
+    condition: Option<Expr>,
+    gaps_range: Option<(Expr, Expr)>,
+}
+
+impl TimeBuckets {
+    pub fn new(ts_column: impl AsRef<str>, bucket: Bucket) -> Self {
+        Self {
+            column: ts_column.as_ref().to_owned(),
+            bucket,
+            aggregates: vec![],
+            condition: None,
+            gaps_range: None,
+        }
+    }
+
+    pub fn aggregate(mut self, aggregate: Expr) -> Self {
+        self.aggregates.push(aggregate);
+        self
+    }
+
+    /// The _WHERE_ condition applied before the grouping.
+    pub fn filter(mut self, condition: Expr) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Return all the buckets between `from` and `to` (inclusive)
+    /// even if they have no rows.
+    ///
+    /// The aggregates of the empty buckets are computed over a single row of `NULL`s,
+    /// so use the `count(column)` instead of the `count(*)`.
+    pub fn fill_gaps(mut self, from: Expr, to: Expr) -> Self {
+        self.gaps_range = Some((from, to));
+        self
+    }
+
+    /// Fails if the timestamp column or any of the expressions refers to unknown columns.
+    pub fn select_sql<T, const N: usize>(&self) -> Result<String, Error>
+    where
+        T: Table<N>,
+    {
+        let columns = T::columns();
+        let ts_type = columns
+            .iter()
+            .find(|col| col.name() == self.column)
+            .map(|col| col.db_type().clone())
+            .ok_or_else(|| Error::UnknownColumn {
+                column: self.column.clone(),
+                purpose: "bucket by",
+            })?;
+        let mut aggregates = String::new();
+        for expr in &self.aggregates {
+            write!(aggregates, ", {}", expr.to_sql(&columns)?).unwrap();
+        }
+        let bucket = self.bucket.truncate(&self.column);
+
+        let mut query = if let Some((from, to)) = &self.gaps_range {
+            let series = format!(
+                "generate_series({}, {}::{}, interval '1 {}')",
+                self.bucket
+                    .truncate(format!("{}::{}", from.to_sql(&columns)?, ts_type.name())),
+                to.to_sql(&columns)?,
+                ts_type.name(),
+                self.bucket.unit()
+            );
+            let mut query = format!(
+                "SELECT buckets.bucket{} FROM {} AS buckets(bucket) LEFT JOIN {} ON {} = buckets.bucket",
+                aggregates,
+                series,
+                T::name(),
+                bucket
+            );
+            if let Some(condition) = &self.condition {
+                write!(query, " AND ({})", condition.to_sql(&columns)?).unwrap();
+            }
+            query
+        } else {
+            let mut query = format!("SELECT {}{} FROM {}", bucket, aggregates, T::name());
+            if let Some(condition) = &self.condition {
+                write!(query, " WHERE {}", condition.to_sql(&columns)?).unwrap();
+            }
+            query
+        };
+        query.push_str(" GROUP BY 1 ORDER BY 1");
+        Ok(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gen_table, param};
+
+    use postgres_types::Type;
+
+    gen_table!(
+        struct Buy("buys") {
+            buy_id: i32 = Type::INT4; [primary_key()],
+            customer_id: i32 = Type::INT4,
+            total_price: f32 = Type::FLOAT4,
+        }
+    );
+
+    #[test]
+    fn group_with_having() {
+        let group = GroupBy::new(&["customer_id"])
+            .aggregate(Expr::count_all())
+            .aggregate(Expr::col("total_price").sum())
+            .filter(Expr::col("total_price").gt(param(1)))
+            .having(Expr::count_all().ge(param(2)));
+        assert_eq!(
+            group.select_sql::<Buy, 3>().unwrap(),
+            "SELECT customer_id, count(*), sum(total_price) FROM buys \
+            WHERE total_price > $1 GROUP BY customer_id HAVING count(*) >= $2"
+        );
+    }
+
+    #[test]
+    fn aggregate_without_groups() {
+        let group = GroupBy::new(&[] as &[&str]).aggregate(Expr::col("total_price").avg());
+        assert_eq!(
+            group.select_sql::<Buy, 3>().unwrap(),
+            "SELECT avg(total_price) FROM buys"
+        );
+    }
+
+    gen_table!(
+        struct Visit("visits") {
+            page: String = Type::TEXT,
+            visited_at: std::time::SystemTime = Type::TIMESTAMPTZ,
+            duration: Option<f64> = Type::FLOAT8; [nullable()],
+        }
+    );
+
+    #[test]
+    fn hourly_buckets() {
+        let buckets = TimeBuckets::new("visited_at", Bucket::Hour)
+            .aggregate(Expr::count_all())
+            .aggregate(Expr::col("duration").avg())
+            .filter(Expr::col("page").eq(param(1)));
+        assert_eq!(
+            buckets.select_sql::<Visit, 3>().unwrap(),
+            "SELECT date_trunc('hour', visited_at), count(*), avg(duration) FROM visits \
+            WHERE page = $1 GROUP BY 1 ORDER BY 1"
+        );
+    }
+
+    #[test]
+    fn fill_gaps() {
+        let buckets = TimeBuckets::new("visited_at", Bucket::Day)
+            .aggregate(Expr::col("page").count())
+            .filter(Expr::col("page").like(param(3)))
+            .fill_gaps(param(1), param(2));
+        assert_eq!(
+            buckets.select_sql::<Visit, 3>().unwrap(),
+            "SELECT buckets.bucket, count(page) FROM generate_series(\
+            date_trunc('day', $1::timestamptz), $2::timestamptz, interval '1 day'\
+            ) AS buckets(bucket) \
+            LEFT JOIN visits ON date_trunc('day', visited_at) = buckets.bucket \
+            AND (page LIKE $3) GROUP BY 1 ORDER BY 1"
+        );
+    }
+
+    #[test]
+    fn unknown_timestamp_column() {
+        let err = TimeBuckets::new("created_at", Bucket::Day)
+            .select_sql::<Visit, 3>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown column to bucket by: \"created_at\""
+        );
+    }
+
+    #[test]
+    fn unknown_group_column() {
+        let err = GroupBy::new(&["customer"])
+            .select_sql::<Buy, 3>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown column to use in the expression: \"customer\""
+        );
+    }
+}
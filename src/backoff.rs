@@ -0,0 +1,198 @@
+use std::time::Duration;
+
+use postgres_types::Type;
+
+use crate::{
+    column::{Column, ColumnBuilder},
+    error::Error,
+    expr::Expr,
+    query::check_column,
+    table::Table,
+};
+
+/// Exponential backoff for the job/queue tables
+/// storing the number of attempts and the time of the next one.
+///
+/// The delay is computed on the server side as `base * 2^attempts`
+/// (optionally limited with the `max_delay`).
+/// The exponent stops growing after the [`MAX_EXPONENT`][Self::MAX_EXPONENT] attempts
+/// to keep the delay within the `interval` range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backoff {
+    retry_at: String,
+    attempts: String,
+    base: Duration,
+    max: Option<Duration>,
+}
+
+impl Backoff {
+    pub const MAX_EXPONENT: u32 = 30;
+
+    pub fn new(retry_at: impl AsRef<str>, attempts: impl AsRef<str>, base: Duration) -> Self {
+        Self {
+            retry_at: retry_at.as_ref().to_owned(),
+            attempts: attempts.as_ref().to_owned(),
+            base,
+            max: None,
+        }
+    }
+
+    pub const fn max_delay(mut self, max: Duration) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Definitions of the columns to include into the table:
+    /// nullable `timestamptz` for the next attempt time and `int4` for the attempts counter.
+    pub fn columns(&self) -> [Column; 2] {
+        [
+            ColumnBuilder::new(&self.retry_at, Type::TIMESTAMPTZ)
+                .nullable()
+                .index()
+                .finish(),
+            ColumnBuilder::new(&self.attempts, Type::INT4).finish(),
+        ]
+    }
+
+    fn delay_sql(&self) -> String {
+        let secs = format!(
+            "{} * power(2, LEAST({}, {}))",
+            self.base.as_secs_f64(),
+            self.attempts,
+            Self::MAX_EXPONENT
+        );
+        let secs = if let Some(max) = self.max {
+            // limit the seconds before the conversion to not overflow the interval
+            format!("LEAST({}, {})", secs, max.as_secs_f64())
+        } else {
+            secs
+        };
+        format!("make_interval(secs => {})", secs)
+    }
+
+    /// Condition to select the rows which are ready to be retried.
+    pub fn due(&self) -> Expr {
+        Expr::col(&self.retry_at).le(Expr::raw("now()"))
+    }
+
+    /// _UPDATE_ statement incrementing the attempts and scheduling the next one
+    /// for all the rows matching the `condition`.
+    ///
+    /// Fails if the table has no backoff columns or the `condition` refers to unknown columns.
+    pub fn schedule_retry_sql<T, const N: usize>(&self, condition: &Expr) -> Result<String, Error>
+    where
+        T: Table<N>,
+    {
+        let columns = T::columns();
+        for name in [&self.retry_at, &self.attempts] {
+            check_column(&columns, name, "store the backoff in")?;
+        }
+
+        Ok(format!(
+            "UPDATE {} SET {} = {} + 1, {} = now() + {} WHERE {}",
+            T::name(),
+            self.attempts,
+            self.attempts,
+            self.retry_at,
+            self.delay_sql(),
+            condition.to_sql(&columns)?
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gen_table, param, test_helpers::get_client};
+
+    gen_table!(
+        struct Job("jobs") {
+            id: i32 = Type::INT4; [primary_key()],
+            payload: String = Type::TEXT,
+        }
+    );
+
+    struct JobWithRetries;
+
+    impl Table<4> for JobWithRetries {
+        fn name() -> &'static str {
+            Job::name()
+        }
+
+        fn columns() -> [Column; 4] {
+            let [id, payload] = Job::columns();
+            let [retry_at, attempts] =
+                Backoff::new("retry_at", "attempts", Duration::ZERO).columns();
+            [id, payload, retry_at, attempts]
+        }
+    }
+
+    fn backoff() -> Backoff {
+        Backoff::new("retry_at", "attempts", Duration::from_millis(1500))
+            .max_delay(Duration::from_secs(3600))
+    }
+
+    #[test]
+    fn schedule_retry() {
+        assert_eq!(
+            backoff()
+                .schedule_retry_sql::<JobWithRetries, 4>(&Expr::col("id").eq(param(1)))
+                .unwrap(),
+            "UPDATE jobs SET attempts = attempts + 1, retry_at = now() + \
+            make_interval(secs => LEAST(1.5 * power(2, LEAST(attempts, 30)), 3600)) \
+            WHERE id = $1"
+        );
+        assert_eq!(backoff().due().to_string(), "retry_at <= now()");
+    }
+
+    #[test]
+    fn many_attempts() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS jobs; \
+                CREATE TABLE jobs (id int4, payload text, retry_at timestamptz, attempts int4); \
+                INSERT INTO jobs VALUES (1, 'capped', NULL, 50), (2, 'unlimited', NULL, 100000)",
+            )
+            .unwrap();
+
+        // the same `now()` within the transaction
+        let mut tx = client.transaction().unwrap();
+        let capped = backoff()
+            .schedule_retry_sql::<JobWithRetries, 4>(&Expr::col("id").eq(param(1)))
+            .unwrap();
+        tx.execute(&capped, &[&1_i32]).unwrap();
+        let unlimited = Backoff::new("retry_at", "attempts", Duration::from_secs(1))
+            .schedule_retry_sql::<JobWithRetries, 4>(&Expr::col("id").eq(param(1)))
+            .unwrap();
+        tx.execute(&unlimited, &[&2_i32]).unwrap();
+
+        let delays: Vec<f64> = tx
+            .query(
+                "SELECT EXTRACT(EPOCH FROM retry_at - now())::float8 FROM jobs ORDER BY id",
+                &[],
+            )
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(delays, [3600.0, 2_f64.powi(30)]);
+        drop(tx);
+
+        client.batch_execute("DROP TABLE jobs").unwrap();
+    }
+
+    #[test]
+    fn no_backoff_columns() {
+        let backoff = Backoff::new("retry_at", "attempts", Duration::from_secs(1));
+        let err = backoff
+            .schedule_retry_sql::<Job, 2>(&Expr::col("id").eq(param(1)))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown column to store the backoff in: \"retry_at\""
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+
+use itertools::Itertools as _;
+use postgres::Row;
+
+use crate::{
+    cache::{self, RawValue, RowChange},
+    table::Table,
+};
+
+/// The number of the rows the batch operation is going to change
+/// (see the `plan_*` extensions).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct BatchPlan {
+    to_delete: u64,
+}
+
+impl BatchPlan {
+    pub(crate) const fn deleting(rows: u64) -> Self {
+        Self { to_delete: rows }
+    }
+
+    pub const fn to_delete(&self) -> u64 {
+        self.to_delete
+    }
+
+    /// Nothing is going to change.
+    pub const fn is_empty(&self) -> bool {
+        self.to_delete == 0
+    }
+}
+
+impl fmt::Display for BatchPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "to delete: {}", self.to_delete)
+    }
+}
+
+pub(crate) fn count_sql<T, const N: usize>(condition: &str) -> String
+where
+    T: Table<N>,
+{
+    format!("SELECT count(*) FROM {} WHERE {}", T::name(), condition)
+}
+
+/// Delete at most `chunk_size` rows matching the condition
+/// returning the primary keys of the deleted rows (if any).
+///
+/// The rows are identified by the `ctid` together with the `tableoid`,
+/// because the `ctid` is only unique within a single partition.
+pub(crate) fn delete_chunk_sql<T, const N: usize>(condition: &str, chunk_size: usize) -> String
+where
+    T: Table<N>,
+{
+    let keys = T::columns()
+        .iter()
+        .filter(|col| col.is_primary_key())
+        .map(|col| col.name())
+        .join(", ");
+    let returning = if keys.is_empty() { "1".into() } else { keys };
+    format!(
+        "DELETE FROM {0} WHERE (tableoid, ctid) IN \
+        (SELECT tableoid, ctid FROM {0} WHERE {1} LIMIT {2}) RETURNING {3}",
+        T::name(),
+        condition,
+        chunk_size,
+        returning
+    )
+}
+
+/// Report the deleted rows having the primary key.
+pub(crate) fn notify_deleted<T, const N: usize>(rows: &[Row])
+where
+    T: Table<N>,
+{
+    if !T::columns().iter().any(|col| col.is_primary_key()) || !cache::has_hook() {
+        return;
+    }
+    for row in rows {
+        let key: Vec<_> = row
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(i, col)| row.get::<_, RawValue<'_>>(i).key_text(col.type_()))
+            .collect();
+        cache::notify(RowChange::Delete, &cache::cache_key(T::name(), &key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::gen_table;
+
+    gen_table!(
+        struct Log("batch_logs") {
+            id: i64 = Type::INT8; [primary_key()],
+            level: String = Type::TEXT,
+        }
+    );
+
+    #[test]
+    fn statements() {
+        assert_eq!(
+            delete_chunk_sql::<Log, 2>("level = $1", 100),
+            "DELETE FROM batch_logs WHERE (tableoid, ctid) IN \
+            (SELECT tableoid, ctid FROM batch_logs WHERE level = $1 LIMIT 100) RETURNING id"
+        );
+        assert_eq!(
+            count_sql::<Log, 2>("level = $1"),
+            "SELECT count(*) FROM batch_logs WHERE level = $1"
+        );
+        assert_eq!(BatchPlan::deleting(3).to_string(), "to delete: 3");
+    }
+}
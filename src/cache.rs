@@ -0,0 +1,554 @@
+use std::{error::Error, fmt::Write as _, sync::RwLock};
+
+use itertools::Itertools as _;
+use postgres_protocol::types as protocol;
+use postgres_types::{private::BytesMut, FromSql, IsNull, Kind, ToSql, Type};
+
+use crate::{
+    column::Column,
+    table::{FieldValue, InsertableValues},
+};
+
+static HOOK: RwLock<Option<RowChangeHook>> = RwLock::new(None);
+
+/// The kind of the modification of the row.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RowChange {
+    Insert,
+    /// Only reported by the admin endpoints of the `axum` feature
+    /// (the library has no other ways to update the rows).
+    Update,
+    Delete,
+}
+
+/// The callback invoked with the [cache key][crate::InsertableValues::cache_key]
+/// of every row modified with the library, e.g. to invalidate the external caches.
+///
+/// The hook is invoked right after the statement succeeds,
+/// so the changes made in a transaction are reported before the commit.
+pub struct RowChangeHook(Box<HookFn>);
+
+type HookFn = dyn Fn(RowChange, &str) + Send + Sync;
+
+impl RowChangeHook {
+    pub fn new<F>(hook: F) -> Self
+    where
+        F: Fn(RowChange, &str) + Send + Sync + 'static,
+    {
+        Self(Box::new(hook))
+    }
+
+    /// Use the hook for all the tables from now on (replacing the previous one).
+    pub fn install(self) {
+        *HOOK.write().unwrap_or_else(|err| err.into_inner()) = Some(self);
+    }
+
+    pub fn uninstall() {
+        *HOOK.write().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+}
+
+/// The key of the row `table:value1[:value2...]` with the [texts][key_text] of the key values.
+pub(crate) fn cache_key(table: &str, values: &[String]) -> String {
+    format!("{}:{}", table, values.iter().format(":"))
+}
+
+/// The positions and the types of the primary key columns.
+pub(crate) fn key_columns(columns: &[Column]) -> Vec<(usize, Type)> {
+    columns
+        .iter()
+        .enumerate()
+        .filter(|(_, col)| col.is_primary_key())
+        .map(|(i, col)| (i, col.db_type().clone()))
+        .collect()
+}
+
+/// The key of the row from its values.
+pub(crate) fn values_key(
+    table: &str,
+    key_columns: &[(usize, Type)],
+    values: &[FieldValue<'_>],
+) -> String {
+    let key: Vec<_> = key_columns
+        .iter()
+        .map(|(i, ty)| value_text(ty, &values[*i]))
+        .collect();
+    cache_key(table, &key)
+}
+
+/// The text of the value the same as the one of the value read from the database
+/// (see the [`key_text`]), or its `Debug` if the value cannot be encoded as the `ty`.
+pub(crate) fn value_text(ty: &Type, value: &dyn ToSql) -> String {
+    let mut raw = BytesMut::new();
+    match value.to_sql_checked(ty, &mut raw) {
+        Ok(IsNull::No) => key_text(ty, Some(&raw)),
+        Ok(IsNull::Yes) => key_text(ty, None),
+        Err(_) => format!("{:?}", value),
+    }
+}
+
+/// The undecoded value of any type.
+pub(crate) struct RawValue<'a>(Option<&'a [u8]>);
+
+impl<'a> RawValue<'a> {
+    /// The text of the value (see the [`key_text`]).
+    pub(crate) fn key_text(&self, ty: &Type) -> String {
+        key_text(ty, self.0)
+    }
+}
+
+impl<'a> FromSql<'a> for RawValue<'a> {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self(Some(raw)))
+    }
+
+    fn from_sql_null(_: &Type) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self(None))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// The text of the key value decoded from its binary form
+/// (so the keys of the Rust values and of the rows read from the database are the same):
+/// the same as the Postgres text output for the numbers, the strings, the UUIDs and the
+/// date and time values, or the hex of the binary form for the other types.
+pub(crate) fn key_text(ty: &Type, raw: Option<&[u8]>) -> String {
+    match raw {
+        Some(raw) => decode_key(ty, raw).unwrap_or_else(|_| hex_text(raw)),
+        None => "NULL".into(),
+    }
+}
+
+type DecodeResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+fn decode_key(ty: &Type, raw: &[u8]) -> DecodeResult<String> {
+    let text = match ty {
+        _ if *ty == Type::BOOL => protocol::bool_from_sql(raw)?.to_string(),
+        _ if *ty == Type::INT2 => protocol::int2_from_sql(raw)?.to_string(),
+        _ if *ty == Type::INT4 => protocol::int4_from_sql(raw)?.to_string(),
+        _ if *ty == Type::INT8 => protocol::int8_from_sql(raw)?.to_string(),
+        _ if *ty == Type::FLOAT4 => protocol::float4_from_sql(raw)?.to_string(),
+        _ if *ty == Type::FLOAT8 => protocol::float8_from_sql(raw)?.to_string(),
+        _ if *ty == Type::NUMERIC => numeric_text(raw)?,
+        _ if [Type::TEXT, Type::VARCHAR, Type::BPCHAR, Type::NAME].contains(ty)
+            || matches!(ty.kind(), Kind::Enum(_)) =>
+        {
+            protocol::text_from_sql(raw)?.to_owned()
+        }
+        _ if *ty == Type::UUID => {
+            let uuid = hex_text(&protocol::uuid_from_sql(raw)?);
+            format!(
+                "{}-{}-{}-{}-{}",
+                &uuid[..8],
+                &uuid[8..12],
+                &uuid[12..16],
+                &uuid[16..20],
+                &uuid[20..]
+            )
+        }
+        _ if *ty == Type::DATE => match protocol::date_from_sql(raw)? {
+            i32::MAX => "infinity".into(),
+            i32::MIN => "-infinity".into(),
+            days => date_text(i64::from(days)),
+        },
+        _ if *ty == Type::TIMESTAMP || *ty == Type::TIMESTAMPTZ => {
+            let zone = if *ty == Type::TIMESTAMPTZ { "+00" } else { "" };
+            match protocol::timestamp_from_sql(raw)? {
+                i64::MAX => "infinity".into(),
+                i64::MIN => "-infinity".into(),
+                micros => {
+                    let (days, time) = (micros.div_euclid(DAY), micros.rem_euclid(DAY));
+                    format!("{} {}{}", date_text(days), time_text(time), zone)
+                }
+            }
+        }
+        _ if *ty == Type::TIME => time_text(protocol::time_from_sql(raw)?),
+        _ => hex_text(raw),
+    };
+    Ok(text)
+}
+
+fn hex_text(raw: &[u8]) -> String {
+    raw.iter().fold(String::new(), |mut out, byte| {
+        write!(out, "{:02x}", byte).unwrap();
+        out
+    })
+}
+
+/// The microseconds in a day.
+const DAY: i64 = 86_400_000_000;
+
+/// The date of the number of days since the Postgres epoch (2000-01-01).
+fn date_text(days: i64) -> String {
+    // the civil date from the days since 0000-03-01
+    let days = days + 730_425;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The time of the number of microseconds since the midnight.
+fn time_text(micros: i64) -> String {
+    let seconds = micros / 1_000_000;
+    let mut text = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    let fraction = micros % 1_000_000;
+    if fraction != 0 {
+        let fraction = format!("{:06}", fraction);
+        write!(text, ".{}", fraction.trim_end_matches('0')).unwrap();
+    }
+    text
+}
+
+/// The `numeric` without the trailing zeros of the fractional part
+/// (so it does not depend on the scale of the column).
+fn numeric_text(raw: &[u8]) -> DecodeResult<String> {
+    let header: Vec<_> = raw
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect();
+    let [count, weight, sign, _scale, ref digits @ ..] = header[..] else {
+        return Err("Invalid numeric: too short".into());
+    };
+    match sign {
+        0xC000 => return Ok("NaN".into()),
+        0xD000 => return Ok("Infinity".into()),
+        0xF000 => return Ok("-Infinity".into()),
+        _ => {}
+    }
+    if digits.len() != usize::from(count) {
+        return Err("Invalid numeric: wrong number of digits".into());
+    }
+    let weight = i32::from(weight as i16);
+    let digit = |i: i32| {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| digits.get(i).copied())
+            .unwrap_or(0)
+    };
+    let mut text = String::new();
+    if sign == 0x4000 {
+        text.push('-');
+    }
+    if weight < 0 {
+        text.push('0');
+    } else {
+        write!(text, "{}", digit(0)).unwrap();
+        for i in 1..=weight {
+            write!(text, "{:04}", digit(i)).unwrap();
+        }
+    }
+    let fraction: String = (weight + 1..i32::from(count))
+        .map(|i| format!("{:04}", digit(i)))
+        .collect();
+    let fraction = fraction.trim_end_matches('0');
+    if !fraction.is_empty() {
+        write!(text, ".{}", fraction).unwrap();
+    }
+    Ok(text)
+}
+
+pub(crate) fn has_hook() -> bool {
+    HOOK.read().unwrap_or_else(|err| err.into_inner()).is_some()
+}
+
+pub(crate) fn notify(change: RowChange, key: &str) {
+    let hook = HOOK.read().unwrap_or_else(|err| err.into_inner());
+    if let Some(hook) = hook.as_ref() {
+        (hook.0)(change, key);
+    }
+}
+
+/// Report the inserted rows having the primary key.
+pub(crate) fn notify_inserted<'a, T, I, const N: usize>(rows: I)
+where
+    T: InsertableValues<N> + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    if T::key_columns().is_empty() || !has_hook() {
+        return;
+    }
+    for row in rows {
+        if let Some(key) = row.cache_key() {
+            notify(RowChange::Insert, &key);
+        }
+    }
+}
+
+/// Report the inserted rows of the table given with their values
+/// (e.g. the rows of the tables whose name is only known at runtime).
+pub(crate) fn notify_inserted_values<'v, I, V>(table: &str, columns: &[Column], rows: I)
+where
+    I: IntoIterator<Item = V>,
+    V: AsRef<[FieldValue<'v>]>,
+{
+    if !has_hook() {
+        return;
+    }
+    let key_columns = key_columns(columns);
+    if key_columns.is_empty() {
+        return;
+    }
+    for values in rows {
+        notify(
+            RowChange::Insert,
+            &values_key(table, &key_columns, values.as_ref()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use postgres_types::Type;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        gen_table, test_helpers::get_client, BatchOptions, Cents, Expr, PgTableExtension as _,
+        Table, TableRef,
+    };
+
+    gen_table!(
+        struct Buy("cache_buys") {
+            id: i32 = Type::INT4; [primary_key()],
+            item: String = Type::TEXT,
+        }
+    );
+
+    gen_table!(
+        struct Visit("cache_visits") {
+            user_name: String = Type::TEXT; [primary_key()],
+            day: i32 = Type::INT4; [primary_key()],
+        }
+    );
+
+    gen_table!(
+        struct Note("cache_notes") {
+            title: String = Type::TEXT; [primary_key()],
+            day: i32 = Type::INT4,
+        }
+    );
+
+    gen_table!(
+        struct Event("cache_events") {
+            name: String = Type::TEXT,
+        }
+    );
+
+    #[test]
+    fn keys() {
+        let buy = Buy {
+            id: 42,
+            item: "milk".into(),
+        };
+        assert_eq!(buy.cache_key().as_deref(), Some("cache_buys:42"));
+        let visit = Visit {
+            user_name: "bob".into(),
+            day: 7,
+        };
+        assert_eq!(visit.cache_key().as_deref(), Some("cache_visits:bob:7"));
+        let event = Event {
+            name: "login".into(),
+        };
+        assert_eq!(event.cache_key(), None);
+    }
+
+    #[test]
+    fn hook_on_insert() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS cache_buys")
+            .unwrap();
+        client.create_table::<Buy, 2>().unwrap();
+
+        let changes = Arc::new(Mutex::new(vec![]));
+        let recorded = Arc::clone(&changes);
+        RowChangeHook::new(move |change, key| {
+            // the hook is global, so ignore the tables of the concurrent tests
+            if key.starts_with("cache_buys:") {
+                recorded.lock().unwrap().push((change, key.to_owned()));
+            }
+        })
+        .install();
+
+        let buys = [1, 2].map(|id| Buy {
+            id,
+            item: "bread".into(),
+        });
+        client.insert_rows(&buys).unwrap();
+        RowChangeHook::uninstall();
+        client
+            .insert_row(&Buy {
+                id: 3,
+                item: "tea".into(),
+            })
+            .unwrap();
+
+        assert_eq!(
+            *changes.lock().unwrap(),
+            [
+                (RowChange::Insert, "cache_buys:1".to_owned()),
+                (RowChange::Insert, "cache_buys:2".to_owned()),
+            ]
+        );
+        client.batch_execute("DROP TABLE cache_buys").unwrap();
+    }
+
+    #[test]
+    fn key_texts() {
+        let at = UNIX_EPOCH + Duration::from_micros(1_500_000);
+        assert_eq!(
+            value_text(&Type::TIMESTAMPTZ, &at),
+            "1970-01-01 00:00:01.5+00"
+        );
+        let leap = UNIX_EPOCH + Duration::from_secs(951_782_400 + 86_399);
+        assert_eq!(value_text(&Type::TIMESTAMP, &leap), "2000-02-29 23:59:59");
+        assert_eq!(value_text(&Type::TEXT, &"o\"neil"), "o\"neil");
+        assert_eq!(value_text(&Type::NUMERIC, &Cents(-1_000_050)), "-10000.5");
+        assert_eq!(value_text(&Type::NUMERIC, &Cents(7)), "0.07");
+        assert_eq!(
+            value_text(&Type::UUID, &Uuid::from_u128(0x0123_4567_89ab_cdef)),
+            "00000000-0000-0000-0123-456789abcdef"
+        );
+        assert_eq!(value_text(&Type::BYTEA, &vec![1_u8, 255]), "01ff");
+    }
+
+    gen_table!(
+        struct Stamp("cache_stamps") {
+            at: SystemTime = Type::TIMESTAMPTZ; [primary_key()],
+        }
+    );
+
+    gen_table!(
+        struct Price("cache_prices") {
+            amount: Cents = Type::NUMERIC; [precision(12, 4), primary_key()],
+        }
+    );
+
+    /// The keys reported on inserting the row and deleting it back.
+    fn insert_and_delete<T, const N: usize>(
+        client: &mut postgres::Client,
+        row: &T,
+    ) -> Vec<(RowChange, String)>
+    where
+        T: Table<N> + InsertableValues<N>,
+    {
+        client
+            .batch_execute(&format!("DROP TABLE IF EXISTS {}", T::name()))
+            .unwrap();
+        client.create_table::<T, N>().unwrap();
+
+        let changes = Arc::new(Mutex::new(vec![]));
+        let recorded = Arc::clone(&changes);
+        let prefix = format!("{}:", T::name());
+        RowChangeHook::new(move |change, key| {
+            // the hook is global, so ignore the tables of the concurrent tests
+            if key.starts_with(&prefix) {
+                recorded.lock().unwrap().push((change, key.to_owned()));
+            }
+        })
+        .install();
+        client.insert_row(row).unwrap();
+        client
+            .delete_batched::<T, N>(&Expr::raw("TRUE"), &[], &BatchOptions::new(10))
+            .unwrap();
+        RowChangeHook::uninstall();
+
+        client
+            .batch_execute(&format!("DROP TABLE {}", T::name()))
+            .unwrap();
+        let changes = changes.lock().unwrap();
+        changes.clone()
+    }
+
+    #[test]
+    fn same_keys_on_delete() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        let note = Note {
+            title: "o\"neil".into(),
+            day: 7,
+        };
+        let at = UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456);
+        let cases = [
+            (insert_and_delete(&mut client, &note), "cache_notes:o\"neil"),
+            (
+                insert_and_delete(&mut client, &Stamp { at }),
+                "cache_stamps:2023-11-14 22:13:20.123456+00",
+            ),
+            // the column scale is not the same as the one of the value
+            (
+                insert_and_delete(&mut client, &Price { amount: Cents(250) }),
+                "cache_prices:2.5",
+            ),
+        ];
+        for (changes, key) in cases {
+            assert_eq!(
+                changes,
+                [
+                    (RowChange::Insert, key.to_owned()),
+                    (RowChange::Delete, key.to_owned())
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn hook_on_dyn_insert() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        let tenant = TableRef::<Buy, 2>::suffixed("acme");
+        client
+            .batch_execute("DROP TABLE IF EXISTS cache_buys_acme")
+            .unwrap();
+        client.create_dyn_table(&tenant).unwrap();
+
+        let changes = Arc::new(Mutex::new(vec![]));
+        let recorded = Arc::clone(&changes);
+        RowChangeHook::new(move |change, key| {
+            if key.starts_with("cache_buys_acme:") {
+                recorded.lock().unwrap().push((change, key.to_owned()));
+            }
+        })
+        .install();
+        let buy = Buy {
+            id: 5,
+            item: "salt".into(),
+        };
+        client.insert_dyn_rows(&tenant, &[buy]).unwrap();
+        RowChangeHook::uninstall();
+
+        assert_eq!(
+            *changes.lock().unwrap(),
+            [(RowChange::Insert, "cache_buys_acme:5".to_owned())]
+        );
+        client.batch_execute("DROP TABLE cache_buys_acme").unwrap();
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use itertools::Itertools as _;
+
+use crate::{error::Error, query::check_column, table::Table};
+
+/// The order-independent digest of the table contents
+/// to compare the copies of the table in different databases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableChecksum {
+    rows: u64,
+    hash: String,
+}
+
+impl TableChecksum {
+    pub(crate) fn new(rows: i64, hash: String) -> Self {
+        Self {
+            rows: rows.try_into().unwrap_or_default(),
+            hash,
+        }
+    }
+
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// The sum of the 64-bit hashes of all the rows (as a decimal number).
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+impl fmt::Display for TableChecksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} rows, hash {}", self.rows, self.hash)
+    }
+}
+
+/// The rows are hashed with the `md5` of their text representation,
+/// so the same values give the same checksum in any physical order.
+pub(crate) fn checksum_sql<T, const N: usize>(columns: &[&str]) -> Result<String, Error>
+where
+    T: Table<N>,
+{
+    let defined = T::columns();
+    let columns = if columns.is_empty() {
+        defined.iter().map(|col| col.name()).join(", ")
+    } else {
+        for name in columns {
+            check_column(&defined, name, "use in the checksum")?;
+        }
+        columns.join(", ")
+    };
+    Ok(format!(
+        "SELECT count(*), coalesce(sum(\
+        ('x' || left(md5(ROW({})::text), 16))::bit(64)::int8::numeric\
+        ), 0)::text FROM {}",
+        columns,
+        T::name()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::gen_table;
+
+    gen_table!(
+        struct Account("checksum_accounts") {
+            id: i32 = Type::INT4; [primary_key()],
+            owner: String = Type::TEXT,
+            balance: Option<i64> = Type::INT8; [nullable()],
+        }
+    );
+
+    #[test]
+    fn statement() {
+        assert_eq!(
+            checksum_sql::<Account, 3>(&["id", "balance"]).unwrap(),
+            "SELECT count(*), coalesce(sum(\
+            ('x' || left(md5(ROW(id, balance)::text), 16))::bit(64)::int8::numeric\
+            ), 0)::text FROM checksum_accounts"
+        );
+        assert!(checksum_sql::<Account, 3>(&[])
+            .unwrap()
+            .contains("ROW(id, owner, balance)"));
+    }
+
+    #[test]
+    fn unknown_column() {
+        let err = checksum_sql::<Account, 3>(&["name"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown column to use in the checksum: \"name\""
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::error::Error;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, Utc};
+
+use crate::{
+    codec::Codec,
+    column::SqlLiteral,
+    interval::{AsInterval, Interval},
+    type_helpers::quote_literal,
+};
+
+impl SqlLiteral for NaiveDate {
+    fn to_literal(&self) -> String {
+        format!("{}::date", quote_literal(&self.to_string()))
+    }
+}
+
+impl SqlLiteral for NaiveTime {
+    fn to_literal(&self) -> String {
+        format!("{}::time", quote_literal(&self.to_string()))
+    }
+}
+
+impl SqlLiteral for NaiveDateTime {
+    fn to_literal(&self) -> String {
+        format!("{}::timestamp", quote_literal(&self.to_string()))
+    }
+}
+
+impl SqlLiteral for DateTime<Utc> {
+    fn to_literal(&self) -> String {
+        let value = self.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+        format!("{}::timestamptz", quote_literal(&value))
+    }
+}
+
+impl SqlLiteral for Duration {
+    fn to_literal(&self) -> String {
+        AsInterval::encode(self).to_literal()
+    }
+}
+
+impl Codec<Duration> for AsInterval {
+    type Sql = Interval;
+
+    /// The durations longer than the `i64` microseconds are saturated.
+    fn encode(value: &Duration) -> Interval {
+        let microseconds = value
+            .num_microseconds()
+            .unwrap_or(if *value < Duration::zero() {
+                i64::MIN
+            } else {
+                i64::MAX
+            });
+        Interval::new(0, 0, microseconds)
+    }
+
+    fn decode(value: Interval) -> Result<Duration, Box<dyn Error + Sync + Send>> {
+        value
+            .total_microseconds()
+            .map(Duration::microseconds)
+            .ok_or_else(|| format!("The interval {:?} is too long", value).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client, ColumnBuilder, PgTableExtension as _};
+
+    gen_table!(
+        #[derive(Debug, PartialEq)]
+        struct Event("chrono_events") {
+            id: i32 = Type::INT4; [primary_key()],
+            day: NaiveDate = Type::DATE,
+            starts: NaiveTime = Type::TIME,
+            local: NaiveDateTime = Type::TIMESTAMP,
+            at: DateTime<Utc> = Type::TIMESTAMPTZ,
+            #[pg(with = AsInterval)]
+            lasts: Duration = Type::INTERVAL,
+        }
+    );
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+    }
+
+    #[test]
+    fn literals() {
+        let local = day().and_hms_micro_opt(13, 5, 0, 250).unwrap();
+        assert_eq!(day().to_literal(), "'2024-02-29'::date");
+        assert_eq!(local.time().to_literal(), "'13:05:00.000250'::time");
+        assert_eq!(
+            local.to_literal(),
+            "'2024-02-29 13:05:00.000250'::timestamp"
+        );
+        assert_eq!(
+            local.and_utc().to_literal(),
+            "'2024-02-29T13:05:00.000250Z'::timestamptz"
+        );
+        assert_eq!(
+            Duration::minutes(-90).to_literal(),
+            "'0 mons 0 days -5400000000 microseconds'::interval"
+        );
+
+        let column = ColumnBuilder::new("due", Type::DATE)
+            .default_value(day())
+            .finish();
+        assert_eq!(
+            column.to_string(),
+            "due date DEFAULT ('2024-02-29'::date) NOT NULL"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS chrono_events")
+            .unwrap();
+        client.create_table::<Event, 6>().unwrap();
+
+        let local = day().and_hms_micro_opt(23, 59, 59, 999_999).unwrap();
+        let event = Event {
+            id: 1,
+            day: day(),
+            starts: local.time(),
+            local,
+            at: local.and_utc(),
+            lasts: Duration::days(3) + Duration::microseconds(1),
+        };
+        client.insert_row(&event).unwrap();
+        assert_eq!(client.select_all::<Event, 6>().unwrap(), [event]);
+
+        let month: Duration = client
+            .query_one("SELECT '1 month'::interval", &[])
+            .map(|row| row.get::<_, Interval>(0))
+            .map(|interval| AsInterval::decode(interval).unwrap())
+            .unwrap();
+        assert_eq!(month, Duration::days(30));
+
+        client.batch_execute("DROP TABLE chrono_events").unwrap();
+    }
+}
@@ -0,0 +1,298 @@
+//! Command-line front end for the schema of the tables (enabled with the `cli` feature).
+//!
+//! The tables are compiled into the binary of the application:
+//!
+//! ```ignore
+//! fn main() -> std::process::ExitCode {
+//!     cli::main(&[TableDef::of::<User, 3>(), TableDef::of::<Post, 4>()])
+//! }
+//! ```
+//!
+//! | Command                    | Action                                                   |
+//! |----------------------------|----------------------------------------------------------|
+//! | `print-schema`             | print the DDL of the tables                              |
+//! | `create`                   | create the missing types, tables and indices             |
+//! | `verify`                   | compare the tables with the live schema                  |
+//! | `diff [--schema <name>]`   | print the statements to bring the live schema up to date |
+//! | `self-check`               | check the permissions of the connection                  |
+//!
+//! All the commands except the `print-schema` connect to the `DATABASE_URL`.
+//!
+//! With the `registry` feature the tables defined with the `gen_table!`
+//! are found without listing them with the [`main_registered`].
+
+use std::{env, error::Error as StdError, fmt, io::Write, process::ExitCode};
+
+use postgres::{Client, NoTls};
+
+use crate::{
+    diff::{diff, TableDef},
+    introspect,
+    script::{create_tables, schema_script},
+    self_check::self_check,
+    verify::SchemaReport,
+};
+
+const USAGE: &str = "Usage: <command> [--schema <name>]
+
+Commands:
+    print-schema    print the DDL of the tables
+    create          create the missing types, tables and indices
+    verify          compare the tables with the live schema
+    diff            print the statements to bring the live schema up to date
+                    (the `public` schema is compared by default)
+    self-check      check the permissions of the connection";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    PrintSchema,
+    Create,
+    Verify,
+    Diff { schema: String },
+    SelfCheck,
+}
+
+impl Command {
+    /// Parse the arguments (without the name of the binary).
+    ///
+    /// # Errors
+    ///
+    /// If the command is unknown or the options are invalid.
+    pub fn parse<I>(args: I) -> Result<Self, UsageError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        let command = args.next().ok_or(UsageError::NoCommand)?;
+        let command = match command.as_str() {
+            "print-schema" => Self::PrintSchema,
+            "create" => Self::Create,
+            "verify" => Self::Verify,
+            "diff" => Self::Diff {
+                schema: "public".into(),
+            },
+            "self-check" => Self::SelfCheck,
+            _ => return Err(UsageError::UnknownCommand(command)),
+        };
+
+        let mut command = command;
+        while let Some(arg) = args.next() {
+            match (&mut command, arg.as_str()) {
+                (Self::Diff { schema }, "--schema") => {
+                    *schema = args
+                        .next()
+                        .ok_or_else(|| UsageError::MissingValue(arg.clone()))?;
+                }
+                _ => return Err(UsageError::UnknownArgument(arg)),
+            }
+        }
+        Ok(command)
+    }
+}
+
+/// The invalid command-line arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsageError {
+    NoCommand,
+    UnknownCommand(String),
+    UnknownArgument(String),
+    MissingValue(String),
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCommand => write!(f, "No command given"),
+            Self::UnknownCommand(command) => write!(f, "Unknown command {:?}", command),
+            Self::UnknownArgument(arg) => write!(f, "Unknown argument {:?}", arg),
+            Self::MissingValue(arg) => write!(f, "No value for the {}", arg),
+        }
+    }
+}
+
+impl StdError for UsageError {}
+
+/// Run the command from the arguments of the process
+/// printing the results to the stdout and the errors to the stderr.
+pub fn main(tables: &[TableDef]) -> ExitCode {
+    let command = match Command::parse(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&command, tables, &mut std::io::stdout()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run the command for all the tables of the [`Schema::global`][crate::Schema::global].
+#[cfg(feature = "registry")]
+pub fn main_registered() -> ExitCode {
+    main(crate::Schema::global().tables())
+}
+
+/// Run the command returning whether the live schema matches the tables
+/// or all the operations are permitted for the `self-check`
+/// (always `true` for the other commands).
+///
+/// # Errors
+///
+/// If the database is not available or the command fails.
+pub fn run(
+    command: &Command,
+    tables: &[TableDef],
+    out: &mut dyn Write,
+) -> Result<bool, Box<dyn StdError>> {
+    if *command == Command::PrintSchema {
+        write!(out, "{}", schema_script(tables))?;
+        return Ok(true);
+    }
+
+    let db_url = env::var("DATABASE_URL").map_err(|_| "The DATABASE_URL is not set")?;
+    let mut client = Client::connect(&db_url, NoTls)?;
+    run_with(&mut client, command, tables, out)
+}
+
+fn run_with(
+    client: &mut Client,
+    command: &Command,
+    tables: &[TableDef],
+    out: &mut dyn Write,
+) -> Result<bool, Box<dyn StdError>> {
+    match command {
+        Command::PrintSchema => {
+            write!(out, "{}", schema_script(tables))?;
+        }
+        Command::Create => {
+            create_tables(client, tables)?;
+            writeln!(out, "{} tables are created", tables.len())?;
+        }
+        Command::Verify => {
+            let mut matches = true;
+            for table in tables {
+                let live = introspect::table(client, table.name())?;
+                let report = SchemaReport::compare_def(table, live.as_ref());
+                matches &= report.is_ok();
+                writeln!(out, "{}", report)?;
+            }
+            return Ok(matches);
+        }
+        Command::Diff { schema } => {
+            let live = introspect::schema(client, schema)?;
+            for statement in diff(tables, &live) {
+                writeln!(out, "{};", statement)?;
+            }
+        }
+        Command::SelfCheck => {
+            let report = self_check(client)?;
+            writeln!(out, "{}", report)?;
+            return Ok(report.is_ok());
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client, Table};
+
+    gen_table!(
+        struct Author("cli_authors") {
+            id: i32 = Type::INT4; [primary_key()],
+            name: String = Type::TEXT; [index()],
+        }
+    );
+
+    gen_table!(
+        struct Book("cli_books") {
+            id: i32 = Type::INT4; [primary_key()],
+            author_id: i32 = Type::INT4; [foreign_key(Author::name(), "id")],
+        }
+    );
+
+    fn args(line: &str) -> Result<Command, UsageError> {
+        Command::parse(line.split_whitespace().map(str::to_owned))
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(args("verify"), Ok(Command::Verify));
+        assert_eq!(args("self-check"), Ok(Command::SelfCheck));
+        assert_eq!(
+            args("diff --schema app"),
+            Ok(Command::Diff {
+                schema: "app".into()
+            })
+        );
+        assert_eq!(args(""), Err(UsageError::NoCommand));
+        assert_eq!(args("drop"), Err(UsageError::UnknownCommand("drop".into())));
+        assert_eq!(
+            args("create --schema app"),
+            Err(UsageError::UnknownArgument("--schema".into()))
+        );
+        assert_eq!(
+            args("diff --schema"),
+            Err(UsageError::MissingValue("--schema".into()))
+        );
+    }
+
+    fn output(client: &mut Client, command: &Command, tables: &[TableDef]) -> (bool, String) {
+        let mut out = vec![];
+        let ok = run_with(client, command, tables, &mut out).unwrap();
+        (ok, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn create_and_verify() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP SCHEMA IF EXISTS cli CASCADE; \
+                CREATE SCHEMA cli; \
+                SET search_path = cli",
+            )
+            .unwrap();
+        let tables = [TableDef::of::<Book, 2>(), TableDef::of::<Author, 2>()];
+
+        let (ok, report) = output(&mut client, &Command::Verify, &tables);
+        assert!(!ok);
+        assert!(report.contains("- table does not exist"), "{}", report);
+
+        let diff = Command::Diff {
+            schema: "cli".into(),
+        };
+        let (_, statements) = output(&mut client, &diff, &tables);
+        assert_eq!(statements.lines().count(), 3, "{}", statements);
+
+        assert_eq!(
+            output(&mut client, &Command::Create, &tables),
+            (true, "2 tables are created\n".into())
+        );
+        assert_eq!(
+            output(&mut client, &Command::Verify, &tables),
+            (
+                true,
+                "Table cli_books matches the definition\n\
+                Table cli_authors matches the definition\n"
+                    .into()
+            )
+        );
+        assert_eq!(output(&mut client, &diff, &tables), (true, String::new()));
+
+        client
+            .batch_execute("RESET search_path; DROP SCHEMA cli CASCADE")
+            .unwrap();
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    any::Any,
+    error::Error,
+    fmt::{self, Debug},
+    marker::PhantomData,
+};
+
+use postgres_types::{private::BytesMut, to_sql_checked, FromSql, IsNull, ToSql, Type as DbType};
+
+use crate::table::FieldValue;
+
+/// Custom conversion between a field and its column
+/// when the Rust type does not match the database one
+/// (enabled with `#[pg(with = Codec)]` on the `gen_table!` field).
+///
+/// Such fields can only be passed as the query parameters with the
+/// [`InsertableValues::encoded_values`][crate::InsertableValues::encoded_values]
+/// (the [`InsertableValues::values`][crate::InsertableValues::values] of the table panics).
+pub trait Codec<T> {
+    /// The type which is really stored in the column.
+    type Sql: ToSql + for<'a> FromSql<'a>;
+
+    fn encode(value: &T) -> Self::Sql;
+
+    fn decode(value: Self::Sql) -> Result<T, Box<dyn Error + Sync + Send>>;
+}
+
+/// The value encoded and decoded with the [`Codec`] `C`
+/// (the borrowed one is encoded, the owned one is decoded).
+pub struct With<C, T> {
+    value: T,
+    codec: PhantomData<fn() -> C>,
+}
+
+impl<C, T> With<C, T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            codec: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<C, T: Debug> Debug for With<C, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<'a, C, T> With<C, &'a T>
+where
+    C: Codec<T>,
+    T: Debug + Sync + 'static,
+{
+    /// The field encoded with the codec to pass as a query parameter.
+    pub fn field_value(value: &'a T) -> FieldValue<'a> {
+        FieldValue::Encoded(Encoded {
+            value,
+            encode: encode::<C, T>,
+        })
+    }
+}
+
+/// The field value with its type erased.
+trait AnyField: Debug + Sync {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Debug + Sync + 'static> AnyField for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+type EncodeFn =
+    fn(&dyn Any, &DbType, &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>>;
+
+fn encode<C, T>(
+    value: &dyn Any,
+    ty: &DbType,
+    out: &mut BytesMut,
+) -> Result<IsNull, Box<dyn Error + Sync + Send>>
+where
+    C: Codec<T>,
+    T: Debug + 'static,
+{
+    let value = value
+        .downcast_ref::<T>()
+        .ok_or("The value does not match its codec")?;
+    With::<C, _>::new(value).to_sql_checked(ty, out)
+}
+
+/// The reference to the field to encode with its [`Codec`]
+/// (the codec is kept as a function, so no wrapper is allocated for every value).
+#[derive(Clone, Copy)]
+pub struct Encoded<'a> {
+    value: &'a dyn AnyField,
+    encode: EncodeFn,
+}
+
+impl Debug for Encoded<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl ToSql for Encoded<'_> {
+    fn to_sql(
+        &self,
+        ty: &DbType,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        (self.encode)(self.value.as_any(), ty, out)
+    }
+
+    /// The type is checked by the codec.
+    fn accepts(_: &DbType) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+impl<C, T> ToSql for With<C, &T>
+where
+    C: Codec<T>,
+    T: Debug,
+{
+    fn to_sql(
+        &self,
+        ty: &DbType,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>>
+    where
+        Self: Sized,
+    {
+        C::encode(self.value).to_sql(ty, out)
+    }
+
+    fn accepts(ty: &DbType) -> bool
+    where
+        Self: Sized,
+    {
+        <C::Sql as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a, C, T> FromSql<'a> for With<C, T>
+where
+    C: Codec<T>,
+{
+    fn from_sql(ty: &DbType, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let value = C::Sql::from_sql(ty, raw)?;
+        C::decode(value).map(Self::new)
+    }
+
+    fn from_sql_null(ty: &DbType) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let value = C::Sql::from_sql_null(ty)?;
+        C::decode(value).map(Self::new)
+    }
+
+    fn accepts(ty: &DbType) -> bool {
+        <C::Sql as FromSql>::accepts(ty)
+    }
+}
+
+/// The value decoded from `NULL` as the `T::default()`.
+///
+/// Useful for the fields of the composite types
+/// since any field of a composite could be `NULL` in the database
+/// regardless of its Rust type.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OrDefault<T>(pub T);
+
+impl<T: ToSql> ToSql for OrDefault<T> {
+    fn to_sql(
+        &self,
+        ty: &DbType,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>>
+    where
+        Self: Sized,
+    {
+        self.0.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &DbType) -> bool
+    where
+        Self: Sized,
+    {
+        T::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a, T> FromSql<'a> for OrDefault<T>
+where
+    T: FromSql<'a> + Default,
+{
+    fn from_sql(ty: &DbType, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        T::from_sql(ty, raw).map(Self)
+    }
+
+    fn from_sql_null(_: &DbType) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self(T::default()))
+    }
+
+    fn accepts(ty: &DbType) -> bool {
+        T::accepts(ty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{gen_table, Insertable, InsertableValues};
+
+    struct Millis;
+
+    impl Codec<Duration> for Millis {
+        type Sql = i64;
+
+        fn encode(value: &Duration) -> i64 {
+            i64::try_from(value.as_millis()).unwrap_or(i64::MAX)
+        }
+
+        fn decode(value: i64) -> Result<Duration, Box<dyn Error + Sync + Send>> {
+            Ok(Duration::from_millis(u64::try_from(value)?))
+        }
+    }
+
+    gen_table!(
+        struct Task("tasks") {
+            id: i32 = DbType::INT4,
+            /// Stored as the number of milliseconds
+            #[pg(with = Millis)]
+            timeout: Duration = DbType::INT8,
+        }
+    );
+
+    #[test]
+    fn encode_with_codec() {
+        let task = Task {
+            id: 1,
+            timeout: Duration::from_secs(2),
+        };
+        let values = task.encoded_values();
+        assert!(Task::check_values(&values).is_ok());
+
+        let mut out = BytesMut::new();
+        values[1].to_sql_checked(&DbType::INT8, &mut out).unwrap();
+        assert_eq!(out.as_ref(), 2000_i64.to_be_bytes());
+        assert!(values[1].to_sql_checked(&DbType::TEXT, &mut out).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "The value 2s of the table Task has a codec")]
+    fn raw_values_with_codec() {
+        let task = Task {
+            id: 1,
+            timeout: Duration::from_secs(2),
+        };
+        let _ = task.values();
+    }
+
+    #[test]
+    fn decode_with_codec() {
+        let raw = 1500_i64.to_be_bytes();
+        let value = With::<Millis, Duration>::from_sql(&DbType::INT8, &raw).unwrap();
+        assert_eq!(value.into_inner(), Duration::from_millis(1500));
+
+        let raw = (-1_i64).to_be_bytes();
+        assert!(With::<Millis, Duration>::from_sql(&DbType::INT8, &raw).is_err());
+    }
+
+    #[test]
+    fn null_as_default() {
+        let value = OrDefault::<i32>::from_sql_nullable(&DbType::INT4, None).unwrap();
+        assert_eq!(value, OrDefault(0));
+        assert!(i32::from_sql_nullable(&DbType::INT4, None).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+//! Generate the `gen_table!` definitions of the existing tables
+//! (e.g. to adopt the crate on the legacy schema).
+//!
+//! ```ignore
+//! let tables = introspect::schema(&mut client, "public")?;
+//! std::fs::write("src/tables.rs", codegen::schema_source(&tables))?;
+//! ```
+//!
+//! The columns of the types without the known Rust counterpart
+//! and the multi-column constraints (except the checks) are left as the comments
+//! to be defined manually.
+
+use std::fmt::Write as _;
+
+use postgres_types::{Kind, Type as DbType};
+
+use crate::{
+    column::Column,
+    config::unqualified,
+    index::IndexMethod,
+    introspect::{ConstraintKind, TableInfo},
+};
+
+const HEADER: &str =
+    "use pg_helper::{array_type, enum_type, gen_table, CheckConstraint, IndexMethod};
+use postgres_types::Type;
+";
+
+/// The source of the module with the definitions of all the tables.
+pub fn schema_source(tables: &[TableInfo]) -> String {
+    let mut source = HEADER.to_owned();
+    for table in tables {
+        source.push('\n');
+        source.push_str(&table_source(table));
+    }
+    source
+}
+
+/// The `gen_table!` invocation for the table.
+pub fn table_source(table: &TableInfo) -> String {
+    let sql_name = table.name.strip_prefix("public.").unwrap_or(&table.name);
+    let mut source = format!(
+        "gen_table!(\n    #[derive(Debug, Clone, PartialEq)]\n    pub struct {}({:?}) {{\n",
+        struct_name(unqualified(&table.name)),
+        sql_name
+    );
+    for col in &table.columns {
+        match field_source(col) {
+            Some(field) => writeln!(source, "        {},", field).unwrap(),
+            None => writeln!(
+                source,
+                "        // {}: unsupported type {}",
+                col.name(),
+                col.db_type()
+            )
+            .unwrap(),
+        }
+    }
+
+    let mut checks = vec![];
+    for constraint in &table.constraints {
+        let single_column = constraint.columns.len() == 1;
+        let column_level = match constraint.kind {
+            ConstraintKind::PrimaryKey | ConstraintKind::Unique | ConstraintKind::ForeignKey => {
+                single_column
+            }
+            _ => false,
+        };
+        if column_level {
+            continue;
+        }
+        let check = (constraint.kind == ConstraintKind::Check)
+            .then(|| constraint.definition.strip_prefix("CHECK "))
+            .flatten();
+        match check {
+            Some(condition) => checks.push(format!(
+                "CheckConstraint::new({:?}, {:?})",
+                constraint.name, condition
+            )),
+            None => writeln!(
+                source,
+                "        // {}: {}",
+                constraint.name, constraint.definition
+            )
+            .unwrap(),
+        }
+    }
+    if !checks.is_empty() {
+        source.push_str("        => constraints = [\n");
+        for check in checks {
+            writeln!(source, "            {},", check).unwrap();
+        }
+        source.push_str("        ]\n");
+    }
+    source.push_str("    }\n);\n");
+    source
+}
+
+fn field_source(col: &Column) -> Option<String> {
+    if !is_identifier(col.name()) {
+        return None;
+    }
+    let (rust_type, type_expr) = type_source(col.db_type())?;
+    let rust_type = if col.is_nullable() {
+        format!("Option<{}>", rust_type)
+    } else {
+        rust_type
+    };
+
+    let mut props = vec![];
+    if col.is_nullable() {
+        props.push("nullable()".to_owned());
+    }
+    if col.is_primary_key() {
+        props.push("primary_key()".to_owned());
+    } else if col.is_unique() {
+        props.push("unique()".to_owned());
+    }
+    if let Some((table, column)) = col.foreign_key() {
+        let table = table.strip_prefix("public.").unwrap_or(&table);
+        props.push(format!("foreign_key({:?}, {:?})", table, column));
+    }
+    if let Some((precision, scale)) = col.precision() {
+        props.push(format!("precision({}, {})", precision, scale));
+    }
+    match col.get_index() {
+        Some(IndexMethod::BTree) => props.push("index()".to_owned()),
+        Some(method) => props.push(format!("index_with(IndexMethod::{:?})", method)),
+        None => {}
+    }
+
+    let mut field = format!("{}: {} = {}", col.name(), rust_type, type_expr);
+    if !props.is_empty() {
+        write!(field, "; [{}]", props.join(", ")).unwrap();
+    }
+    Some(field)
+}
+
+/// The Rust type and the expression of the database type.
+fn type_source(ty: &DbType) -> Option<(String, String)> {
+    match ty.kind() {
+        Kind::Array(inner) => {
+            let (rust_type, _) = type_source(inner)?;
+            let type_expr = if is_builtin(ty) {
+                format!("Type::{}_ARRAY", inner.name().to_uppercase())
+            } else {
+                format!("array_type({})", type_source(inner)?.1)
+            };
+            Some((format!("Vec<{}>", rust_type), type_expr))
+        }
+        Kind::Enum(variants) => {
+            let variants: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
+            Some((
+                // the enums are mapped to the custom types deriving the `FromSql`
+                format!("/* {} */ String", ty.name()),
+                format!("enum_type({:?}, &[{}])", ty.name(), variants.join(", ")),
+            ))
+        }
+        Kind::Simple if is_builtin(ty) => {
+            let rust_type = match *ty {
+                DbType::BOOL => "bool",
+                DbType::CHAR => "i8",
+                DbType::INT2 => "i16",
+                DbType::INT4 => "i32",
+                DbType::INT8 => "i64",
+                DbType::OID => "u32",
+                DbType::FLOAT4 => "f32",
+                DbType::FLOAT8 => "f64",
+                DbType::NUMERIC => "rust_decimal::Decimal",
+                DbType::TEXT | DbType::VARCHAR | DbType::BPCHAR | DbType::NAME => "String",
+                DbType::BYTEA => "Vec<u8>",
+                DbType::TIMESTAMP | DbType::TIMESTAMPTZ => "std::time::SystemTime",
+                DbType::INET => "std::net::IpAddr",
+                DbType::CIDR => "cidr::IpCidr",
+                DbType::MACADDR => "eui48::MacAddress",
+                DbType::UUID => "uuid::Uuid",
+                DbType::POINT => "pg_helper::Point",
+                DbType::LINE => "pg_helper::Line",
+                DbType::BOX => "pg_helper::Rect",
+                DbType::PATH => "pg_helper::Path",
+                DbType::POLYGON => "pg_helper::Polygon",
+                DbType::CIRCLE => "pg_helper::Circle",
+                DbType::JSON | DbType::JSONB => "serde_json::Value",
+                _ => return None,
+            };
+            Some((
+                rust_type.to_owned(),
+                format!("Type::{}", ty.name().to_uppercase()),
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn is_builtin(ty: &DbType) -> bool {
+    DbType::from_oid(ty.oid()).is_some()
+}
+
+fn is_identifier(name: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else",
+        "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+        "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while", "yield",
+    ];
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c == '_' || c.is_ascii_lowercase())
+        && chars.all(|c| c == '_' || c.is_ascii_lowercase() || c.is_ascii_digit())
+        && !KEYWORDS.contains(&name)
+}
+
+/// The `order_items` table is defined with the `OrderItems` struct.
+fn struct_name(table: &str) -> String {
+    table
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_uppercase().chain(chars).collect()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{enum_type, introspect, test_helpers::get_client, ColumnBuilder};
+
+    #[test]
+    fn names() {
+        assert_eq!(struct_name("order_items"), "OrderItems");
+        assert_eq!(struct_name("_legacy__v2"), "LegacyV2");
+        assert!(is_identifier("total_2"));
+        assert!(!is_identifier("type"));
+        assert!(!is_identifier("Total"));
+    }
+
+    #[test]
+    fn columns() {
+        let field = |builder: ColumnBuilder| field_source(&builder.finish());
+        assert_eq!(
+            field(ColumnBuilder::new("id", DbType::INT8).primary_key()),
+            Some("id: i64 = Type::INT8; [primary_key()]".into())
+        );
+        assert_eq!(
+            field(ColumnBuilder::new("tags", DbType::TEXT_ARRAY).nullable()),
+            Some("tags: Option<Vec<String>> = Type::TEXT_ARRAY; [nullable()]".into())
+        );
+        assert_eq!(
+            field(
+                ColumnBuilder::new("owner", DbType::INT4)
+                    .foreign_key("public.users", "id")
+                    .index_with(IndexMethod::Hash)
+            ),
+            Some(
+                "owner: i32 = Type::INT4; [foreign_key(\"users\", \"id\"), \
+                index_with(IndexMethod::Hash)]"
+                    .into()
+            )
+        );
+        assert_eq!(
+            field(ColumnBuilder::new(
+                "mood",
+                enum_type("mood", &["sad", "ok"])
+            )),
+            Some("mood: /* mood */ String = enum_type(\"mood\", &[\"sad\", \"ok\"])".into())
+        );
+        assert_eq!(field(ColumnBuilder::new("at", DbType::TIME)), None);
+    }
+
+    #[test]
+    fn existing_table() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP SCHEMA IF EXISTS generated CASCADE; \
+                CREATE SCHEMA generated; \
+                CREATE TABLE generated.stock_items (\
+                    sku text PRIMARY KEY, \
+                    warehouse int4 NOT NULL, \
+                    amount int8 CHECK (amount >= 0), \
+                    price numeric(12, 2) NOT NULL, \
+                    opened_at time, \
+                    UNIQUE (sku, warehouse)\
+                )",
+            )
+            .unwrap();
+
+        let tables = introspect::schema(&mut client, "generated").unwrap();
+        assert_eq!(
+            schema_source(&tables),
+            format!(
+                "{}\n{}",
+                HEADER,
+                r#"gen_table!(
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct StockItems("generated.stock_items") {
+        sku: String = Type::TEXT; [primary_key()],
+        warehouse: i32 = Type::INT4,
+        amount: Option<i64> = Type::INT8; [nullable()],
+        price: rust_decimal::Decimal = Type::NUMERIC; [precision(12, 2)],
+        // opened_at: unsupported type time
+        // stock_items_sku_warehouse_key: UNIQUE (sku, warehouse)
+        => constraints = [
+            CheckConstraint::new("stock_items_amount_check", "((amount >= 0))"),
+        ]
+    }
+);
+"#
+            )
+        );
+        client
+            .batch_execute("DROP SCHEMA generated CASCADE")
+            .unwrap();
+    }
+}
@@ -1,17 +1,36 @@
-use std::fmt::{self, Debug, Display};
+use std::{
+    fmt::{self, Debug, Display, Write as _},
+    net::IpAddr,
+};
 
 use postgres_types::{Kind, Type as DbType};
 
-use crate::type_helpers::ObjectAndCreateSql;
+use crate::{
+    constraint::ForeignKeyAction,
+    index::{Index, IndexKey, IndexMethod},
+    query::Redaction,
+    type_helpers::{quote_literal, validate_name, ObjectAndCreateSql},
+};
 
 pub struct ColumnBuilder {
     name: String,
     db_type: DbType,
     nullable: bool,
     unique: bool,
+    nulls_not_distinct: bool,
     primary_key: bool,
     foreign_key: Option<(String, String)>,
+    on_delete: Option<ForeignKeyAction>,
+    on_update: Option<ForeignKeyAction>,
     index: Option<IndexMethod>,
+    index_opclass: Option<String>,
+    rust_type: Option<&'static str>,
+    storage: Option<Storage>,
+    compression: Option<Compression>,
+    precision: Option<(u16, u16)>,
+    default: Option<String>,
+    check: Option<String>,
+    sensitive: bool,
 }
 
 impl ColumnBuilder {
@@ -21,9 +40,20 @@ impl ColumnBuilder {
             db_type,
             nullable: false,
             unique: false,
+            nulls_not_distinct: false,
             primary_key: false,
             foreign_key: None,
+            on_delete: None,
+            on_update: None,
             index: None,
+            index_opclass: None,
+            rust_type: None,
+            storage: None,
+            compression: None,
+            precision: None,
+            default: None,
+            check: None,
+            sensitive: false,
         }
     }
 
@@ -37,6 +67,13 @@ impl ColumnBuilder {
         self
     }
 
+    /// The unique column allowing only a single `NULL` value (requires PostgreSQL 15).
+    pub const fn unique_nulls_not_distinct(mut self) -> Self {
+        self.unique = true;
+        self.nulls_not_distinct = true;
+        self
+    }
+
     pub const fn primary_key(mut self) -> Self {
         self.primary_key = true;
         self.unique = true;
@@ -48,6 +85,18 @@ impl ColumnBuilder {
         self
     }
 
+    /// The action on deleting the row referenced with the [foreign key][Self::foreign_key].
+    pub const fn on_delete(mut self, action: ForeignKeyAction) -> Self {
+        self.on_delete = Some(action);
+        self
+    }
+
+    /// The action on updating the key referenced with the [foreign key][Self::foreign_key].
+    pub const fn on_update(mut self, action: ForeignKeyAction) -> Self {
+        self.on_update = Some(action);
+        self
+    }
+
     pub fn index(self) -> Self {
         self.index_with(IndexMethod::default())
     }
@@ -57,16 +106,124 @@ impl ColumnBuilder {
         self
     }
 
+    /// The operator class of the column index other than the default one
+    /// (e.g. the `jsonb_path_ops` for the smaller GIN index only supporting the `@>` and `@?`).
+    ///
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn index_opclass(mut self, opclass: impl AsRef<str>) -> Self {
+        let opclass = opclass.as_ref();
+        validate_name("operator class", opclass);
+        self.index_opclass = Some(opclass.to_owned());
+        self
+    }
+
+    /// The name of the Rust type of the field stored in the column.
+    pub const fn rust_type(mut self, name: &'static str) -> Self {
+        self.rust_type = Some(name);
+        self
+    }
+
+    /// How the values of the column are stored out of line (TOAST).
+    pub const fn storage(mut self, storage: Storage) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// The compression method of the big values (`lz4` requires PostgreSQL 14).
+    pub const fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// The total number of the digits and the number of the fractional ones
+    /// of the `numeric` column (e.g. `numeric(12, 2)` for the money).
+    ///
+    /// # Panics
+    ///
+    /// If the column is not `numeric` (or its array)
+    /// or the precision is not in the `1..=1000` range or is less than the scale.
+    pub fn precision(mut self, precision: u16, scale: u16) -> Self {
+        let element = match self.db_type.kind() {
+            Kind::Array(inner) => inner,
+            _ => &self.db_type,
+        };
+        assert!(
+            *element == DbType::NUMERIC,
+            "The precision is only supported for the numeric columns, got {}",
+            self.db_type
+        );
+        assert!(
+            (1..=1000).contains(&precision) && scale <= precision,
+            "Invalid precision {} and scale {} of the column {}",
+            precision,
+            scale,
+            self.name
+        );
+        self.precision = Some((precision, scale));
+        self
+    }
+
+    /// The SQL expression of the value for the omitted column, e.g. `now()`.
+    ///
+    /// The `None` value of the `Option` field is inserted as the default.
+    pub fn default_sql(mut self, expr: impl AsRef<str>) -> Self {
+        self.default = Some(expr.as_ref().to_owned());
+        self
+    }
+
+    /// The constant value for the omitted column (see the [`Self::default_sql`]).
+    pub fn default_value(self, value: impl SqlLiteral) -> Self {
+        self.default_sql(value.to_literal())
+    }
+
+    /// The rule for the values of the column (`CHECK (expr)` inline in its definition),
+    /// see the [`CheckConstraint`][crate::CheckConstraint] for the rules on multiple columns.
+    pub fn check(mut self, expr: impl AsRef<str>) -> Self {
+        self.check = Some(expr.as_ref().to_owned());
+        self
+    }
+
+    /// The column holds the secrets not to be shown in the
+    /// [redacted][crate::SelectOptions::redacted] selects.
+    pub const fn sensitive(mut self) -> Self {
+        self.sensitive = true;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// If the column is [sensitive][Self::sensitive] and not nullable
+    /// but its type has no default value to redact with.
     pub fn finish(self) -> Column {
-        Column {
+        let column = Column {
             name: self.name,
             db_type: self.db_type,
             nullable: self.nullable,
             unique: self.unique,
+            nulls_not_distinct: self.nulls_not_distinct,
             primary_key: self.primary_key,
             foreign_key: self.foreign_key,
+            on_delete: self.on_delete,
+            on_update: self.on_update,
             index: self.index,
-        }
+            index_opclass: self.index_opclass,
+            rust_type: self.rust_type,
+            storage: self.storage,
+            compression: self.compression,
+            precision: self.precision,
+            default: self.default,
+            check: self.check,
+            sensitive: self.sensitive,
+        };
+        assert!(
+            !column.sensitive || column.zero_value_sql().is_some(),
+            "Column {} of type {} has no default value to redact with",
+            column.name,
+            column.db_type
+        );
+        column
     }
 }
 
@@ -76,9 +233,20 @@ pub struct Column {
     db_type: DbType,
     nullable: bool,
     unique: bool,
+    nulls_not_distinct: bool,
     primary_key: bool,
     foreign_key: Option<(String, String)>,
+    on_delete: Option<ForeignKeyAction>,
+    on_update: Option<ForeignKeyAction>,
     index: Option<IndexMethod>,
+    index_opclass: Option<String>,
+    rust_type: Option<&'static str>,
+    storage: Option<Storage>,
+    compression: Option<Compression>,
+    precision: Option<(u16, u16)>,
+    default: Option<String>,
+    check: Option<String>,
+    sensitive: bool,
 }
 
 impl Column {
@@ -88,9 +256,20 @@ impl Column {
             db_type,
             nullable: false,
             unique: false,
+            nulls_not_distinct: false,
             primary_key: false,
             foreign_key: None,
+            on_delete: None,
+            on_update: None,
             index: None,
+            index_opclass: None,
+            rust_type: None,
+            storage: None,
+            compression: None,
+            precision: None,
+            default: None,
+            check: None,
+            sensitive: false,
         }
     }
 
@@ -102,16 +281,142 @@ impl Column {
         ObjectAndCreateSql::from_type(self.db_type())
     }
 
-    pub(crate) fn create_index_sql(&self, table_name: &str) -> Option<ObjectAndCreateSql> {
-        self.index.map(|im| {
-            let idx = Index {
-                table_name: table_name.to_string(),
-                column_name: self.name.clone(),
-                method: im,
+    /// The index declared on the column itself.
+    pub(crate) fn own_index(&self) -> Option<Index> {
+        self.index.map(|method| {
+            let key = match &self.index_opclass {
+                Some(opclass) => IndexKey::new(self).opclass(opclass),
+                None => IndexKey::new(self),
             };
-            ObjectAndCreateSql::new(&self.name, idx.to_string())
+            Index::new([key]).using(method)
         })
     }
+
+    pub(crate) fn index_name(&self, table_name: &str) -> Option<String> {
+        self.own_index().map(|idx| idx.name(table_name))
+    }
+
+    /// The type as written in the DDL.
+    pub(crate) fn type_sql(&self) -> String {
+        let modifier = match self.precision {
+            Some((precision, scale)) => format!("({}, {})", precision, scale),
+            None => String::new(),
+        };
+        match self.db_type.kind() {
+            Kind::Array(inner) => format!("{}{}[]", inner, modifier),
+            _ => format!("{}{}", self.db_type, modifier),
+        }
+    }
+
+    /// The name of the type as stored in the `pg_type`
+    /// followed by the type modifier (e.g. `numeric(12,2)`).
+    pub(crate) fn catalog_type_name(&self) -> String {
+        let name = match self.db_type.kind() {
+            Kind::Array(inner) => format!("_{}", inner.name()),
+            _ => self.db_type.name().to_owned(),
+        };
+        match self.precision {
+            Some((precision, scale)) => format!("{}({},{})", name, precision, scale),
+            None => name,
+        }
+    }
+
+    /// The placeholder of the value in the _INSERT_
+    /// (the `NULL` is replaced with the default value if any).
+    pub(crate) fn write_insert_placeholder(&self, out: &mut String, index: usize) {
+        match &self.default {
+            // the cast prevents inferring the type of the parameter from the default
+            Some(default) => write!(
+                out,
+                "COALESCE(${}::{}, {})",
+                index,
+                self.type_sql(),
+                default
+            ),
+            None => write!(out, "${}", index),
+        }
+        .unwrap();
+    }
+
+    /// The `REFERENCES` clause with the referential actions.
+    pub(crate) fn references_sql(&self) -> Option<String> {
+        let (table, column) = self.foreign_key.as_ref()?;
+        let mut sql = format!("REFERENCES {}({})", table, column);
+        if let Some(action) = self.on_delete {
+            write!(sql, " ON DELETE {}", action).unwrap();
+        }
+        if let Some(action) = self.on_update {
+            write!(sql, " ON UPDATE {}", action).unwrap();
+        }
+        Some(sql)
+    }
+
+    /// The item of the select list hiding the value of the [sensitive][Self::is_sensitive] column.
+    pub(crate) fn redacted_sql(&self, redaction: &Redaction) -> String {
+        if !self.sensitive {
+            return self.name.clone();
+        }
+        let is_text = [DbType::TEXT, DbType::VARCHAR, DbType::BPCHAR].contains(&self.db_type);
+        let value = match redaction {
+            Redaction::Mask(mask) if is_text => quote_literal(mask),
+            _ => self
+                .zero_value_sql()
+                .expect("The sensitive column is checked to have the default value"),
+        };
+        format!("{}::{} AS {}", value, self.type_sql(), self.name)
+    }
+
+    /// The value decoded into the `Default` of the Rust type of the column.
+    fn zero_value_sql(&self) -> Option<String> {
+        let numbers = [
+            DbType::INT2,
+            DbType::INT4,
+            DbType::INT8,
+            DbType::FLOAT4,
+            DbType::FLOAT8,
+            DbType::NUMERIC,
+            DbType::OID,
+        ];
+        let strings = [
+            DbType::TEXT,
+            DbType::VARCHAR,
+            DbType::BPCHAR,
+            DbType::NAME,
+            DbType::BYTEA,
+        ];
+        let value = if self.nullable {
+            "NULL"
+        } else if numbers.contains(&self.db_type) {
+            "0"
+        } else if strings.contains(&self.db_type) {
+            "''"
+        } else if self.db_type == DbType::BOOL {
+            "false"
+        } else if matches!(self.db_type.kind(), Kind::Array(_)) {
+            "'{}'"
+        } else {
+            return None;
+        };
+        Some(value.to_owned())
+    }
+
+    /// The storage settings could not be specified in the _CREATE TABLE_
+    /// until PostgreSQL 16, so they are set after the table creation.
+    pub(crate) fn alter_storage_sql(&self, table_name: &str) -> Option<String> {
+        let actions = self.storage_actions();
+        (!actions.is_empty()).then(|| format!("ALTER TABLE {} {}", table_name, actions.join(", ")))
+    }
+
+    /// The `ALTER COLUMN` actions of the storage settings.
+    pub(crate) fn storage_actions(&self) -> Vec<String> {
+        let storage = self
+            .storage
+            .map(|storage| format!("ALTER COLUMN {} SET STORAGE {}", self.name, storage));
+        let compression = self.compression.map(|compression| {
+            format!("ALTER COLUMN {} SET COMPRESSION {}", self.name, compression)
+        });
+        storage.into_iter().chain(compression).collect()
+    }
 }
 
 impl Column {
@@ -124,7 +429,11 @@ impl Column {
     }
 
     pub const fn is_unique(&self) -> bool {
-        self.nullable
+        self.unique
+    }
+
+    pub const fn is_nulls_not_distinct(&self) -> bool {
+        self.nulls_not_distinct
     }
 
     pub const fn is_primary_key(&self) -> bool {
@@ -135,77 +444,225 @@ impl Column {
         self.foreign_key.clone()
     }
 
+    pub const fn on_delete(&self) -> Option<ForeignKeyAction> {
+        self.on_delete
+    }
+
+    pub const fn on_update(&self) -> Option<ForeignKeyAction> {
+        self.on_update
+    }
+
+    pub fn index_opclass(&self) -> Option<&str> {
+        self.index_opclass.as_deref()
+    }
+
     pub fn get_index(&self) -> Option<IndexMethod> {
         self.index
     }
+
+    pub const fn rust_type(&self) -> Option<&'static str> {
+        self.rust_type
+    }
+
+    pub const fn storage(&self) -> Option<Storage> {
+        self.storage
+    }
+
+    pub const fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+
+    /// The precision and the scale of the `numeric` column.
+    pub const fn precision(&self) -> Option<(u16, u16)> {
+        self.precision
+    }
+
+    pub fn default_sql(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    pub fn check(&self) -> Option<&str> {
+        self.check.as_deref()
+    }
+
+    pub const fn is_sensitive(&self) -> bool {
+        self.sensitive
+    }
+
+    /// Detached description of the column for the generic code.
+    pub fn meta(&self) -> ColumnMeta {
+        ColumnMeta {
+            name: self.name.clone(),
+            pg_type: self.db_type.clone(),
+            rust_type_name: self.rust_type,
+            nullable: self.nullable,
+            unique: self.unique,
+            primary_key: self.primary_key,
+            foreign_key: self.foreign_key.clone(),
+            default: self.default.clone(),
+        }
+    }
+}
+
+/// Runtime information about the column
+/// (e.g. to generate the forms or to validate the input).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMeta {
+    pub name: String,
+    pub pg_type: DbType,
+    /// Only known for the columns generated from the struct fields.
+    pub rust_type_name: Option<&'static str>,
+    pub nullable: bool,
+    pub unique: bool,
+    pub primary_key: bool,
+    /// The referenced table and column.
+    pub foreign_key: Option<(String, String)>,
+    /// The SQL expression of the default value.
+    pub default: Option<String>,
 }
 
 impl Display for Column {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let nullable = if self.nullable { " NULL" } else { " NOT NULL" };
-        let unique = if self.unique { " UNIQUE" } else { "" };
+        let unique = match (self.unique, self.nulls_not_distinct) {
+            (true, true) => " UNIQUE NULLS NOT DISTINCT",
+            (true, false) => " UNIQUE",
+            (false, _) => "",
+        };
         let primary_key = if self.primary_key { " PRIMARY KEY" } else { "" };
-        let foreign_key = if let Some((ref_table, ref_column)) = &self.foreign_key {
-            format!(" REFERENCES {}({})", ref_table, ref_column)
+        let foreign_key = if let Some(references) = self.references_sql() {
+            format!(" {}", references)
         } else {
             "".into()
         };
 
-        let type_desc = match self.db_type.kind() {
-            Kind::Array(inner) => format!("{}[]", inner),
-            _ => self.db_type.to_string(),
+        // only the restricted expressions are allowed without the parentheses
+        let default = if let Some(default) = &self.default {
+            format!(" DEFAULT ({})", default)
+        } else {
+            "".into()
+        };
+        let check = if let Some(check) = &self.check {
+            format!(" CHECK ({})", check)
+        } else {
+            "".into()
         };
 
         write!(
             f,
-            "{} {}{}{}{}{}",
-            self.name, type_desc, nullable, unique, primary_key, foreign_key
+            "{} {}{}{}{}{}{}{}",
+            self.name,
+            self.type_sql(),
+            default,
+            nullable,
+            unique,
+            primary_key,
+            foreign_key,
+            check
         )
     }
 }
 
-#[derive(Debug)]
-pub struct Index {
-    table_name: String,
-    column_name: String,
-    method: IndexMethod,
+/// The value which could be written as the SQL literal
+/// (e.g. for the [column defaults][ColumnBuilder::default_value]).
+pub trait SqlLiteral {
+    fn to_literal(&self) -> String;
+}
+
+macro_rules! number_literal {
+    ($($ty:ty),+) => {
+        $(
+            impl SqlLiteral for $ty {
+                fn to_literal(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )+
+    };
+}
+
+number_literal!(i16, i32, i64);
+
+macro_rules! float_literal {
+    ($($ty:ty),+) => {
+        $(
+            impl SqlLiteral for $ty {
+                fn to_literal(&self) -> String {
+                    if self.is_nan() {
+                        quote_literal("NaN")
+                    } else if self.is_infinite() {
+                        quote_literal(if *self > 0.0 { "Infinity" } else { "-Infinity" })
+                    } else {
+                        self.to_string()
+                    }
+                }
+            }
+        )+
+    };
+}
+
+float_literal!(f32, f64);
+
+impl SqlLiteral for bool {
+    fn to_literal(&self) -> String {
+        self.to_string()
+    }
 }
 
-impl Index {
-    fn generate_name(&self) -> String {
-        format!("{}_idx_{}", self.column_name, self.table_name)
+impl SqlLiteral for &str {
+    fn to_literal(&self) -> String {
+        quote_literal(self)
     }
 }
 
-impl Display for Index {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let name = self.generate_name();
-        write!(
-            f,
-            "CREATE INDEX IF NOT EXISTS {} ON {} USING {} ({})",
-            name, self.table_name, self.method, self.column_name
-        )
+impl SqlLiteral for String {
+    fn to_literal(&self) -> String {
+        quote_literal(self)
+    }
+}
+
+impl SqlLiteral for IpAddr {
+    fn to_literal(&self) -> String {
+        format!("{}::inet", quote_literal(&self.to_string()))
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-#[non_exhaustive]
-pub enum IndexMethod {
-    BTree,
-    Hash,
+/// The strategy of storing the column values (see the `ALTER TABLE ... SET STORAGE`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Storage {
+    /// Inline and uncompressed.
+    Plain,
+    /// Out of line and uncompressed (faster substring operations on `text` and `bytea`).
+    External,
+    /// Out of line and compressed (the default for most of the variable-length types).
+    Extended,
+    /// Inline and compressed, out of line only as a last resort.
+    Main,
 }
 
-impl Default for IndexMethod {
-    fn default() -> Self {
-        Self::BTree
+impl Display for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            Storage::Plain => "PLAIN",
+            Storage::External => "EXTERNAL",
+            Storage::Extended => "EXTENDED",
+            Storage::Main => "MAIN",
+        };
+        write!(f, "{}", desc)
     }
 }
 
-impl Display for IndexMethod {
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compression {
+    Pglz,
+    Lz4,
+}
+
+impl Display for Compression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let desc = match self {
-            IndexMethod::BTree => "btree",
-            IndexMethod::Hash => "hash",
+            Compression::Pglz => "pglz",
+            Compression::Lz4 => "lz4",
         };
         write!(f, "{}", desc)
     }
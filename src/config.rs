@@ -0,0 +1,221 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Mutex, RwLock},
+};
+
+static CONFIG: RwLock<Option<TableConfig>> = RwLock::new(None);
+
+/// The names already resolved with the installed config
+/// (leaked once to comply with the `&'static str` of the [`Table::name`][crate::Table::name]).
+static RESOLVED: Mutex<Option<HashMap<String, &'static str>>> = Mutex::new(None);
+
+/// Runtime overrides of the table names, e.g. the `test_` prefix to isolate
+/// the tests sharing the database or the suffix for the blue/green tables.
+///
+/// Once [installed][Self::install], it is applied to the names of all the tables
+/// defined with the `gen_table!` (the manual implementations of the [`Table`][crate::Table]
+/// should wrap their names with the [`Self::table_name`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableConfig {
+    schema: Option<String>,
+    prefix: String,
+    suffix: String,
+}
+
+impl TableConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Qualify the tables with the schema (overrides the [`Table::schema`][crate::Table::schema]).
+    pub fn schema(mut self, schema: impl Into<String>) -> Self {
+        self.schema = Some(schema.into());
+        self
+    }
+
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    pub fn suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = suffix.into();
+        self
+    }
+
+    /// Read the overrides from the `PG_HELPER_SCHEMA`,
+    /// `PG_HELPER_TABLE_PREFIX` and `PG_HELPER_TABLE_SUFFIX` variables.
+    pub fn from_env() -> Self {
+        Self {
+            schema: env::var("PG_HELPER_SCHEMA").ok(),
+            prefix: env::var("PG_HELPER_TABLE_PREFIX").unwrap_or_default(),
+            suffix: env::var("PG_HELPER_TABLE_SUFFIX").unwrap_or_default(),
+        }
+    }
+
+    /// Read the overrides from the `schema`, `prefix` and `suffix` string keys.
+    ///
+    /// # Errors
+    ///
+    /// If the document is invalid or has other keys.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(document: &str) -> Result<Self, toml::de::Error> {
+        use serde::de::Error as _;
+
+        let mut config = Self::new();
+        let values: HashMap<String, String> = toml::from_str(document)?;
+        for (key, value) in values {
+            match key.as_str() {
+                "schema" => config.schema = Some(value),
+                "prefix" => config.prefix = value,
+                "suffix" => config.suffix = value,
+                _ => {
+                    return Err(toml::de::Error::custom(format!(
+                        "Unknown table config key {:?}",
+                        key
+                    )))
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Use the config for all the tables from now on.
+    ///
+    /// The tables already created with the previous names are not renamed.
+    pub fn install(self) {
+        *CONFIG.write().unwrap_or_else(|err| err.into_inner()) = Some(self);
+    }
+
+    /// Return to the names as they are defined.
+    pub fn uninstall() {
+        *CONFIG.write().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+
+    fn apply(&self, name: &str) -> String {
+        let (schema, name) = match name.rsplit_once('.') {
+            Some((schema, name)) => (Some(schema), name),
+            None => (None, name),
+        };
+        let name = format!("{}{}{}", self.prefix, name, self.suffix);
+        match self.schema.as_deref().or(schema) {
+            Some(schema) => format!("{}.{}", schema, name),
+            None => name,
+        }
+    }
+
+    /// The name of the table with the installed overrides.
+    pub fn table_name(name: &'static str) -> &'static str {
+        let config = CONFIG.read().unwrap_or_else(|err| err.into_inner());
+        let Some(config) = config.as_ref() else {
+            return name;
+        };
+        resolved(config.apply(name))
+    }
+
+    /// The name of the table qualified with the `schema` with the installed overrides.
+    pub fn table_name_in(schema: &'static str, name: &'static str) -> &'static str {
+        let name = format!("{}.{}", schema, name);
+        let config = CONFIG.read().unwrap_or_else(|err| err.into_inner());
+        match config.as_ref() {
+            Some(config) => resolved(config.apply(&name)),
+            None => resolved(name),
+        }
+    }
+}
+
+fn resolved(name: String) -> &'static str {
+    let mut resolved = RESOLVED.lock().unwrap_or_else(|err| err.into_inner());
+    resolved
+        .get_or_insert_with(HashMap::new)
+        .entry(name)
+        .or_insert_with_key(|name| Box::leak(name.clone().into_boxed_str()))
+}
+
+/// The name of the table without the schema.
+pub(crate) fn unqualified(name: &str) -> &str {
+    name.rsplit_once('.').map_or(name, |(_, name)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client, PgTableExtension as _, Table};
+
+    gen_table!(
+        #[derive(Debug, PartialEq)]
+        struct Flag("flags") {
+            id: i32 = Type::INT4; [primary_key()],
+            name: String = Type::TEXT; [index()],
+        }
+    );
+
+    #[test]
+    fn overridden_names() {
+        let config = TableConfig::new().prefix("test_").suffix("_blue");
+        assert_eq!(config.apply("flags"), "test_flags_blue");
+        assert_eq!(config.apply("billing.flags"), "billing.test_flags_blue");
+        assert_eq!(
+            config.schema("qa").apply("billing.flags"),
+            "qa.test_flags_blue"
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_document() {
+        let config = TableConfig::from_toml("schema = \"qa\"\nprefix = \"test_\"").unwrap();
+        assert_eq!(config, TableConfig::new().schema("qa").prefix("test_"));
+
+        let err = TableConfig::from_toml("table = \"flags\"").unwrap_err();
+        assert!(
+            err.to_string().contains("Unknown table config key"),
+            "{}",
+            err
+        );
+    }
+
+    /// The only test installing the global config as the others rely on the defined names.
+    #[test]
+    fn installed() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP SCHEMA IF EXISTS configured CASCADE; CREATE SCHEMA configured")
+            .unwrap();
+
+        TableConfig::new()
+            .schema("configured")
+            .prefix("test_")
+            .install();
+        assert_eq!(Flag::name(), "configured.test_flags");
+        let created = client.create_table::<Flag, 2>();
+        let flag = Flag {
+            id: 1,
+            name: "dark-mode".into(),
+        };
+        let inserted = created.and_then(|_| client.insert_row(&flag));
+        let selected = client.select_all::<Flag, 2>();
+        TableConfig::uninstall();
+        assert_eq!(Flag::name(), "flags");
+
+        assert_eq!(inserted.unwrap(), 1);
+        assert_eq!(selected.unwrap(), [flag]);
+        let index: String = client
+            .query_one(
+                "SELECT indexname FROM pg_indexes WHERE schemaname = 'configured'\
+                AND indexname LIKE '%_idx_%'",
+                &[],
+            )
+            .unwrap()
+            .get(0);
+        assert_eq!(index, "name_idx_test_flags");
+        client
+            .batch_execute("DROP SCHEMA configured CASCADE")
+            .unwrap();
+    }
+}
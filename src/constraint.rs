@@ -1,3 +1,5 @@
+use std::fmt::{self, Write as _};
+
 use super::column::Column;
 
 pub trait Constraint {
@@ -8,6 +10,11 @@ pub trait Constraint {
     fn name(&self) -> &str;
 
     fn body(&self) -> String;
+
+    /// The table referenced by the constraint.
+    fn references(&self) -> Option<&str> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -60,11 +67,60 @@ impl Constraint for PrimaryKeyConstraint {
     }
 }
 
+/// What happens to the referencing rows
+/// when the referenced one is deleted or its key is updated.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ForeignKeyAction {
+    /// Fail at the end of the statement (the default).
+    NoAction,
+    /// Fail immediately even if the constraint is deferred.
+    Restrict,
+    /// Delete the referencing rows or update their values.
+    Cascade,
+    SetNull,
+    SetDefault,
+}
+
+impl fmt::Display for ForeignKeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            Self::NoAction => "NO ACTION",
+            Self::Restrict => "RESTRICT",
+            Self::Cascade => "CASCADE",
+            Self::SetNull => "SET NULL",
+            Self::SetDefault => "SET DEFAULT",
+        };
+        write!(f, "{}", action)
+    }
+}
+
+/// How the composite foreign key treats the `NULL` values of its columns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ForeignKeyMatch {
+    /// Either all the columns are `NULL` or none of them.
+    Full,
+    /// The key with any of the columns being `NULL` is not checked (the default).
+    Simple,
+}
+
+impl fmt::Display for ForeignKeyMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self {
+            Self::Full => "FULL",
+            Self::Simple => "SIMPLE",
+        };
+        write!(f, "{}", mode)
+    }
+}
+
 #[derive(Debug)]
 pub struct ForeignKeyConstraint {
     name: String,
     target_table: String,
     column_pairs: Vec<(String, String)>,
+    match_mode: Option<ForeignKeyMatch>,
+    on_delete: Option<ForeignKeyAction>,
+    on_update: Option<ForeignKeyAction>,
 }
 
 impl ForeignKeyConstraint {
@@ -80,8 +136,28 @@ impl ForeignKeyConstraint {
                 .iter()
                 .map(|(src, dest)| (src.name().to_owned(), dest.name().to_owned()))
                 .collect(),
+            match_mode: None,
+            on_delete: None,
+            on_update: None,
         }
     }
+
+    pub const fn match_mode(mut self, mode: ForeignKeyMatch) -> Self {
+        self.match_mode = Some(mode);
+        self
+    }
+
+    /// The action on deleting the referenced row.
+    pub const fn on_delete(mut self, action: ForeignKeyAction) -> Self {
+        self.on_delete = Some(action);
+        self
+    }
+
+    /// The action on updating the referenced key.
+    pub const fn on_update(mut self, action: ForeignKeyAction) -> Self {
+        self.on_update = Some(action);
+        self
+    }
 }
 
 impl Constraint for ForeignKeyConstraint {
@@ -95,12 +171,75 @@ impl Constraint for ForeignKeyConstraint {
             .iter()
             .map(|x| (x.0.as_str(), x.1.as_str()))
             .unzip();
-        format!(
+        let mut body = format!(
             "FOREIGN KEY ({}) REFERENCES {} ({})",
             src.join(", "),
             self.target_table,
             dest.join(", ")
-        )
+        );
+        if let Some(mode) = self.match_mode {
+            write!(body, " MATCH {}", mode).unwrap();
+        }
+        if let Some(action) = self.on_delete {
+            write!(body, " ON DELETE {}", action).unwrap();
+        }
+        if let Some(action) = self.on_update {
+            write!(body, " ON UPDATE {}", action).unwrap();
+        }
+        body
+    }
+
+    fn references(&self) -> Option<&str> {
+        Some(&self.target_table)
+    }
+}
+
+/// No two rows have all the column values matching with the operators,
+/// e.g. the overlapping (`&&`) time ranges of the same (`=`) room.
+///
+/// The scalar columns compared with the `=` require the `btree_gist` extension.
+#[derive(Debug)]
+pub struct ExclusionConstraint {
+    name: String,
+    elements: Vec<(String, String)>,
+    predicate: Option<String>,
+}
+
+impl ExclusionConstraint {
+    pub fn new(name: impl AsRef<str>, elements: &[(&Column, &str)]) -> Self {
+        Self {
+            name: name.as_ref().to_owned(),
+            elements: elements
+                .iter()
+                .map(|(col, operator)| (col.name().to_owned(), (*operator).to_owned()))
+                .collect(),
+            predicate: None,
+        }
+    }
+
+    /// Only check the rows matching the condition.
+    pub fn predicate(mut self, condition: impl AsRef<str>) -> Self {
+        self.predicate = Some(condition.as_ref().to_owned());
+        self
+    }
+}
+
+impl Constraint for ExclusionConstraint {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn body(&self) -> String {
+        let elements: Vec<_> = self
+            .elements
+            .iter()
+            .map(|(column, operator)| format!("{} WITH {}", column, operator))
+            .collect();
+        let mut body = format!("EXCLUDE USING gist ({})", elements.join(", "));
+        if let Some(predicate) = &self.predicate {
+            write!(body, " WHERE ({})", predicate).unwrap();
+        }
+        body
     }
 }
 
@@ -108,7 +247,6 @@ impl Constraint for ForeignKeyConstraint {
 pub struct UniqueConstraint {
     name: String,
     columns: Vec<String>,
-    // TODO
     with_nulls_non_distinct: bool,
 }
 
@@ -120,6 +258,13 @@ impl UniqueConstraint {
             with_nulls_non_distinct: false,
         }
     }
+
+    /// Treat the `NULL` values as equal, so only a single row
+    /// could have them in all the columns (requires PostgreSQL 15).
+    pub const fn nulls_not_distinct(mut self) -> Self {
+        self.with_nulls_non_distinct = true;
+        self
+    }
 }
 
 impl Constraint for UniqueConstraint {
@@ -0,0 +1,253 @@
+use std::fmt;
+
+use log::{debug, info};
+
+use crate::{
+    error::{Context as _, Error, Operation},
+    ext::SyncClient,
+    query::check_column,
+    table::Table,
+    type_helpers::quote_literal,
+};
+
+/// The table the `pg_cron` keeps the jobs in.
+const JOBS_TABLE: &str = "cron.job";
+
+/// The statement run by the scheduled job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronCommand(String);
+
+impl CronCommand {
+    /// The arbitrary SQL.
+    pub fn sql(sql: impl Into<String>) -> Self {
+        Self(sql.into())
+    }
+
+    /// Delete the rows of the table `T` whose timestamp `column`
+    /// is older than the `interval` (e.g. `"30 days"`).
+    ///
+    /// Fails if the table has no such column.
+    pub fn retention<T, const N: usize>(column: &str, interval: &str) -> Result<Self, Error>
+    where
+        T: Table<N>,
+    {
+        check_column(&T::columns(), column, "retain by")?;
+        Ok(Self(format!(
+            "DELETE FROM {} WHERE {} < now() - {}::interval",
+            T::name(),
+            column,
+            quote_literal(interval)
+        )))
+    }
+
+    /// Reclaim the space and refresh the statistics of the table `T`.
+    pub fn vacuum_analyze<T, const N: usize>() -> Self
+    where
+        T: Table<N>,
+    {
+        Self(format!("VACUUM ANALYZE {}", T::name()))
+    }
+
+    /// Recompute the materialized view
+    /// (the `concurrently` requires a unique index on the view).
+    pub fn refresh_view(view: &str, concurrently: bool) -> Self {
+        let concurrently = if concurrently { "CONCURRENTLY " } else { "" };
+        Self(format!(
+            "REFRESH MATERIALIZED VIEW {}{}",
+            concurrently, view
+        ))
+    }
+
+    pub fn as_sql(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CronCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The periodic maintenance run by the `pg_cron` extension
+/// (enabled with the `cron` feature).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronJob {
+    name: String,
+    schedule: String,
+    command: CronCommand,
+}
+
+impl CronJob {
+    /// The `schedule` is either the standard cron expression (`"0 3 * * *"`)
+    /// or the interval like `"30 seconds"`.
+    pub fn new(name: impl Into<String>, schedule: impl Into<String>, command: CronCommand) -> Self {
+        Self {
+            name: name.into(),
+            schedule: schedule.into(),
+            command,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn schedule(&self) -> &str {
+        &self.schedule
+    }
+
+    pub const fn command(&self) -> &CronCommand {
+        &self.command
+    }
+
+    const fn schedule_sql() -> &'static str {
+        "SELECT cron.schedule($1, $2, $3)"
+    }
+
+    const fn unschedule_sql() -> &'static str {
+        "SELECT cron.unschedule(jobid) FROM cron.job WHERE jobname = $1"
+    }
+
+    const fn list_sql() -> &'static str {
+        "SELECT jobname, schedule, command FROM cron.job \
+        WHERE jobname IS NOT NULL AND username = current_user ORDER BY jobname"
+    }
+}
+
+/// Create the job or replace the schedule and the command of the job with the same name.
+///
+/// Returns the identifier of the job.
+///
+/// # Errors
+///
+/// If the `pg_cron` extension is not installed
+/// or the schedule is not valid.
+pub fn schedule_job<C>(client: &mut C, job: &CronJob) -> Result<i64, Error>
+where
+    C: SyncClient,
+{
+    let query = CronJob::schedule_sql();
+    debug!("Scheduling the job {}: {}", job.name, job.command);
+    let id = client
+        .query_one(query, &[&job.name, &job.schedule, &job.command.0])
+        .and_then(|row| row.try_get(0))
+        .context(JOBS_TABLE, Operation::Insert, query)?;
+    info!("The job {} is scheduled at {:?}", job.name, job.schedule);
+    Ok(id)
+}
+
+/// Remove the job.
+///
+/// Returns `false` if there was no job with such a name.
+///
+/// # Errors
+///
+/// If the `pg_cron` extension is not installed.
+pub fn unschedule_job<C>(client: &mut C, name: &str) -> Result<bool, Error>
+where
+    C: SyncClient,
+{
+    let query = CronJob::unschedule_sql();
+    let removed = client
+        .query(query, &[&name])
+        .context(JOBS_TABLE, Operation::Delete, query)?;
+    Ok(!removed.is_empty())
+}
+
+/// The named jobs of the current user.
+///
+/// # Errors
+///
+/// If the `pg_cron` extension is not installed.
+pub fn scheduled_jobs<C>(client: &mut C) -> Result<Vec<CronJob>, Error>
+where
+    C: SyncClient,
+{
+    let query = CronJob::list_sql();
+    client
+        .query(query, &[])
+        .and_then(|rows| {
+            rows.iter()
+                .map(|row| {
+                    Ok(CronJob {
+                        name: row.try_get(0)?,
+                        schedule: row.try_get(1)?,
+                        command: CronCommand(row.try_get(2)?),
+                    })
+                })
+                .collect()
+        })
+        .context(JOBS_TABLE, Operation::Select, query)
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres::error::SqlState;
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client};
+
+    gen_table!(
+        struct Reading("cron_readings") {
+            id: i32 = Type::INT4; [primary_key()],
+            taken_at: std::time::SystemTime = Type::TIMESTAMPTZ,
+        }
+    );
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            CronCommand::retention::<Reading, 2>("taken_at", "30 days")
+                .unwrap()
+                .as_sql(),
+            "DELETE FROM cron_readings WHERE taken_at < now() - '30 days'::interval"
+        );
+        assert_eq!(
+            CronCommand::vacuum_analyze::<Reading, 2>().as_sql(),
+            "VACUUM ANALYZE cron_readings"
+        );
+        assert_eq!(
+            CronCommand::refresh_view("daily_stats", true).as_sql(),
+            "REFRESH MATERIALIZED VIEW CONCURRENTLY daily_stats"
+        );
+    }
+
+    #[test]
+    fn unknown_retention_column() {
+        let err = CronCommand::retention::<Reading, 2>("created_at", "1 day").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown column to retain by: \"created_at\""
+        );
+    }
+
+    /// The `pg_cron` can only be installed with the `shared_preload_libraries`,
+    /// so the error is checked when it is missing.
+    #[test]
+    fn without_extension() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        let installed: bool = client
+            .query_one(
+                "SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_cron')",
+                &[],
+            )
+            .unwrap()
+            .get(0);
+        if installed {
+            return;
+        }
+
+        let job = CronJob::new(
+            "cron_readings_vacuum",
+            "0 3 * * *",
+            CronCommand::vacuum_analyze::<Reading, 2>(),
+        );
+        let err = schedule_job(&mut client, &job).unwrap_err();
+        assert_eq!(err.code(), Some(&SqlState::INVALID_SCHEMA_NAME));
+        assert_eq!(err.table(), Some("cron.job"));
+    }
+}
@@ -0,0 +1,155 @@
+use rust_decimal::Decimal;
+
+use crate::column::SqlLiteral;
+
+impl SqlLiteral for Decimal {
+    fn to_literal(&self) -> String {
+        // the literal with the fractional part is the `numeric` constant
+        format!("{}::numeric", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{
+        diff, gen_table, introspect, test_helpers::get_client, ColumnBuilder, Mismatch,
+        PgTableExtension as _, Table as _, TableDef,
+    };
+
+    gen_table!(
+        #[derive(Debug, PartialEq)]
+        struct Payment("decimal_payments") {
+            id: i32 = Type::INT4; [primary_key()],
+            amount: Decimal = Type::NUMERIC; [precision(12, 2)],
+            rates: Vec<Decimal> = Type::NUMERIC_ARRAY; [precision(6, 4)],
+        }
+    );
+
+    #[test]
+    fn statements() {
+        assert_eq!(
+            Payment::create_table_sql(),
+            "CREATE TABLE IF NOT EXISTS decimal_payments \
+            (id int4 NOT NULL UNIQUE PRIMARY KEY, amount numeric(12, 2) NOT NULL, \
+            rates numeric(6, 4)[] NOT NULL);"
+        );
+        let fee = ColumnBuilder::new("fee", Type::NUMERIC)
+            .precision(5, 2)
+            .default_value(Decimal::from_str("-0.50").unwrap())
+            .finish();
+        assert_eq!(
+            fee.to_string(),
+            "fee numeric(5, 2) DEFAULT (-0.50::numeric) NOT NULL"
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The precision is only supported for the numeric columns, got float8"
+    )]
+    fn float_precision() {
+        let _ = ColumnBuilder::new("amount", Type::FLOAT8).precision(12, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid precision 2 and scale 4 of the column amount")]
+    fn scale_above_precision() {
+        let _ = ColumnBuilder::new("amount", Type::NUMERIC).precision(2, 4);
+    }
+
+    #[test]
+    fn round_trip() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS decimal_payments")
+            .unwrap();
+        client.create_table::<Payment, 3>().unwrap();
+
+        let payment = Payment {
+            id: 1,
+            amount: Decimal::from_str("9999999999.99").unwrap(),
+            rates: vec![
+                Decimal::from_str("0.1").unwrap(),
+                Decimal::from_str("-1.2345").unwrap(),
+            ],
+        };
+        client.insert_row(&payment).unwrap();
+        let mut stored = client.select_all::<Payment, 3>().unwrap();
+        // the scale of the column is applied
+        assert_eq!(stored[0].rates[0].to_string(), "0.1000");
+        stored[0].rates[0] = stored[0].rates[0].normalize();
+        assert_eq!(stored, [payment]);
+
+        // the rounding to the scale of the column
+        let rounded: Decimal = client
+            .query_scalar("SELECT 0.125::numeric(12, 2)", &[])
+            .unwrap();
+        assert_eq!(rounded.to_string(), "0.13");
+        assert!(client
+            .execute("INSERT INTO decimal_payments VALUES (2, 1e10, '{}')", &[],)
+            .is_err());
+
+        client.batch_execute("DROP TABLE decimal_payments").unwrap();
+    }
+
+    #[test]
+    fn live_precision() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS decimal_payments")
+            .unwrap();
+        client.create_table::<Payment, 3>().unwrap();
+        assert!(client.verify_table::<Payment, 3>().unwrap().is_ok());
+
+        client
+            .batch_execute(
+                "ALTER TABLE decimal_payments ALTER COLUMN amount TYPE numeric(10, 2), \
+                ALTER COLUMN rates TYPE numeric[]",
+            )
+            .unwrap();
+        let report = client.verify_table::<Payment, 3>().unwrap();
+        assert_eq!(
+            report.mismatches(),
+            [
+                Mismatch::ColumnType {
+                    column: "amount".into(),
+                    expected: "numeric(12,2)".into(),
+                    actual: "numeric(10,2)".into(),
+                },
+                Mismatch::ColumnType {
+                    column: "rates".into(),
+                    expected: "_numeric(6,4)".into(),
+                    actual: "_numeric".into(),
+                },
+            ]
+        );
+
+        let live = introspect::table(&mut client, "decimal_payments").unwrap();
+        let statements = diff(&[TableDef::of::<Payment, 3>()], live.as_slice());
+        let sql: Vec<_> = statements.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            sql,
+            [
+                "ALTER TABLE decimal_payments ALTER COLUMN amount \
+                TYPE numeric(12, 2) USING amount::numeric(12, 2)",
+                "ALTER TABLE decimal_payments ALTER COLUMN rates \
+                TYPE numeric(6, 4)[] USING rates::numeric(6, 4)[]",
+            ]
+        );
+        for statement in &statements {
+            client.batch_execute(&statement.to_string()).unwrap();
+        }
+        assert!(client.verify_table::<Payment, 3>().unwrap().is_ok());
+
+        client.batch_execute("DROP TABLE decimal_payments").unwrap();
+    }
+}
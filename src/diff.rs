@@ -0,0 +1,581 @@
+use std::fmt;
+
+use crate::{
+    column::Column,
+    config::unqualified,
+    constraint::Constraint,
+    dyn_table::DynTable,
+    function::SqlFunction,
+    introspect::{ConstraintInfo, ConstraintKind, TableInfo},
+    table::{self, Table, TableSchema},
+    type_helpers::ObjectAndCreateSql,
+};
+
+/// The definition of the table detached from its type
+/// (to compare a number of different tables at once).
+pub struct TableDef {
+    columns: Vec<Column>,
+    constraints: Vec<Box<dyn Constraint>>,
+    creation: TableCreation,
+}
+
+/// The objects to create the table with in the order of their creation
+/// (unlike the [`TableDef`] it is `Send`).
+#[derive(Debug, Clone)]
+pub(crate) struct TableCreation {
+    pub(crate) name: String,
+    pub(crate) schema: String,
+    pub(crate) types: Vec<ObjectAndCreateSql>,
+    pub(crate) create_sql: String,
+    pub(crate) storage_sql: Vec<String>,
+    pub(crate) functions: Vec<SqlFunction>,
+    pub(crate) row_security_sql: Vec<String>,
+    pub(crate) triggers_sql: Vec<String>,
+    pub(crate) indices: Vec<ObjectAndCreateSql>,
+    pub(crate) cluster_sql: Option<String>,
+}
+
+impl TableDef {
+    pub fn of<T, const N: usize>() -> Self
+    where
+        T: Table<N>,
+    {
+        let creation = TableCreation {
+            name: T::name().to_owned(),
+            schema: T::schema().to_owned(),
+            types: T::create_types_sql(),
+            create_sql: T::create_table_sql().trim_end_matches(';').to_owned(),
+            storage_sql: T::alter_storage_sql(),
+            functions: T::functions(),
+            row_security_sql: T::row_security_sql(),
+            triggers_sql: T::create_triggers_sql(),
+            indices: T::create_indices_sql(),
+            cluster_sql: T::cluster_index()
+                .map(|index| format!("ALTER TABLE {} CLUSTER ON {}", T::name(), index)),
+        };
+        Self {
+            columns: Vec::from(T::columns()),
+            constraints: T::constraints().unwrap_or_default(),
+            creation,
+        }
+    }
+
+    /// The definition of the table whose name is only known at runtime.
+    pub fn of_dyn<D, const N: usize>(table: &D) -> Self
+    where
+        D: DynTable<N> + ?Sized,
+    {
+        let creation = TableCreation {
+            name: table.name().to_owned(),
+            schema: table::schema_of(table.name()).to_owned(),
+            types: table.create_types_sql(),
+            create_sql: table.create_table_sql().trim_end_matches(';').to_owned(),
+            storage_sql: table.alter_storage_sql(),
+            functions: table.functions(),
+            row_security_sql: table.row_security_sql(),
+            triggers_sql: table.create_triggers_sql(),
+            indices: table.create_indices_sql(),
+            cluster_sql: table
+                .cluster_index()
+                .map(|index| format!("ALTER TABLE {} CLUSTER ON {}", table.name(), index)),
+        };
+        Self {
+            columns: Vec::from(table.columns()),
+            constraints: table.constraints(),
+            creation,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.creation.name
+    }
+
+    pub(crate) fn schema(&self) -> &str {
+        &self.creation.schema
+    }
+
+    pub(crate) fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub(crate) fn constraints(&self) -> &[Box<dyn Constraint>] {
+        &self.constraints
+    }
+
+    /// The other tables referenced with the foreign keys.
+    pub(crate) fn dependencies(&self) -> Vec<String> {
+        let columns = self
+            .columns
+            .iter()
+            .filter_map(|col| col.foreign_key().map(|(table, _)| table));
+        let constraints = self
+            .constraints
+            .iter()
+            .filter_map(|c| c.references().map(str::to_owned));
+        columns
+            .chain(constraints)
+            .filter(|table| *table != self.creation.name)
+            .collect()
+    }
+
+    pub(crate) fn types(&self) -> &[ObjectAndCreateSql] {
+        &self.creation.types
+    }
+
+    pub(crate) const fn creation(&self) -> &TableCreation {
+        &self.creation
+    }
+
+    pub(crate) fn into_creation(self) -> TableCreation {
+        self.creation
+    }
+
+    /// The statements to create the table with its indices.
+    pub(crate) fn create_script(&self) -> Vec<String> {
+        let creation = &self.creation;
+        std::iter::once(creation.create_sql.clone())
+            .chain(creation.storage_sql.iter().cloned())
+            .chain(
+                creation
+                    .functions
+                    .iter()
+                    .map(SqlFunction::create_if_missing_sql),
+            )
+            .chain(creation.row_security_sql.iter().cloned())
+            .chain(creation.triggers_sql.iter().cloned())
+            .chain(
+                creation
+                    .indices
+                    .iter()
+                    .map(|idx| idx.create_sql().to_owned()),
+            )
+            .chain(creation.cluster_sql.clone())
+            .collect()
+    }
+
+    fn create(&self) -> Vec<AlterStatement> {
+        let create = AlterStatement::CreateTable {
+            table: self.creation.name.clone(),
+            sql: self.creation.create_sql.clone(),
+        };
+        let indices = self
+            .creation
+            .indices
+            .iter()
+            .map(|idx| AlterStatement::CreateIndex {
+                name: idx.name().to_owned(),
+                sql: idx.create_sql().to_owned(),
+            });
+        std::iter::once(create).chain(indices).collect()
+    }
+
+    /// The constraint is either one of the named table constraints
+    /// or corresponds to the column properties.
+    fn is_defined(&self, constraint: &ConstraintInfo) -> bool {
+        if self
+            .constraints
+            .iter()
+            .any(|c| c.name().to_lowercase() == constraint.name)
+        {
+            return true;
+        }
+        self.columns.iter().any(|col| {
+            column_constraints(col)
+                .into_iter()
+                .any(|(kind, references)| matches_column(constraint, col, kind, references))
+        })
+    }
+
+    fn alter(&self, live: &TableInfo) -> Vec<AlterStatement> {
+        let table = &self.creation.name;
+        let mut drops = vec![];
+        let mut changes = vec![];
+        let mut adds = vec![];
+
+        for constraint in &live.constraints {
+            if !self.is_defined(constraint) {
+                drops.push(AlterStatement::DropConstraint {
+                    table: table.clone(),
+                    name: constraint.name.clone(),
+                });
+            }
+        }
+        let free_indices = live
+            .indexes
+            .iter()
+            .filter(|idx| !live.constraints.iter().any(|c| c.name == idx.name));
+        for idx in free_indices {
+            if !self
+                .creation
+                .indices
+                .iter()
+                .any(|index| index.name() == idx.name)
+            {
+                drops.push(AlterStatement::DropIndex {
+                    name: idx.name.clone(),
+                });
+            }
+        }
+
+        for col in &self.columns {
+            let Some(live_col) = live.column(col.name()) else {
+                changes.push(AlterStatement::AddColumn {
+                    table: table.clone(),
+                    definition: col.to_string(),
+                });
+                continue;
+            };
+            if live_col.catalog_type_name() != col.catalog_type_name() {
+                changes.push(AlterStatement::AlterColumnType {
+                    table: table.clone(),
+                    column: col.name().to_owned(),
+                    ty: col.type_sql(),
+                });
+            }
+            match (live_col.is_nullable(), col.is_nullable()) {
+                (true, false) => changes.push(AlterStatement::SetNotNull {
+                    table: table.clone(),
+                    column: col.name().to_owned(),
+                }),
+                (false, true) => changes.push(AlterStatement::DropNotNull {
+                    table: table.clone(),
+                    column: col.name().to_owned(),
+                }),
+                _ => {}
+            }
+            for (kind, references) in column_constraints(col) {
+                let exists = live
+                    .constraints
+                    .iter()
+                    .any(|c| matches_column(c, col, kind, references.clone()));
+                if !exists {
+                    adds.push(AlterStatement::AddConstraint {
+                        table: table.clone(),
+                        definition: column_constraint_sql(col, kind),
+                    });
+                }
+            }
+        }
+        for live_col in &live.columns {
+            if !self.columns.iter().any(|col| col.name() == live_col.name()) {
+                changes.push(AlterStatement::DropColumn {
+                    table: table.clone(),
+                    column: live_col.name().to_owned(),
+                });
+            }
+        }
+
+        for constraint in &self.constraints {
+            let name = constraint.name().to_lowercase();
+            if !live.constraints.iter().any(|c| c.name == name) {
+                adds.push(AlterStatement::AddConstraint {
+                    table: table.clone(),
+                    definition: constraint.as_sql(),
+                });
+            }
+        }
+        for index in &self.creation.indices {
+            if !live.indexes.iter().any(|idx| idx.name == index.name()) {
+                adds.push(AlterStatement::CreateIndex {
+                    name: index.name().to_owned(),
+                    sql: index.create_sql().to_owned(),
+                });
+            }
+        }
+
+        drops.into_iter().chain(changes).chain(adds).collect()
+    }
+}
+
+/// The single-column constraints implied by the column properties.
+fn column_constraints(col: &Column) -> Vec<(ConstraintKind, Option<(String, String)>)> {
+    let mut constraints = vec![];
+    if col.is_primary_key() {
+        constraints.push((ConstraintKind::PrimaryKey, None));
+    } else if col.is_unique() {
+        constraints.push((ConstraintKind::Unique, None));
+    }
+    if let Some(references) = col.foreign_key() {
+        constraints.push((ConstraintKind::ForeignKey, Some(references)));
+    }
+    if col.check().is_some() {
+        constraints.push((ConstraintKind::Check, None));
+    }
+    constraints
+}
+
+fn matches_column(
+    constraint: &ConstraintInfo,
+    col: &Column,
+    kind: ConstraintKind,
+    references: Option<(String, String)>,
+) -> bool {
+    if !constraint.is_on_single(kind, col.name()) {
+        return false;
+    }
+    match (&constraint.references, references) {
+        (Some((live_table, live_columns)), Some((table, column))) => {
+            unqualified(live_table) == table && *live_columns == [column]
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn column_constraint_sql(col: &Column, kind: ConstraintKind) -> String {
+    match (kind, col.references_sql()) {
+        (ConstraintKind::PrimaryKey, _) => format!("PRIMARY KEY ({})", col.name()),
+        (ConstraintKind::Check, _) => format!("CHECK ({})", col.check().unwrap_or_default()),
+        (ConstraintKind::ForeignKey, Some(references)) => {
+            format!("FOREIGN KEY ({}) {}", col.name(), references)
+        }
+        _ if col.is_nulls_not_distinct() => format!("UNIQUE NULLS NOT DISTINCT ({})", col.name()),
+        _ => format!("UNIQUE ({})", col.name()),
+    }
+}
+
+/// The change to make the live schema match the definitions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlterStatement {
+    CreateTable {
+        table: String,
+        sql: String,
+    },
+    DropTable {
+        table: String,
+    },
+    AddColumn {
+        table: String,
+        definition: String,
+    },
+    DropColumn {
+        table: String,
+        column: String,
+    },
+    AlterColumnType {
+        table: String,
+        column: String,
+        ty: String,
+    },
+    SetNotNull {
+        table: String,
+        column: String,
+    },
+    DropNotNull {
+        table: String,
+        column: String,
+    },
+    AddConstraint {
+        table: String,
+        definition: String,
+    },
+    DropConstraint {
+        table: String,
+        name: String,
+    },
+    CreateIndex {
+        name: String,
+        sql: String,
+    },
+    DropIndex {
+        name: String,
+    },
+}
+
+impl AlterStatement {
+    /// Whether applying the statement could lose the data.
+    pub const fn is_destructive(&self) -> bool {
+        matches!(
+            self,
+            Self::DropTable { .. } | Self::DropColumn { .. } | Self::AlterColumnType { .. }
+        )
+    }
+}
+
+impl fmt::Display for AlterStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CreateTable { sql, .. } | Self::CreateIndex { sql, .. } => write!(f, "{}", sql),
+            Self::DropTable { table } => write!(f, "DROP TABLE {}", table),
+            Self::AddColumn { table, definition } => {
+                write!(f, "ALTER TABLE {} ADD COLUMN {}", table, definition)
+            }
+            Self::DropColumn { table, column } => {
+                write!(f, "ALTER TABLE {} DROP COLUMN {}", table, column)
+            }
+            Self::AlterColumnType { table, column, ty } => write!(
+                f,
+                "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::{}",
+                table, column, ty, column, ty
+            ),
+            Self::SetNotNull { table, column } => {
+                write!(
+                    f,
+                    "ALTER TABLE {} ALTER COLUMN {} SET NOT NULL",
+                    table, column
+                )
+            }
+            Self::DropNotNull { table, column } => {
+                write!(
+                    f,
+                    "ALTER TABLE {} ALTER COLUMN {} DROP NOT NULL",
+                    table, column
+                )
+            }
+            Self::AddConstraint { table, definition } => {
+                write!(f, "ALTER TABLE {} ADD {}", table, definition)
+            }
+            Self::DropConstraint { table, name } => {
+                write!(f, "ALTER TABLE {} DROP CONSTRAINT {}", table, name)
+            }
+            Self::DropIndex { name } => write!(f, "DROP INDEX {}", name),
+        }
+    }
+}
+
+/// Compute the statements (without executing them) to bring the `live` schema
+/// (e.g. from the [`introspect::schema`][crate::introspect::schema])
+/// to the `defined` tables.
+///
+/// The live tables which are not defined are dropped,
+/// the custom types are not compared.
+pub fn diff(defined: &[TableDef], live: &[TableInfo]) -> Vec<AlterStatement> {
+    let mut statements = vec![];
+    for def in defined {
+        match live
+            .iter()
+            .find(|info| unqualified(&info.name) == def.name())
+        {
+            Some(info) => statements.extend(def.alter(info)),
+            None => statements.extend(def.create()),
+        }
+    }
+    for info in live {
+        if !defined
+            .iter()
+            .any(|def| unqualified(&info.name) == def.name())
+        {
+            statements.push(AlterStatement::DropTable {
+                table: info.name.clone(),
+            });
+        }
+    }
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{
+        gen_table, introspect, test_helpers::get_client, CheckConstraint, ColumnBuilder,
+        PgTableExtension as _,
+    };
+
+    gen_table!(
+        struct Customer("customers") {
+            id: i32 = Type::INT4; [primary_key()],
+            email: String = Type::TEXT; [unique(), index()],
+        }
+    );
+
+    gen_table!(
+        struct Invoice("invoices") {
+            id: i64 = Type::INT8; [primary_key()],
+            customer_id: i32 = Type::INT4; [foreign_key(Customer::name(), "id")],
+            total: i64 = Type::INT8,
+            memo: Option<String> = Type::TEXT; [nullable()],
+            => constraints = [
+                CheckConstraint::new("positive_total", "total > 0"),
+            ]
+        }
+    );
+
+    fn old_invoices() -> TableInfo {
+        TableInfo {
+            name: "public.invoices".into(),
+            columns: vec![
+                ColumnBuilder::new("id", Type::INT4).primary_key().finish(),
+                ColumnBuilder::new("total", Type::INT8).nullable().finish(),
+                ColumnBuilder::new("legacy", Type::BOOL).finish(),
+            ],
+            constraints: vec![ConstraintInfo {
+                name: "invoices_pkey".into(),
+                kind: ConstraintKind::PrimaryKey,
+                columns: vec!["id".into()],
+                references: None,
+                definition: "PRIMARY KEY (id)".into(),
+            }],
+            indexes: vec![],
+        }
+    }
+
+    #[test]
+    fn changed_table() {
+        let statements = diff(&[TableDef::of::<Invoice, 4>()], &[old_invoices()]);
+        let sql: Vec<_> = statements.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            sql,
+            [
+                "ALTER TABLE invoices ALTER COLUMN id TYPE int8 USING id::int8",
+                "ALTER TABLE invoices ADD COLUMN customer_id int4 NOT NULL REFERENCES customers(id)",
+                "ALTER TABLE invoices ALTER COLUMN total SET NOT NULL",
+                "ALTER TABLE invoices ADD COLUMN memo text NULL",
+                "ALTER TABLE invoices DROP COLUMN legacy",
+                "ALTER TABLE invoices ADD CONSTRAINT positive_total CHECK (total > 0)",
+            ]
+        );
+        let destructive: Vec<_> = statements
+            .iter()
+            .map(AlterStatement::is_destructive)
+            .collect();
+        assert_eq!(destructive, [true, false, false, false, true, false]);
+    }
+
+    #[test]
+    fn new_and_removed_tables() {
+        let statements = diff(&[TableDef::of::<Customer, 2>()], &[old_invoices()]);
+        let sql: Vec<_> = statements.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            sql,
+            [
+                "CREATE TABLE IF NOT EXISTS customers \
+                (id int4 NOT NULL UNIQUE PRIMARY KEY, email text NOT NULL UNIQUE)",
+                "CREATE INDEX IF NOT EXISTS email_idx_customers ON customers USING btree (email)",
+                "DROP TABLE public.invoices",
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_to_live_schema() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP SCHEMA IF EXISTS diffed CASCADE; \
+                CREATE SCHEMA diffed; \
+                SET search_path = diffed; \
+                CREATE TABLE invoices (id int4 PRIMARY KEY, total int8, legacy bool UNIQUE); \
+                CREATE INDEX invoices_total ON invoices (total)",
+            )
+            .unwrap();
+        client.create_table::<Customer, 2>().unwrap();
+
+        let defined = [TableDef::of::<Customer, 2>(), TableDef::of::<Invoice, 4>()];
+        let live = introspect::schema(&mut client, "diffed").unwrap();
+        let statements = diff(&defined, &live);
+        assert_eq!(statements.len(), 8, "{:#?}", statements);
+        for statement in &statements {
+            client.batch_execute(&statement.to_string()).unwrap();
+        }
+
+        let live = introspect::schema(&mut client, "diffed").unwrap();
+        assert_eq!(diff(&defined, &live), []);
+        client
+            .batch_execute("RESET search_path; DROP SCHEMA diffed CASCADE")
+            .unwrap();
+    }
+}
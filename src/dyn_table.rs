@@ -0,0 +1,348 @@
+use std::{fmt, marker::PhantomData};
+
+use itertools::Itertools as _;
+
+use crate::{
+    column::Column,
+    constraint::Constraint,
+    diff::TableDef,
+    function::SqlFunction,
+    index::Index,
+    policy::{self, Policy},
+    query::SelectOptions,
+    table::{self, Table},
+    table_options::TableOptions,
+    trigger::Trigger,
+    type_helpers::{validate_name, ObjectAndCreateSql},
+};
+
+/// The table whose name is only known at runtime
+/// (e.g. the per-tenant copies `events_{tenant}` of the same table).
+///
+/// Use the [`TableRef`] to target the existing [`Table`] definition
+/// with another name.
+pub trait DynTable<const N: usize> {
+    fn name(&self) -> &str;
+
+    fn columns(&self) -> [Column; N];
+
+    fn constraints(&self) -> Vec<Box<dyn Constraint>> {
+        vec![]
+    }
+
+    fn options(&self) -> TableOptions {
+        TableOptions::default()
+    }
+
+    fn create_types_sql(&self) -> Vec<ObjectAndCreateSql> {
+        self.columns()
+            .iter()
+            .flat_map(|col| col.create_types_sql())
+            .unique()
+            .collect()
+    }
+
+    fn create_table_sql(&self) -> String {
+        table::create_table_sql(
+            self.name(),
+            &self.columns(),
+            &self.constraints(),
+            &self.options(),
+        )
+    }
+
+    fn indexes(&self) -> Vec<Index> {
+        vec![]
+    }
+
+    fn create_indices_sql(&self) -> Vec<ObjectAndCreateSql> {
+        table::create_indices_sql(
+            self.name(),
+            &self.columns(),
+            self.indexes(),
+            &self.options(),
+        )
+    }
+
+    /// The storage settings of the columns applied after the table creation.
+    fn alter_storage_sql(&self) -> Vec<String> {
+        self.columns()
+            .iter()
+            .filter_map(|col| col.alter_storage_sql(self.name()))
+            .collect()
+    }
+
+    /// The functions created with the table unless they exist.
+    fn functions(&self) -> Vec<SqlFunction> {
+        vec![]
+    }
+
+    fn policies(&self) -> Vec<Policy> {
+        vec![]
+    }
+
+    fn row_security_sql(&self) -> Vec<String> {
+        policy::row_security_sql(self.name(), &self.policies())
+    }
+
+    fn triggers(&self) -> Vec<Trigger> {
+        vec![]
+    }
+
+    fn create_triggers_sql(&self) -> Vec<String> {
+        self.triggers()
+            .iter()
+            .map(|trigger| trigger.create_sql(self.name()))
+            .collect()
+    }
+
+    fn cluster_on(&self) -> Option<&str> {
+        None
+    }
+
+    /// # Panics
+    ///
+    /// If the column to [`cluster_on`][Self::cluster_on] is unknown or has no index.
+    fn cluster_index(&self) -> Option<String> {
+        let column = self.cluster_on()?;
+        Some(table::cluster_index(self.name(), &self.columns(), column))
+    }
+
+    /// The complete definition to create the table with.
+    fn table_def(&self) -> TableDef {
+        TableDef::of_dyn(self)
+    }
+
+    fn insert_many_sql(&self, rows_number: usize) -> String {
+        table::insert_many_sql(self.name(), &self.columns(), rows_number)
+    }
+
+    /// Fails if the `options` refer to the unknown columns.
+    fn select_sql(
+        &self,
+        condition: Option<&str>,
+        options: &SelectOptions,
+    ) -> Result<String, crate::Error> {
+        table::select_sql(self.name(), &self.columns(), condition, options)
+    }
+}
+
+/// The columns and the constraints of the table `T` under another name.
+pub struct TableRef<T, const N: usize> {
+    name: String,
+    table: PhantomData<fn() -> T>,
+}
+
+impl<T, const N: usize> TableRef<T, N>
+where
+    T: Table<N>,
+{
+    /// # Panics
+    ///
+    /// If the name (or its schema) is not a valid lowercase identifier.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        for part in name.splitn(2, '.') {
+            validate_name("table", part);
+        }
+        Self {
+            name,
+            table: PhantomData,
+        }
+    }
+
+    /// The name of the `T` table followed with the `_{suffix}`.
+    ///
+    /// # Panics
+    ///
+    /// If the resulting name is not a valid lowercase identifier.
+    pub fn suffixed(suffix: &str) -> Self {
+        Self::new(format!("{}_{}", T::name(), suffix))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<T, const N: usize> Clone for TableRef<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            table: PhantomData,
+        }
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for TableRef<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TableRef").field(&self.name).finish()
+    }
+}
+
+impl<T, const N: usize> DynTable<N> for TableRef<T, N>
+where
+    T: Table<N>,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn columns(&self) -> [Column; N] {
+        T::columns()
+    }
+
+    fn constraints(&self) -> Vec<Box<dyn Constraint>> {
+        T::constraints().unwrap_or_default()
+    }
+
+    fn options(&self) -> TableOptions {
+        T::options()
+    }
+
+    fn indexes(&self) -> Vec<Index> {
+        T::indexes()
+    }
+
+    fn functions(&self) -> Vec<SqlFunction> {
+        T::functions()
+    }
+
+    fn policies(&self) -> Vec<Policy> {
+        T::policies()
+    }
+
+    fn triggers(&self) -> Vec<Trigger> {
+        T::triggers()
+    }
+
+    fn cluster_on(&self) -> Option<&str> {
+        T::cluster_on()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, param, test_helpers::get_client, Expr, PgTableExtension as _};
+
+    gen_table!(
+        struct Event("events") {
+            id: i32 = Type::INT4; [primary_key()],
+            kind: String = Type::TEXT; [index()],
+        }
+    );
+
+    #[test]
+    fn tenant_sql() {
+        let acme = TableRef::<Event, 2>::suffixed("acme");
+        assert_eq!(DynTable::name(&acme), "events_acme");
+        assert_eq!(
+            acme.create_table_sql(),
+            "CREATE TABLE IF NOT EXISTS events_acme (id int4 NOT NULL UNIQUE PRIMARY KEY, kind text NOT NULL);"
+        );
+        assert_eq!(
+            acme.insert_many_sql(1),
+            "INSERT INTO events_acme (id, kind) VALUES ($1, $2);"
+        );
+        assert_eq!(
+            acme.select_sql(Some("id > $1"), &SelectOptions::default())
+                .unwrap(),
+            "SELECT * FROM events_acme WHERE id > $1"
+        );
+        let indices = acme.create_indices_sql();
+        assert!(indices[0].create_sql().contains(" ON events_acme "));
+    }
+
+    #[test]
+    fn tenants() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        let tenants = ["acme", "globex"].map(TableRef::<Event, 2>::suffixed);
+        for tenant in &tenants {
+            client
+                .batch_execute(&format!("DROP TABLE IF EXISTS {}", tenant.name()))
+                .unwrap();
+            client.create_dyn_table(tenant).unwrap();
+        }
+
+        let events = [(1, "login"), (2, "logout")].map(|(id, kind)| Event {
+            id,
+            kind: kind.into(),
+        });
+        let inserted = client.insert_dyn_rows(&tenants[0], &events).unwrap();
+        assert_eq!(inserted, 2);
+        client.insert_dyn_rows(&tenants[1], &events[1..]).unwrap();
+
+        let logins = Expr::col("kind").eq(param(1));
+        let acme: Vec<Event> = client
+            .select_dyn(
+                &tenants[0],
+                Some(&logins),
+                &[&"login"],
+                &SelectOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(acme.len(), 1);
+        let globex: Vec<Event> = client
+            .select_dyn(
+                &tenants[1],
+                Some(&logins),
+                &[&"login"],
+                &SelectOptions::default(),
+            )
+            .unwrap();
+        assert!(globex.is_empty());
+
+        for tenant in &tenants {
+            client
+                .batch_execute(&format!("DROP TABLE {}", tenant.name()))
+                .unwrap();
+        }
+    }
+
+    gen_table!(
+        struct Visit("visits") {
+            id: i32 = Type::INT4; [primary_key()],
+            day: i32 = Type::INT4; [index()],
+            => cluster_on = "day"
+            => registered = false
+        }
+    );
+
+    #[test]
+    fn same_as_static() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP SCHEMA IF EXISTS dyn_tenants CASCADE")
+            .unwrap();
+        let tenant = TableRef::<Visit, 2>::new("dyn_tenants.visits_acme");
+        assert_eq!(tenant.table_def().schema(), "dyn_tenants");
+        // the schema is created along with the table
+        client.create_dyn_table(&tenant).unwrap();
+        client.create_dyn_table(&tenant).unwrap();
+
+        let clustered: bool = client
+            .query_scalar(
+                "SELECT indisclustered FROM pg_index \
+                WHERE indrelid = 'dyn_tenants.visits_acme'::regclass AND indisclustered",
+                &[],
+            )
+            .unwrap();
+        assert!(clustered);
+        client
+            .batch_execute("DROP SCHEMA dyn_tenants CASCADE")
+            .unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid table name \"events_acme; DROP TABLE users\"")]
+    fn invalid_name() {
+        let _ = TableRef::<Event, 2>::suffixed("acme; DROP TABLE users");
+    }
+}
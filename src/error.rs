@@ -0,0 +1,163 @@
+use std::{error::Error as StdError, fmt};
+
+use tokio_postgres::{error::SqlState, Error as DbError};
+
+/// The kind of the statement generated for the table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operation {
+    Create,
+    Alter,
+    Insert,
+    Select,
+    Update,
+    Delete,
+    /// The `CLUSTER`, `VACUUM`, etc.
+    Maintenance,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            Self::Create => "create",
+            Self::Alter => "alter",
+            Self::Insert => "insert into",
+            Self::Select => "select from",
+            Self::Update => "update",
+            Self::Delete => "delete from",
+            Self::Maintenance => "maintain",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+/// The failure of the database communication.
+#[derive(Debug)]
+pub enum Error {
+    /// The statement generated for the table has failed.
+    Query {
+        table: String,
+        operation: Operation,
+        sql: String,
+        source: DbError,
+    },
+    /// The failure without the table context (e.g. the connection is lost
+    /// or the arbitrary query has failed).
+    Db(DbError),
+    /// The [`NamedQuery`][crate::NamedQuery] is unknown
+    /// or run with the wrong number of parameters (nothing is sent to the server).
+    NamedQuery {
+        table: String,
+        name: String,
+        reason: String,
+    },
+    /// The statement refers to a column the table does not have
+    /// (nothing is sent to the server).
+    UnknownColumn {
+        column: String,
+        purpose: &'static str,
+    },
+}
+
+impl Error {
+    /// The failure reported by the driver (if the query was sent at all).
+    pub fn db_error(&self) -> Option<&DbError> {
+        match self {
+            Self::Query { source, .. } => Some(source),
+            Self::Db(err) => Some(err),
+            Self::NamedQuery { .. } | Self::UnknownColumn { .. } => None,
+        }
+    }
+
+    pub fn into_db_error(self) -> Option<DbError> {
+        match self {
+            Self::Query { source, .. } => Some(source),
+            Self::Db(err) => Some(err),
+            Self::NamedQuery { .. } | Self::UnknownColumn { .. } => None,
+        }
+    }
+
+    /// The SQLSTATE of the error reported by the server.
+    pub fn code(&self) -> Option<&SqlState> {
+        self.db_error()?.code()
+    }
+
+    pub fn table(&self) -> Option<&str> {
+        match self {
+            Self::Query { table, .. } | Self::NamedQuery { table, .. } => Some(table),
+            Self::Db(_) | Self::UnknownColumn { .. } => None,
+        }
+    }
+
+    pub fn operation(&self) -> Option<Operation> {
+        match self {
+            Self::Query { operation, .. } => Some(*operation),
+            Self::NamedQuery { .. } => Some(Operation::Select),
+            Self::Db(_) | Self::UnknownColumn { .. } => None,
+        }
+    }
+
+    pub fn sql(&self) -> Option<&str> {
+        match self {
+            Self::Query { sql, .. } => Some(sql),
+            Self::Db(_) | Self::NamedQuery { .. } | Self::UnknownColumn { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Query {
+                table,
+                operation,
+                sql,
+                source,
+            } => write!(
+                f,
+                "Failed to {} the table {}: {} (query: {})",
+                operation, table, source, sql
+            ),
+            Self::Db(err) => write!(f, "{}", err),
+            Self::NamedQuery {
+                table,
+                name,
+                reason,
+            } => write!(
+                f,
+                "Failed to run the query {:?} of the table {}: {}",
+                name, table, reason
+            ),
+            Self::UnknownColumn { column, purpose } => {
+                write!(f, "Unknown column to {}: {:?}", purpose, column)
+            }
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.db_error().map(|err| err as _)
+    }
+}
+
+impl From<DbError> for Error {
+    fn from(err: DbError) -> Self {
+        Self::Db(err)
+    }
+}
+
+/// Attach the table context to the driver errors.
+pub(crate) trait Context<T> {
+    fn context(self, table: &str, operation: Operation, sql: &str) -> Result<T, Error>;
+}
+
+impl<T> Context<T> for Result<T, DbError> {
+    fn context(self, table: &str, operation: Operation, sql: &str) -> Result<T, Error> {
+        self.map_err(|source| Error::Query {
+            table: table.to_owned(),
+            operation,
+            sql: sql.to_owned(),
+            source,
+        })
+    }
+}
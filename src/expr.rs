@@ -0,0 +1,486 @@
+use std::{
+    fmt::{self, Display},
+    ops::Not,
+};
+
+use itertools::Itertools as _;
+use postgres_types::Type as DbType;
+
+use crate::{column::Column, error::Error, query::check_column, type_helpers::quote_literal};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BinaryOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Like,
+    /// The JSON (or array) containment `@>`.
+    Contains,
+    /// The JSON path returning any item `@?`.
+    PathExists,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    const fn is_logical(self) -> bool {
+        matches!(self, Self::And | Self::Or)
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            BinaryOp::Eq => "=",
+            BinaryOp::NotEq => "<>",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEq => ">=",
+            BinaryOp::Like => "LIKE",
+            BinaryOp::Contains => "@>",
+            BinaryOp::PathExists => "@?",
+            BinaryOp::And => "AND",
+            BinaryOp::Or => "OR",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+/// Expression to build a _WHERE_ condition without writing the SQL by hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Column(String),
+    Param(usize),
+    /// Piece of SQL inserted as is.
+    Raw(String),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+    IsNull(Box<Expr>),
+    IsNotNull(Box<Expr>),
+    Not(Box<Expr>),
+    /// Function call (including aggregate functions).
+    Func(String, Vec<Expr>),
+    /// Ordered-set aggregate: `func(args) WITHIN GROUP (ORDER BY expr)`.
+    WithinGroup(String, Vec<Expr>, Box<Expr>),
+    /// Type cast: `expr::type`.
+    Cast(Box<Expr>, String),
+}
+
+/// Positional parameter placeholder (`$1`, `$2`, ...).
+pub fn param(index: usize) -> Expr {
+    assert!(index > 0, "Parameters are numbered starting from 1");
+    Expr::Param(index)
+}
+
+impl Expr {
+    pub fn col(name: impl AsRef<str>) -> Self {
+        Self::Column(name.as_ref().to_owned())
+    }
+
+    pub fn raw(sql: impl AsRef<str>) -> Self {
+        Self::Raw(sql.as_ref().to_owned())
+    }
+
+    fn binary(self, op: BinaryOp, other: impl Into<Self>) -> Self {
+        Self::Binary(Box::new(self), op, Box::new(other.into()))
+    }
+
+    pub fn eq(self, other: impl Into<Self>) -> Self {
+        self.binary(BinaryOp::Eq, other)
+    }
+
+    pub fn ne(self, other: impl Into<Self>) -> Self {
+        self.binary(BinaryOp::NotEq, other)
+    }
+
+    pub fn lt(self, other: impl Into<Self>) -> Self {
+        self.binary(BinaryOp::Lt, other)
+    }
+
+    pub fn le(self, other: impl Into<Self>) -> Self {
+        self.binary(BinaryOp::LtEq, other)
+    }
+
+    pub fn gt(self, other: impl Into<Self>) -> Self {
+        self.binary(BinaryOp::Gt, other)
+    }
+
+    pub fn ge(self, other: impl Into<Self>) -> Self {
+        self.binary(BinaryOp::GtEq, other)
+    }
+
+    pub fn like(self, pattern: impl Into<Self>) -> Self {
+        self.binary(BinaryOp::Like, pattern)
+    }
+
+    /// The `jsonb` value contains the other one (`expr @> other`),
+    /// e.g. `{"tags": ["a"]}` matches the documents having the tag `a` among others.
+    pub fn jsonb_contains(self, other: impl Into<Self>) -> Self {
+        self.binary(BinaryOp::Contains, other)
+    }
+
+    /// The JSON path (e.g. `$.a.b` or `$.tags[*] ? (@ == "a")`)
+    /// returns any item for the `jsonb` value.
+    pub fn jsonb_path(self, path: impl AsRef<str>) -> Self {
+        let path = format!("{}::jsonpath", quote_literal(path.as_ref()));
+        self.binary(BinaryOp::PathExists, Self::Raw(path))
+    }
+
+    pub fn and(self, other: impl Into<Self>) -> Self {
+        self.binary(BinaryOp::And, other)
+    }
+
+    pub fn or(self, other: impl Into<Self>) -> Self {
+        self.binary(BinaryOp::Or, other)
+    }
+
+    pub fn is_null(self) -> Self {
+        Self::IsNull(Box::new(self))
+    }
+
+    pub fn is_not_null(self) -> Self {
+        Self::IsNotNull(Box::new(self))
+    }
+
+    /// Check the column is one of the `values` passed as an array parameter:
+    /// `column = ANY($index)`.
+    ///
+    /// For the empty `values` the condition is known to be `FALSE`,
+    /// but the parameter is still referenced (`FALSE AND column = ANY($index)`)
+    /// so the same parameters could be passed to the query in both cases.
+    ///
+    /// # Panics
+    ///
+    /// If the `index` is zero.
+    pub fn in_list<T>(column: impl AsRef<str>, values: &[T], index: usize) -> Self {
+        let any = Self::col(column).eq(Self::func("ANY", [param(index)]));
+        if values.is_empty() {
+            Self::raw("FALSE").and(any)
+        } else {
+            any
+        }
+    }
+
+    pub fn func(name: impl AsRef<str>, args: impl IntoIterator<Item = Self>) -> Self {
+        Self::Func(name.as_ref().to_owned(), args.into_iter().collect())
+    }
+
+    /// Number of rows: `count(*)`.
+    pub fn count_all() -> Self {
+        Self::func("count", [Self::raw("*")])
+    }
+
+    /// Number of non-NULL values.
+    pub fn count(self) -> Self {
+        Self::func("count", [self])
+    }
+
+    pub fn sum(self) -> Self {
+        Self::func("sum", [self])
+    }
+
+    pub fn avg(self) -> Self {
+        Self::func("avg", [self])
+    }
+
+    pub fn min(self) -> Self {
+        Self::func("min", [self])
+    }
+
+    pub fn max(self) -> Self {
+        Self::func("max", [self])
+    }
+
+    /// Sample standard deviation.
+    ///
+    /// The result is `numeric` for the integer and numeric arguments,
+    /// use the [`Self::cast`] to decode it as `f64`.
+    pub fn stddev(self) -> Self {
+        Self::func("stddev", [self])
+    }
+
+    /// Sample variance.
+    ///
+    /// The result is `numeric` for the integer and numeric arguments,
+    /// use the [`Self::cast`] to decode it as `f64`.
+    pub fn variance(self) -> Self {
+        Self::func("variance", [self])
+    }
+
+    /// Continuous percentile (`double precision`) interpolated between the values:
+    /// `percentile_cont(fraction) WITHIN GROUP (ORDER BY expr)`.
+    ///
+    /// # Panics
+    ///
+    /// If the `fraction` is not in the range `0..=1`.
+    pub fn percentile_cont(self, fraction: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&fraction),
+            "Percentile fraction should be between 0 and 1, got {}",
+            fraction
+        );
+        Self::WithinGroup(
+            "percentile_cont".to_owned(),
+            vec![Self::raw(fraction.to_string())],
+            Box::new(self),
+        )
+    }
+
+    /// The most frequent value having the type of the expression:
+    /// `mode() WITHIN GROUP (ORDER BY expr)`.
+    pub fn mode(self) -> Self {
+        Self::WithinGroup("mode".to_owned(), vec![], Box::new(self))
+    }
+
+    pub fn cast(self, ty: &DbType) -> Self {
+        Self::Cast(Box::new(self), ty.name().to_owned())
+    }
+
+    /// All the column names used in the expression.
+    pub fn columns(&self) -> Vec<&str> {
+        let mut columns = Vec::new();
+        self.visit(&mut |expr| {
+            if let Self::Column(name) = expr {
+                columns.push(name.as_str());
+            }
+        });
+        columns
+    }
+
+    /// The number of parameters required by the expression,
+    /// i.e. the biggest placeholder index.
+    pub fn params_count(&self) -> usize {
+        let mut count = 0;
+        self.visit(&mut |expr| {
+            if let Self::Param(index) = expr {
+                count = count.max(*index);
+            }
+        });
+        count
+    }
+
+    fn visit<'a>(&'a self, f: &mut impl FnMut(&'a Self)) {
+        f(self);
+        match self {
+            Self::Column(_) | Self::Param(_) | Self::Raw(_) => {}
+            Self::Binary(left, _, right) => {
+                left.visit(f);
+                right.visit(f);
+            }
+            Self::IsNull(inner)
+            | Self::IsNotNull(inner)
+            | Self::Not(inner)
+            | Self::Cast(inner, _) => inner.visit(f),
+            Self::Func(_, args) => {
+                for arg in args {
+                    arg.visit(f);
+                }
+            }
+            Self::WithinGroup(_, args, order_by) => {
+                for arg in args {
+                    arg.visit(f);
+                }
+                order_by.visit(f);
+            }
+        }
+    }
+
+    /// Render the expression ensuring it only refers to the given columns.
+    pub(crate) fn to_sql(&self, columns: &[Column]) -> Result<String, Error> {
+        for name in self.columns() {
+            check_column(columns, name, "use in the expression")?;
+        }
+        Ok(self.to_string())
+    }
+
+    fn needs_parens_inside(&self, parent: BinaryOp) -> bool {
+        match self {
+            Self::Binary(_, op, _) => {
+                if parent.is_logical() {
+                    // AND binds tighter than OR
+                    op.is_logical() && *op != parent && parent == BinaryOp::And
+                } else {
+                    true
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Not for Expr {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self::Not(Box::new(self))
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Column(name) => write!(f, "{}", name),
+            Self::Param(index) => write!(f, "${}", index),
+            Self::Raw(sql) => write!(f, "{}", sql),
+            Self::Binary(left, op, right) => {
+                for (i, operand) in [left, right].into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " {} ", op)?;
+                    }
+                    if operand.needs_parens_inside(*op) {
+                        write!(f, "({})", operand)?;
+                    } else {
+                        write!(f, "{}", operand)?;
+                    }
+                }
+                Ok(())
+            }
+            Self::IsNull(inner) => write!(f, "{} IS NULL", Parens(inner)),
+            Self::IsNotNull(inner) => write!(f, "{} IS NOT NULL", Parens(inner)),
+            Self::Not(inner) => write!(f, "NOT {}", Parens(inner)),
+            Self::Func(name, args) => write!(f, "{}({})", name, args.iter().join(", ")),
+            Self::WithinGroup(name, args, order_by) => write!(
+                f,
+                "{}({}) WITHIN GROUP (ORDER BY {})",
+                name,
+                args.iter().join(", "),
+                order_by
+            ),
+            Self::Cast(inner, ty) => write!(f, "{}::{}", Parens(inner), ty),
+        }
+    }
+}
+
+/// Wrap the complex expressions into parentheses.
+struct Parens<'a>(&'a Expr);
+
+impl Display for Parens<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if matches!(self.0, Expr::Binary(..)) {
+            write!(f, "({})", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnBuilder;
+
+    use postgres_types::Type;
+
+    #[test]
+    fn simple_comparison() {
+        let expr = Expr::col("price").ge(param(2));
+        assert_eq!(expr.to_string(), "price >= $2");
+        assert_eq!(expr.params_count(), 2);
+    }
+
+    #[test]
+    fn precedence() {
+        let expr = Expr::col("a")
+            .eq(param(1))
+            .or(Expr::col("b").is_not_null())
+            .and(!Expr::col("c").like(Expr::raw("'%foo%'")));
+        assert_eq!(
+            expr.to_string(),
+            "(a = $1 OR b IS NOT NULL) AND NOT (c LIKE '%foo%')"
+        );
+        assert_eq!(expr.columns(), ["a", "b", "c"]);
+        assert_eq!(expr.params_count(), 1);
+    }
+
+    #[test]
+    fn statistics() {
+        let expr = Expr::col("latency").percentile_cont(0.95);
+        assert_eq!(
+            expr.to_string(),
+            "percentile_cont(0.95) WITHIN GROUP (ORDER BY latency)"
+        );
+        assert_eq!(expr.columns(), ["latency"]);
+        assert_eq!(
+            Expr::col("status").mode().to_string(),
+            "mode() WITHIN GROUP (ORDER BY status)"
+        );
+        assert_eq!(
+            Expr::col("latency")
+                .stddev()
+                .cast(&Type::FLOAT8)
+                .to_string(),
+            "stddev(latency)::float8"
+        );
+        assert_eq!(
+            Expr::col("a").eq(param(1)).cast(&Type::INT4).to_string(),
+            "(a = $1)::int4"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Percentile fraction should be between 0 and 1, got 95")]
+    fn percentile_out_of_range() {
+        let _ = Expr::col("latency").percentile_cont(95.0);
+    }
+
+    #[test]
+    fn functions() {
+        let expr = Expr::count_all()
+            .gt(param(1))
+            .and(Expr::col("price").max().le(Expr::func("abs", [param(2)])));
+        assert_eq!(expr.to_string(), "count(*) > $1 AND max(price) <= abs($2)");
+        assert_eq!(expr.columns(), ["price"]);
+        assert_eq!(expr.params_count(), 2);
+    }
+
+    #[test]
+    fn jsonb() {
+        let expr = Expr::col("doc")
+            .jsonb_contains(param(1))
+            .and(Expr::col("doc").jsonb_path("$.owner ? (@ == \"it's me\")"));
+        assert_eq!(
+            expr.to_string(),
+            "doc @> $1 AND doc @? '$.owner ? (@ == \"it''s me\")'::jsonpath"
+        );
+        assert_eq!(expr.params_count(), 1);
+    }
+
+    #[test]
+    fn in_list() {
+        let expr = Expr::in_list("id", &[1, 2, 3], 2).and(Expr::col("name").is_not_null());
+        assert_eq!(expr.to_string(), "id = ANY($2) AND name IS NOT NULL");
+        assert_eq!(expr.params_count(), 2);
+
+        let expr = Expr::in_list("id", &[] as &[i32], 1).or(Expr::col("name").is_null());
+        assert_eq!(expr.to_string(), "FALSE AND id = ANY($1) OR name IS NULL");
+        assert_eq!(expr.columns(), ["id", "name"]);
+    }
+
+    #[test]
+    fn check_columns() {
+        let columns = [
+            ColumnBuilder::new("id", Type::INT4).finish(),
+            ColumnBuilder::new("name", Type::TEXT).finish(),
+        ];
+        let expr = Expr::col("id")
+            .gt(param(1))
+            .and(Expr::col("name").is_null());
+        assert_eq!(expr.to_sql(&columns).unwrap(), "id > $1 AND name IS NULL");
+    }
+
+    #[test]
+    fn unknown_column() {
+        let columns = [ColumnBuilder::new("id", Type::INT4).finish()];
+        let err = Expr::col("identifier")
+            .is_null()
+            .to_sql(&columns)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown column to use in the expression: \"identifier\""
+        );
+    }
+}
@@ -1,154 +1,1527 @@
-use crate::table::{InsertableValues, Table};
+use crate::{
+    aggregate::{GroupBy, TimeBuckets},
+    backoff::Backoff,
+    batch::{self, BatchPlan},
+    cache,
+    checksum::{checksum_sql, TableChecksum},
+    diff::{TableCreation, TableDef},
+    dyn_table::DynTable,
+    error::{Context as _, Error, Operation},
+    expr::Expr,
+    function::SqlFunction,
+    guard::{CostGuard, ExplainedPlan, GuardError},
+    introspect,
+    join::{Join, JoinError, JoinKind, MATCHED_COLUMN, POSITION_COLUMN},
+    migrate::{add_columns_sql, SyncOptions},
+    partition::{self, PartitionBounds, PartitionChanges, PartitionSchedule},
+    progress::{encoded_size, BatchOptions, Progress, ProgressTracker},
+    query::{Redaction, SelectOptions},
+    table::{self, DynamicTable, FromRow, InsertableValues, RowsValues, Table},
+    type_helpers::{quote_ident, ObjectAndCreateSql},
+    verify::SchemaReport,
+    view::{self, View},
+};
 
 use async_trait::async_trait;
-use log::{debug, info};
-use postgres_types::ToSql;
-use tokio_postgres::{Client, Error, Row};
+use futures_util::future::try_join_all;
+use log::{debug, info, warn};
+use postgres_types::{FromSql, ToSql};
+use tokio_postgres::{Error as DbError, GenericClient, Row};
+
+use super::ext::{
+    debug_assert_rows, debug_assert_values, index_relation, insert_returning_sql,
+    is_duplicate_object, is_outside_transaction, is_unique_violation, query_enum_labels,
+    query_function_existence, query_relation_existence, query_schema_existence,
+    query_type_existence, select_by_key_sql, GET_OR_CREATE_ATTEMPTS, GET_OR_CREATE_SAVEPOINT,
+};
 
-use super::ext::query_type_existence;
+#[async_trait]
+pub trait PgTableExtension {
+    /// Create the table with its schema, types and indices if they are missing.
+    ///
+    /// The objects created concurrently (e.g. by the replicas of the application
+    /// starting at once) are accepted as long as the table matches the definition.
+    async fn create_table<T, const N: usize>(&self) -> Result<(), Error>
+    where
+        T: Table<N>;
+    /// Create the missing types and add the new labels to the existing enum types.
+    async fn create_types<T, const N: usize>(&self) -> Result<(), Error>
+    where
+        T: Table<N>;
+    async fn create_indices<T, const N: usize>(&self) -> Result<(), Error>
+    where
+        T: Table<N>;
+    /// Compare the live schema of the table with its definition.
+    async fn verify_table<T, const N: usize>(&self) -> Result<SchemaReport, Error>
+    where
+        T: Table<N>;
+    /// Create the table or add the columns missing in the existing one
+    /// returning the names of the added columns.
+    ///
+    /// The other differences (see the [`Self::verify_table`]) are left as is.
+    async fn sync_table<T, const N: usize>(
+        &self,
+        options: &SyncOptions,
+    ) -> Result<Vec<String>, Error>
+    where
+        T: Table<N>;
+    /// Rewrite the table in the order of its [`Table::cluster_on`] index
+    /// (takes the `ACCESS EXCLUSIVE` lock, so run it in the maintenance window).
+    async fn recluster<T, const N: usize>(&self) -> Result<(), Error>
+    where
+        T: Table<N>;
+    /// Create the partition `{table}_{suffix}` of the table
+    /// [partitioned][crate::TableOptions::partition_by] by the key in the `bounds`
+    /// returning the name of the partition.
+    async fn create_partition<T, const N: usize>(
+        &self,
+        suffix: &str,
+        bounds: &PartitionBounds,
+    ) -> Result<String, Error>
+    where
+        T: Table<N>;
+    /// Create the missing partitions of the range-partitioned table for the time
+    /// from the current interval up to the ones ahead of it
+    /// and expire the old ones according to the schedule.
+    ///
+    /// It is intended to be run periodically (e.g. daily with the cron).
+    async fn ensure_partitions<T, const N: usize>(
+        &self,
+        schedule: &PartitionSchedule,
+    ) -> Result<PartitionChanges, Error>
+    where
+        T: Table<N>;
+    /// Create the function unless the one with the same signature exists.
+    async fn create_function(&self, function: &SqlFunction) -> Result<(), Error>;
+    /// Create the view or replace the definition of the existing one
+    /// (the materialized views are only created if they are missing).
+    async fn create_view<V>(&self) -> Result<(), Error>
+    where
+        V: View;
+    async fn select_view<V, OptionStr>(
+        &self,
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<V>, Error>
+    where
+        V: View + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send;
+    /// Recompute the rows of the materialized view
+    /// (the `concurrently` does not block the readers but requires a unique index).
+    async fn refresh_view<V>(&self, concurrently: bool) -> Result<(), Error>
+    where
+        V: View;
+
+    async fn insert_row<T, const N: usize>(&self, row: &T) -> Result<u64, Error>
+    where
+        T: InsertableValues<N> + Sync;
+    async fn insert_rows<T, const N: usize>(&self, rows: &[T]) -> Result<u64, Error>
+    where
+        T: InsertableValues<N> + Sync;
+    /// Insert the rows in the chunks reporting the [`Progress`] after every chunk.
+    ///
+    /// The chunks are inserted with the separate statements,
+    /// so run it in a transaction to insert all the rows or nothing.
+    ///
+    /// Returns the number of the inserted rows
+    /// (less than the number of the `rows` if the operation is cancelled).
+    async fn insert_rows_chunked<T, F, const N: usize>(
+        &self,
+        rows: &[T],
+        options: &BatchOptions,
+        progress: F,
+    ) -> Result<u64, Error>
+    where
+        T: Table<N> + InsertableValues<N> + Sync,
+        F: FnMut(Progress) + Send;
+    /// Insert the rows with the single-row statement prepared once
+    /// and executed for all the rows concurrently (pipelined over the connection).
+    async fn insert_rows_pipelined<T, const N: usize>(&self, rows: &[T]) -> Result<u64, Error>
+    where
+        T: InsertableValues<N> + Sync;
+    /// Find the row with the same `key` columns or insert it,
+    /// returning the row from the database and whether it was created.
+    ///
+    /// The insert is protected with a savepoint if running inside a transaction,
+    /// so losing the race to a concurrent creator (unique violation) leads to
+    /// the repeated search instead of the aborted transaction.
+    ///
+    /// Fails if the key refers to unknown columns.
+    ///
+    /// # Panics
+    ///
+    /// If the key is empty.
+    async fn get_or_create<T, const N: usize>(
+        &self,
+        row: &T,
+        key: &[&str],
+    ) -> Result<(T, bool), Error>
+    where
+        T: Table<N> + InsertableValues<N> + TryFrom<Row, Error = DbError> + Sync + Send;
+
+    async fn select_all<T, const N: usize>(&self) -> Result<Vec<T>, Error>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError>;
+    async fn select<T, OptionStr, const N: usize>(
+        &self,
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<T>, Error>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send;
+    async fn select_with_options<T, OptionStr, const N: usize>(
+        &self,
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+        options: &SelectOptions,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send;
+    async fn select_distinct<T, OptionStr, const N: usize>(
+        &self,
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<T>, Error>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send;
+    async fn select_distinct_on<T, OptionStr, const N: usize>(
+        &self,
+        columns: &[&str],
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<T>, Error>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send;
+    /// Select the rows without reading the values of the sensitive columns.
+    async fn select_redacted<T, OptionStr, const N: usize>(
+        &self,
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+        redaction: Redaction,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send;
+    async fn select_where<T, const N: usize>(
+        &self,
+        condition: &Expr,
+        params: &[&(dyn ToSql + Sync)],
+        options: &SelectOptions,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError>;
+    /// Delete the rows matching the `condition` in the chunks
+    /// (each one with a separate statement, so the locks are held for a short time).
+    ///
+    /// Returns the number of the deleted rows.
+    async fn delete_batched<T, const N: usize>(
+        &self,
+        condition: &Expr,
+        params: &[&(dyn ToSql + Sync)],
+        options: &BatchOptions,
+    ) -> Result<u64, Error>
+    where
+        T: Table<N>;
+    /// Count the rows the [`Self::delete_batched`] is going to delete without deleting them.
+    async fn plan_delete<T, const N: usize>(
+        &self,
+        condition: &Expr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<BatchPlan, Error>
+    where
+        T: Table<N>;
+    /// Create the types, the table and the indices of the table named at runtime.
+    async fn create_dyn_table<D, const N: usize>(&self, table: &D) -> Result<(), Error>
+    where
+        D: DynTable<N> + Sync;
+    async fn insert_dyn_rows<T, D, const N: usize>(
+        &self,
+        table: &D,
+        rows: &[T],
+    ) -> Result<u64, Error>
+    where
+        T: InsertableValues<N> + Sync,
+        D: DynTable<N> + Sync;
+    async fn select_dyn<T, D, const N: usize>(
+        &self,
+        table: &D,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+        options: &SelectOptions,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: TryFrom<Row, Error = DbError>,
+        D: DynTable<N> + Sync;
+    /// Same as the [`Self::create_table`] without the number of the columns.
+    async fn create_dynamic_table<T>(&self) -> Result<(), Error>
+    where
+        T: DynamicTable;
+    async fn insert_dynamic_rows<T>(&self, rows: &[T]) -> Result<u64, Error>
+    where
+        T: DynamicTable + Sync;
+    async fn select_dynamic<T>(
+        &self,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+        options: &SelectOptions,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: DynamicTable + TryFrom<Row, Error = DbError>;
+    /// Check the planner estimates of the query with the `guard` before running it.
+    async fn select_guarded<T, const N: usize>(
+        &self,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+        options: &SelectOptions,
+        guard: &CostGuard,
+    ) -> Result<Vec<T>, GuardError>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError> + Send;
+    /// The digest of the `columns` (all of them if empty) of all the rows
+    /// to compare the table with its copy in another database.
+    /// Fails if any of the `columns` is unknown.
+    async fn table_checksum<T, const N: usize>(
+        &self,
+        columns: &[&str],
+    ) -> Result<TableChecksum, Error>
+    where
+        T: Table<N>;
+    /// The approximate number of the rows matching the condition
+    /// from the planner statistics without scanning the table.
+    async fn estimate_count<T, const N: usize>(
+        &self,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, Error>
+    where
+        T: Table<N>;
+    async fn select_grouped<T, V, const N: usize>(
+        &self,
+        group_by: &GroupBy,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<V>, Error>
+    where
+        T: Table<N>,
+        V: FromRow + Send;
+    async fn aggregate_by_time<T, V, const N: usize>(
+        &self,
+        buckets: &TimeBuckets,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<V>, Error>
+    where
+        T: Table<N>,
+        V: FromRow + Send;
+    async fn run_named<T, V, const N: usize>(
+        &self,
+        name: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<V>, Error>
+    where
+        T: Table<N>,
+        V: FromRow + Send;
+    async fn query_scalar<V>(
+        &self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<V, Error>
+    where
+        V: for<'a> FromSql<'a> + Send;
+    async fn query_tuple<V>(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<V, Error>
+    where
+        V: FromRow + Send;
+    async fn query_tuples<V>(
+        &self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<V>, Error>
+    where
+        V: FromRow + Send;
+    async fn schedule_retry<T, const N: usize>(
+        &self,
+        backoff: &Backoff,
+        condition: &Expr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, Error>
+    where
+        T: Table<N>;
+    async fn select_joined<L, R, const N: usize, const M: usize>(
+        &self,
+        join: &Join,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<(L, R)>, JoinError>
+    where
+        L: Table<N> + FromRow + Send,
+        R: Table<M> + FromRow + Send;
+    async fn select_left_joined<L, R, const N: usize, const M: usize>(
+        &self,
+        join: &Join,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<(L, Option<R>)>, JoinError>
+    where
+        L: Table<N> + FromRow + Send,
+        R: Table<M> + FromRow + Send;
+    /// Select the rows of the left table each with the list of the joined right rows
+    /// (see the [`Join::lateral`] to limit them).
+    async fn select_lateral<L, R, const N: usize, const M: usize>(
+        &self,
+        join: &Join,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<(L, Vec<R>)>, JoinError>
+    where
+        L: Table<N> + FromRow + Send,
+        R: Table<M> + FromRow + Send;
+}
+
+/// Create the schema of the table unless it is the `public` one or exists.
+async fn create_schema<C>(client: &C, table: &str, schema: &str) -> Result<(), Error>
+where
+    C: GenericClient + Sync,
+{
+    if schema == "public" {
+        return Ok(());
+    }
+    let exists_query = query_schema_existence();
+    let res = client.query(exists_query, &[&schema]).await.context(
+        table,
+        Operation::Select,
+        exists_query,
+    )?;
+    if res.is_empty() {
+        info!("Creating the schema {}...", schema);
+        let query = format!("CREATE SCHEMA IF NOT EXISTS {}", quote_ident(schema));
+        if let Err(err) = client.execute(&query, &[]).await {
+            let created = is_duplicate_object(&err)
+                && matches!(client.query(exists_query, &[&schema]).await, Ok(rows) if !rows.is_empty());
+            if !created {
+                return Err(err).context(table, Operation::Create, &query);
+            }
+            info!("The schema {} has been created concurrently", schema);
+        }
+    }
+    Ok(())
+}
+
+/// Create the missing types of the table and add the missing values to the existing enums.
+async fn create_types_of<C>(
+    client: &C,
+    table: &str,
+    types: &[ObjectAndCreateSql],
+) -> Result<(), Error>
+where
+    C: GenericClient + Sync,
+{
+    if types.is_empty() {
+        debug!("Skip the types for a table {:?}...", table);
+        return Ok(());
+    }
+    info!("Creating the types for a table {:?}...", table);
+    for ty_query in types {
+        let type_name = ty_query.name();
+        let exists_query = query_type_existence();
+        let res = client.query(exists_query, &[&type_name]).await.context(
+            table,
+            Operation::Select,
+            exists_query,
+        )?;
+        if res.is_empty() {
+            let sql = ty_query.create_sql();
+            info!("Not found type {:?}. Creating it with {:?}", type_name, sql);
+            if let Err(err) = client.execute(sql, &[]).await {
+                let created = is_duplicate_object(&err)
+                    && matches!(client.query(exists_query, &[&type_name]).await, Ok(rows) if !rows.is_empty());
+                if !created {
+                    return Err(err).context(table, Operation::Create, sql);
+                }
+                info!("The type {:?} has been created concurrently", type_name);
+            }
+        } else if !ty_query.enum_labels().is_empty() {
+            let labels_query = query_enum_labels();
+            let existing: Vec<String> = client
+                .query(labels_query, &[&type_name])
+                .await
+                .and_then(|rows| rows.iter().map(|row| row.try_get(0)).collect())
+                .context(table, Operation::Select, labels_query)?;
+            for sql in ty_query.add_enum_values_sql(&existing) {
+                info!(
+                    "Adding the value to the type {:?} with {:?}",
+                    type_name, sql
+                );
+                client
+                    .execute(&sql, &[])
+                    .await
+                    .context(table, Operation::Alter, &sql)?;
+            }
+        }
+    }
+    info!("Types for table {} created", table);
+    Ok(())
+}
+
+/// Create the missing indices of the table.
+async fn create_indices_of<C>(
+    client: &C,
+    table: &str,
+    indices: &[ObjectAndCreateSql],
+) -> Result<(), Error>
+where
+    C: GenericClient + Sync,
+{
+    if indices.is_empty() {
+        debug!("Skip the indices for a table {:?}...", table);
+        return Ok(());
+    }
+    info!("Creating the indices for a table {:?}...", table);
+    for idx_query in indices {
+        let idx_name = idx_query.name();
+        info!(
+            "Creating the index {:?} for a table {:?}...",
+            idx_name, table
+        );
+        let sql = idx_query.create_sql();
+        debug!("Full index query: {:?}", sql);
+        if let Err(err) = client.execute(sql, &[]).await {
+            let mut created = false;
+            if is_duplicate_object(&err) {
+                let index = index_relation(table, idx_name);
+                let found = client
+                    .query_one(query_relation_existence(), &[&index])
+                    .await
+                    .and_then(|row| row.try_get::<_, Option<String>>(0));
+                created = matches!(found, Ok(Some(_)));
+            }
+            if !created {
+                return Err(err).context(table, Operation::Create, sql);
+            }
+            info!("The index {:?} has been created concurrently", idx_name);
+        }
+    }
+    info!("Indices for table {} created", table);
+    Ok(())
+}
+
+/// Create the table with all its objects (shared by the static and the dynamic tables).
+///
+/// The definition itself is not `Send`, so it is only built to verify
+/// the concurrently created table.
+async fn create_from_def<C>(
+    client: &C,
+    creation: &TableCreation,
+    def: &(dyn Fn() -> TableDef + Sync),
+) -> Result<(), Error>
+where
+    C: GenericClient + Sync,
+{
+    let table = creation.name.as_str();
+    create_schema(client, table, &creation.schema).await?;
+    create_types_of(client, table, &creation.types).await?;
+
+    info!("Creating the table {}...", table);
+    let query = &creation.create_sql;
+    debug!("CREATE for table {}: {}", table, query);
+    // same as the `batch_execute` of a transaction
+    if let Err(err) = client.client().batch_execute(query).await {
+        // the concurrently created table is only accepted if it is the same
+        let mut created = false;
+        if is_duplicate_object(&err) {
+            if let Ok(live) = introspect::table_async(client, table).await {
+                let report = SchemaReport::compare_def(&def(), live.as_ref());
+                if !report.is_ok() {
+                    warn!("{}", report);
+                }
+                created = report.is_ok();
+            }
+        }
+        if !created {
+            return Err(err).context(table, Operation::Create, query);
+        }
+        info!("The table {} has been created concurrently", table);
+    }
+    for query in &creation.storage_sql {
+        debug!("ALTER for table {}: {}", table, query);
+        client
+            .execute(query, &[])
+            .await
+            .context(table, Operation::Alter, query)?;
+    }
+    for function in &creation.functions {
+        client.create_function(function).await?;
+    }
+    for query in &creation.row_security_sql {
+        debug!("ALTER for table {}: {}", table, query);
+        client
+            .execute(query, &[])
+            .await
+            .context(table, Operation::Alter, query)?;
+    }
+    for query in &creation.triggers_sql {
+        debug!("CREATE for table {}: {}", table, query);
+        client
+            .execute(query, &[])
+            .await
+            .context(table, Operation::Create, query)?;
+    }
+
+    create_indices_of(client, table, &creation.indices).await?;
+    if let Some(query) = &creation.cluster_sql {
+        debug!("ALTER for table {}: {}", table, query);
+        client
+            .execute(query, &[])
+            .await
+            .context(table, Operation::Alter, query)?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl<C> PgTableExtension for C
+where
+    C: GenericClient + Sync,
+{
+    async fn create_table<T, const N: usize>(&self) -> Result<(), Error>
+    where
+        T: Table<N>,
+    {
+        let creation = TableDef::of::<T, N>().into_creation();
+        create_from_def(self, &creation, &TableDef::of::<T, N>).await
+    }
+
+    async fn create_types<T, const N: usize>(&self) -> Result<(), Error>
+    where
+        T: Table<N>,
+    {
+        create_types_of(self, T::name(), &T::create_types_sql()).await
+    }
+
+    async fn create_indices<T, const N: usize>(&self) -> Result<(), Error>
+    where
+        T: Table<N>,
+    {
+        create_indices_of(self, T::name(), &T::create_indices_sql()).await
+    }
+
+    async fn verify_table<T, const N: usize>(&self) -> Result<SchemaReport, Error>
+    where
+        T: Table<N>,
+    {
+        debug!("Verifying the table {}", T::name());
+        let live = introspect::table_async(self, T::name()).await?;
+        Ok(SchemaReport::compare::<T, N>(live.as_ref()))
+    }
+
+    async fn sync_table<T, const N: usize>(
+        &self,
+        options: &SyncOptions,
+    ) -> Result<Vec<String>, Error>
+    where
+        T: Table<N>,
+    {
+        if options.types_enabled() {
+            self.create_types::<T, N>().await?;
+        }
+        let Some(live) = introspect::table_async(self, T::name()).await? else {
+            self.create_table::<T, N>().await?;
+            return Ok(T::columns()
+                .iter()
+                .map(|col| col.name().to_owned())
+                .collect());
+        };
+
+        let mut added = vec![];
+        for (column, query) in add_columns_sql::<T, N>(&live) {
+            info!("Adding the column {} to the table {}", column, T::name());
+            debug!("ALTER for table {}: {}", T::name(), query);
+            self.execute(&query, &[])
+                .await
+                .context(T::name(), Operation::Alter, &query)?;
+            added.push(column);
+        }
+        if options.indices_enabled() {
+            self.create_indices::<T, N>().await?;
+        }
+        Ok(added)
+    }
+
+    async fn recluster<T, const N: usize>(&self) -> Result<(), Error>
+    where
+        T: Table<N>,
+    {
+        let Some(index) = T::cluster_index() else {
+            debug!("Skip the clustering of a table {:?}...", T::name());
+            return Ok(());
+        };
+        info!("Clustering the table {} on {}...", T::name(), index);
+        let query = format!("CLUSTER {} USING {}", T::name(), index);
+        self.execute(&query, &[])
+            .await
+            .context(T::name(), Operation::Maintenance, &query)?;
+        Ok(())
+    }
+
+    async fn create_partition<T, const N: usize>(
+        &self,
+        suffix: &str,
+        bounds: &PartitionBounds,
+    ) -> Result<String, Error>
+    where
+        T: Table<N>,
+    {
+        let name = partition::partition_name::<T, N>(suffix);
+        info!(
+            "Creating the partition {} of a table {}...",
+            name,
+            T::name()
+        );
+        let query = partition::create_partition_sql::<T, N>(&name, bounds);
+        debug!("CREATE for partition {}: {}", name, query);
+        self.execute(&query, &[])
+            .await
+            .context(T::name(), Operation::Create, &query)?;
+        Ok(name)
+    }
+
+    async fn ensure_partitions<T, const N: usize>(
+        &self,
+        schedule: &PartitionSchedule,
+    ) -> Result<PartitionChanges, Error>
+    where
+        T: Table<N>,
+    {
+        let unit = schedule.interval().unit();
+        let ahead = i32::try_from(schedule.get_ahead()).unwrap_or(i32::MAX);
+        let upcoming_query = partition::UPCOMING_PARTITIONS_SQL;
+        let upcoming: Vec<(String, String, String)> = self
+            .query(upcoming_query, &[&unit, &ahead])
+            .await
+            .and_then(|rows| {
+                rows.iter()
+                    .map(|row| Ok((row.try_get(0)?, row.try_get(1)?, row.try_get(2)?)))
+                    .collect()
+            })
+            .context(T::name(), Operation::Select, upcoming_query)?;
+
+        let mut changes = PartitionChanges::default();
+        for (suffix, from, to) in upcoming {
+            let name = partition::partition_name::<T, N>(&suffix);
+            let exists_query = query_relation_existence();
+            let existing: Option<String> = self
+                .query_one(exists_query, &[&name])
+                .await
+                .and_then(|row| row.try_get(0))
+                .context(T::name(), Operation::Select, exists_query)?;
+            if existing.is_none() {
+                let bounds = PartitionBounds::range(from, to);
+                let name = self.create_partition::<T, N>(&suffix, &bounds).await?;
+                changes.push_created(name);
+            }
+        }
+
+        if let Some((count, expiration)) = schedule.retention() {
+            let count = i32::try_from(count).unwrap_or(i32::MAX);
+            let expired_query = partition::EXPIRED_PARTITIONS_SQL;
+            let expired: Vec<String> = self
+                .query(expired_query, &[&T::name(), &unit, &count])
+                .await
+                .and_then(|rows| rows.iter().map(|row| row.try_get(0)).collect())
+                .context(T::name(), Operation::Select, expired_query)?;
+            for name in expired {
+                let name = partition::qualified::<T, N>(&name);
+                info!(
+                    "Expiring the partition {} of a table {}...",
+                    name,
+                    T::name()
+                );
+                let query = partition::expire_sql::<T, N>(&name, expiration);
+                self.execute(&query, &[]).await.context(
+                    T::name(),
+                    Operation::Maintenance,
+                    &query,
+                )?;
+                changes.push_expired(name);
+            }
+        }
+        Ok(changes)
+    }
+
+    async fn create_function(&self, function: &SqlFunction) -> Result<(), Error> {
+        let signature = function.signature();
+        let exists_query = query_function_existence();
+        let res = self.query(exists_query, &[&signature]).await.context(
+            function.name(),
+            Operation::Select,
+            exists_query,
+        )?;
+        if !res.is_empty() {
+            debug!("Skip the existing function {:?}", signature);
+            return Ok(());
+        }
+        let sql = function.create_sql();
+        info!(
+            "Not found function {:?}. Creating it with {:?}",
+            signature, sql
+        );
+        if let Err(err) = self.execute(&sql, &[]).await {
+            let created = is_duplicate_object(&err)
+                && matches!(self.query(exists_query, &[&signature]).await, Ok(rows) if !rows.is_empty());
+            if !created {
+                return Err(err).context(function.name(), Operation::Create, &sql);
+            }
+            info!("The function {:?} has been created concurrently", signature);
+        }
+        Ok(())
+    }
+
+    async fn create_view<V>(&self) -> Result<(), Error>
+    where
+        V: View,
+    {
+        info!("Creating the view {}...", V::name());
+        let query = V::create_view_sql();
+        debug!("CREATE for view {}: {}", V::name(), query);
+        self.execute(&query, &[])
+            .await
+            .context(V::name(), Operation::Create, &query)?;
+        Ok(())
+    }
+
+    async fn select_view<V, OptionStr>(
+        &self,
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<V>, Error>
+    where
+        V: View + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send,
+    {
+        let condition = condition.into();
+        let query = V::select_sql(condition.as_deref());
+        debug!("SELECT for view {}: {}", V::name(), query);
+        self.query(&query, params)
+            .await
+            .and_then(|rows| rows.into_iter().map(V::try_from).collect())
+            .context(V::name(), Operation::Select, &query)
+    }
+
+    async fn refresh_view<V>(&self, concurrently: bool) -> Result<(), Error>
+    where
+        V: View,
+    {
+        info!("Refreshing the view {}...", V::name());
+        let query = view::refresh_view_sql::<V>(concurrently);
+        self.execute(&query, &[])
+            .await
+            .context(V::name(), Operation::Maintenance, &query)?;
+        Ok(())
+    }
+
+    async fn insert_row<T, const N: usize>(&self, row: &T) -> Result<u64, Error>
+    where
+        T: InsertableValues<N> + Sync,
+    {
+        let query = T::insert_sql();
+        let values = row.encoded_values();
+        debug_assert_values::<T, N>(&values);
+        let inserted = self
+            .execute(&query, &table::params(&values))
+            .await
+            .context(T::table_name(), Operation::Insert, &query)?;
+        cache::notify_inserted([row]);
+        Ok(inserted)
+    }
+
+    async fn insert_rows<T, const N: usize>(&self, rows: &[T]) -> Result<u64, Error>
+    where
+        T: InsertableValues<N> + Sync,
+    {
+        if cfg!(debug_assertions) {
+            for row in rows {
+                debug_assert_values::<T, N>(&row.encoded_values());
+            }
+        }
+        let query = T::insert_many_sql(rows.len());
+        let inserted = self
+            .execute_raw(&query, RowsValues::new(rows))
+            .await
+            .context(T::table_name(), Operation::Insert, &query)?;
+        cache::notify_inserted(rows);
+        Ok(inserted)
+    }
+
+    async fn insert_rows_chunked<T, F, const N: usize>(
+        &self,
+        rows: &[T],
+        options: &BatchOptions,
+        mut progress: F,
+    ) -> Result<u64, Error>
+    where
+        T: Table<N> + InsertableValues<N> + Sync,
+        F: FnMut(Progress) + Send,
+    {
+        let mut tracker = ProgressTracker::new(rows.len());
+        let mut inserted = 0;
+        for chunk in rows.chunks(options.chunk_size()) {
+            if options.is_cancelled() {
+                info!(
+                    "Inserting into the table {} is cancelled after {} rows",
+                    T::table_name(),
+                    inserted
+                );
+                break;
+            }
+            let bytes = encoded_size::<T, N>(chunk);
+            let chunk_inserted = self.insert_rows(chunk).await?;
+            inserted += chunk_inserted;
+            progress(tracker.chunk_done(chunk_inserted, bytes));
+        }
+        Ok(inserted)
+    }
+
+    async fn insert_rows_pipelined<T, const N: usize>(&self, rows: &[T]) -> Result<u64, Error>
+    where
+        T: InsertableValues<N> + Sync,
+    {
+        let query = T::insert_sql();
+        let statement =
+            self.prepare(&query)
+                .await
+                .context(T::table_name(), Operation::Insert, &query)?;
+        let inserts = rows.iter().map(|row| {
+            let statement = &statement;
+            async move {
+                let values = row.encoded_values();
+                debug_assert_values::<T, N>(&values);
+                self.execute(statement, &table::params(&values)).await
+            }
+        });
+        let inserted =
+            try_join_all(inserts)
+                .await
+                .context(T::table_name(), Operation::Insert, &query)?;
+        cache::notify_inserted(rows);
+        Ok(inserted.into_iter().sum())
+    }
+
+    async fn get_or_create<T, const N: usize>(
+        &self,
+        row: &T,
+        key: &[&str],
+    ) -> Result<(T, bool), Error>
+    where
+        T: Table<N> + InsertableValues<N> + TryFrom<Row, Error = DbError> + Sync + Send,
+    {
+        let (select, key_indices) = select_by_key_sql::<T, N>(key)?;
+        let insert = insert_returning_sql::<T, N>();
+        let values = row.encoded_values();
+        debug_assert_values::<T, N>(&values);
+        let params = table::params(&values);
+        let key_values: Vec<_> = key_indices.iter().map(|&i| params[i]).collect();
+
+        let savepoint = format!("SAVEPOINT {}", GET_OR_CREATE_SAVEPOINT);
+        let release = format!("RELEASE SAVEPOINT {}", GET_OR_CREATE_SAVEPOINT);
+        let rollback = format!(
+            "ROLLBACK TO SAVEPOINT {0}; RELEASE SAVEPOINT {0}",
+            GET_OR_CREATE_SAVEPOINT
+        );
+        let mut in_transaction = None;
+
+        for attempt in 1..=GET_OR_CREATE_ATTEMPTS {
+            let existing = self
+                .query_opt(&select, &key_values)
+                .await
+                .and_then(|row| row.map(T::try_from).transpose())
+                .context(T::name(), Operation::Select, &select)?;
+            if let Some(existing) = existing {
+                return Ok((existing, false));
+            }
+
+            let protected = match in_transaction {
+                Some(protected) => protected,
+                None => {
+                    let protected = match self.client().batch_execute(&savepoint).await {
+                        Ok(()) => true,
+                        Err(err) if is_outside_transaction(&err) => false,
+                        Err(err) => return Err(err.into()),
+                    };
+                    in_transaction = Some(protected);
+                    protected
+                }
+            };
+            if protected && attempt > 1 {
+                self.client().batch_execute(&savepoint).await?;
+            }
+
+            debug!(
+                "Insert into the table {} (attempt {}): {}",
+                T::name(),
+                attempt,
+                insert
+            );
+            match self.query_one(&insert, &params).await {
+                Ok(created) => {
+                    if protected {
+                        self.client().batch_execute(&release).await?;
+                    }
+                    let created =
+                        T::try_from(created).context(T::name(), Operation::Insert, &insert)?;
+                    cache::notify_inserted([&created]);
+                    return Ok((created, true));
+                }
+                Err(err) => {
+                    if protected {
+                        self.client().batch_execute(&rollback).await?;
+                    }
+                    if !is_unique_violation(&err) || attempt == GET_OR_CREATE_ATTEMPTS {
+                        return Err(err).context(T::name(), Operation::Insert, &insert);
+                    }
+                    info!(
+                        "The row was concurrently inserted into {}: {}",
+                        T::name(),
+                        err
+                    );
+                }
+            }
+        }
+        unreachable!("The last attempt always returns")
+    }
+
+    async fn select_all<T, const N: usize>(&self) -> Result<Vec<T>, Error>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+    {
+        self.select(None, &[]).await
+    }
+
+    async fn select<T, OptionStr, const N: usize>(
+        &self,
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<T>, Error>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send,
+    {
+        self.select_with_options(condition, params, &SelectOptions::default())
+            .await
+    }
+
+    // TODO: make it lazy iterator
+    async fn select_with_options<T, OptionStr, const N: usize>(
+        &self,
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+        options: &SelectOptions,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send,
+    {
+        let condition = condition.into();
+        let query = T::select_sql(condition.as_deref(), options)?;
+        debug!("SELECT for table {}: {}", T::name(), query);
 
-#[async_trait]
-pub trait PgTableExtension {
-    async fn create_table<T, const N: usize>(&self) -> Result<(), Error>
+        self.query(&query, params)
+            .await
+            .and_then(|rows| rows.into_iter().map(T::try_from).collect())
+            .context(T::name(), Operation::Select, &query)
+    }
+
+    async fn select_distinct<T, OptionStr, const N: usize>(
+        &self,
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<T>, Error>
     where
-        T: Table<N>;
-    async fn create_types<T, const N: usize>(&self) -> Result<(), Error>
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send,
+    {
+        let options = SelectOptions::new().distinct();
+        self.select_with_options(condition, params, &options).await
+    }
+
+    async fn select_distinct_on<T, OptionStr, const N: usize>(
+        &self,
+        columns: &[&str],
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<T>, Error>
     where
-        T: Table<N>;
-    async fn create_indices<T, const N: usize>(&self) -> Result<(), Error>
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send,
+    {
+        let options = SelectOptions::new().distinct_on(columns);
+        self.select_with_options(condition, params, &options).await
+    }
+
+    async fn select_redacted<T, OptionStr, const N: usize>(
+        &self,
+        condition: OptionStr,
+        params: &[&(dyn ToSql + Sync)],
+        redaction: Redaction,
+    ) -> Result<Vec<T>, Error>
     where
-        T: Table<N>;
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+        OptionStr: Into<Option<String>> + Send,
+    {
+        let options = SelectOptions::new().redacted(redaction);
+        self.select_with_options(condition, params, &options).await
+    }
 
-    async fn insert_row<T, const N: usize>(&self, row: &T) -> Result<u64, Error>
+    async fn select_where<T, const N: usize>(
+        &self,
+        condition: &Expr,
+        params: &[&(dyn ToSql + Sync)],
+        options: &SelectOptions,
+    ) -> Result<Vec<T>, Error>
     where
-        T: InsertableValues<N> + Sync;
-    async fn insert_rows<T, const N: usize>(&self, rows: &[T]) -> Result<u64, Error>
+        T: Table<N> + TryFrom<Row, Error = DbError>,
+    {
+        let condition = condition.to_sql(&T::columns())?;
+        self.select_with_options(condition, params, options).await
+    }
+
+    async fn delete_batched<T, const N: usize>(
+        &self,
+        condition: &Expr,
+        params: &[&(dyn ToSql + Sync)],
+        options: &BatchOptions,
+    ) -> Result<u64, Error>
     where
-        T: InsertableValues<N> + Sync;
+        T: Table<N>,
+    {
+        let condition = condition.to_sql(&T::columns())?;
+        let query = batch::delete_chunk_sql::<T, N>(&condition, options.chunk_size());
+        debug!("Batched DELETE for table {}: {}", T::name(), query);
+        let mut deleted = 0;
+        while !options.is_cancelled() {
+            let rows =
+                self.query(&query, params)
+                    .await
+                    .context(T::name(), Operation::Delete, &query)?;
+            batch::notify_deleted::<T, N>(&rows);
+            deleted += rows.len() as u64;
+            if rows.len() < options.chunk_size() {
+                return Ok(deleted);
+            }
+        }
+        info!(
+            "Deleting from the table {} is cancelled after {} rows",
+            T::name(),
+            deleted
+        );
+        Ok(deleted)
+    }
 
-    async fn select_all<T, const N: usize>(&self) -> Result<Vec<T>, Error>
+    async fn plan_delete<T, const N: usize>(
+        &self,
+        condition: &Expr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<BatchPlan, Error>
     where
-        T: Table<N> + TryFrom<Row, Error = Error>;
-    async fn select<T, OptionStr, const N: usize>(
+        T: Table<N>,
+    {
+        let query = batch::count_sql::<T, N>(&condition.to_sql(&T::columns())?);
+        let count: i64 = self
+            .query_one(&query, params)
+            .await
+            .and_then(|row| row.try_get(0))
+            .context(T::name(), Operation::Select, &query)?;
+        Ok(BatchPlan::deleting(count as u64))
+    }
+
+    async fn create_dyn_table<D, const N: usize>(&self, table: &D) -> Result<(), Error>
+    where
+        D: DynTable<N> + Sync,
+    {
+        let creation = table.table_def().into_creation();
+        create_from_def(self, &creation, &|| table.table_def()).await
+    }
+
+    async fn insert_dyn_rows<T, D, const N: usize>(
         &self,
-        condition: OptionStr,
+        table: &D,
+        rows: &[T],
+    ) -> Result<u64, Error>
+    where
+        T: InsertableValues<N> + Sync,
+        D: DynTable<N> + Sync,
+    {
+        let query = table.insert_many_sql(rows.len());
+        let columns = table.columns();
+        if cfg!(debug_assertions) {
+            for row in rows {
+                debug_assert_rows(table.name(), &columns, &row.encoded_values());
+            }
+        }
+        let inserted = self
+            .execute_raw(&query, RowsValues::new(rows))
+            .await
+            .context(table.name(), Operation::Insert, &query)?;
+        cache::notify_inserted_values(
+            table.name(),
+            &columns,
+            rows.iter().map(|row| row.encoded_values()),
+        );
+        Ok(inserted)
+    }
+
+    async fn select_dyn<T, D, const N: usize>(
+        &self,
+        table: &D,
+        condition: Option<&Expr>,
         params: &[&(dyn ToSql + Sync)],
+        options: &SelectOptions,
     ) -> Result<Vec<T>, Error>
     where
-        T: Table<N> + TryFrom<Row, Error = Error>,
-        OptionStr: Into<Option<String>> + Send;
-}
+        T: TryFrom<Row, Error = DbError>,
+        D: DynTable<N> + Sync,
+    {
+        let condition = condition
+            .map(|condition| condition.to_sql(&table.columns()))
+            .transpose()?;
+        let query = table.select_sql(condition.as_deref(), options)?;
+        debug!("SELECT for table {}: {}", table.name(), query);
+        self.query(&query, params)
+            .await
+            .and_then(|rows| rows.into_iter().map(T::try_from).collect())
+            .context(table.name(), Operation::Select, &query)
+    }
 
-#[async_trait]
-impl PgTableExtension for Client {
-    async fn create_table<T, const N: usize>(&self) -> Result<(), Error>
+    async fn create_dynamic_table<T>(&self) -> Result<(), Error>
     where
-        T: Table<N>,
+        T: DynamicTable,
+    {
+        create_from_def(self, &T::table_def().into_creation(), &T::table_def).await
+    }
+
+    async fn insert_dynamic_rows<T>(&self, rows: &[T]) -> Result<u64, Error>
+    where
+        T: DynamicTable + Sync,
     {
-        self.create_types::<T, N>().await?;
+        let columns = T::dynamic_columns();
+        let query = table::insert_many_sql(T::dynamic_name(), &columns, rows.len());
+        let params: Vec<_> = rows.iter().flat_map(|row| row.dynamic_values()).collect();
+        debug_assert_rows(T::dynamic_name(), &columns, &params);
+        let inserted = self.execute_raw(&query, &params).await.context(
+            T::dynamic_name(),
+            Operation::Insert,
+            &query,
+        )?;
+        cache::notify_inserted_values(T::dynamic_name(), &columns, params.chunks(columns.len()));
+        Ok(inserted)
+    }
 
-        info!("Creating the table {}...", T::name());
-        let query = T::create_table_sql();
-        debug!("CREATE for table {}: {}", T::name(), query);
-        self.batch_execute(&query).await?;
+    async fn select_dynamic<T>(
+        &self,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+        options: &SelectOptions,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: DynamicTable + TryFrom<Row, Error = DbError>,
+    {
+        let columns = T::dynamic_columns();
+        let condition = condition
+            .map(|condition| condition.to_sql(&columns))
+            .transpose()?;
+        let query = table::select_sql(T::dynamic_name(), &columns, condition.as_deref(), options)?;
+        debug!("SELECT for table {}: {}", T::dynamic_name(), query);
+        self.query(&query, params)
+            .await
+            .and_then(|rows| rows.into_iter().map(T::try_from).collect())
+            .context(T::dynamic_name(), Operation::Select, &query)
+    }
 
-        self.create_indices::<T, N>().await
+    async fn select_guarded<T, const N: usize>(
+        &self,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+        options: &SelectOptions,
+        guard: &CostGuard,
+    ) -> Result<Vec<T>, GuardError>
+    where
+        T: Table<N> + TryFrom<Row, Error = DbError> + Send,
+    {
+        let condition = condition
+            .map(|condition| condition.to_sql(&T::columns()))
+            .transpose()?;
+        let query = T::select_sql(condition.as_deref(), options)?;
+        let plan: ExplainedPlan = self
+            .query_one(&CostGuard::explain_sql(&query), params)
+            .await?
+            .try_get(0)?;
+        guard.check(&query, &plan)?;
+        debug!("Guarded SELECT for table {}: {}", T::name(), query);
+
+        let rows = self
+            .query(&query, params)
+            .await
+            .and_then(|rows| rows.into_iter().map(T::try_from).collect())
+            .context(T::name(), Operation::Select, &query)?;
+        Ok(rows)
     }
 
-    async fn create_types<T, const N: usize>(&self) -> Result<(), Error>
+    async fn table_checksum<T, const N: usize>(
+        &self,
+        columns: &[&str],
+    ) -> Result<TableChecksum, Error>
     where
         T: Table<N>,
     {
-        let create_types = T::create_types_sql();
+        let query = checksum_sql::<T, N>(columns)?;
+        debug!("Checksum of table {}: {}", T::name(), query);
+        let (rows, hash) = self
+            .query_one(&query, &[])
+            .await
+            .and_then(|row| FromRow::from_row(&row, 0))
+            .context(T::name(), Operation::Select, &query)?;
+        Ok(TableChecksum::new(rows, hash))
+    }
 
-        if create_types.is_empty() {
-            debug!("Skip the types for a table {:?}...", T::name());
-        } else {
-            info!("Creating the types for a table {:?}...", T::name());
-            for ty_query in create_types {
-                let type_name = ty_query.name();
-                let res = self.query(&query_type_existence(type_name), &[]).await?;
-                if res.is_empty() {
-                    let sql = ty_query.create_sql();
-                    info!("Not found type {:?}. Creating it with {:?}", type_name, sql);
-                    self.execute(sql, &[]).await?;
-                }
+    async fn estimate_count<T, const N: usize>(
+        &self,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, Error>
+    where
+        T: Table<N>,
+    {
+        if condition.is_none() {
+            let tuples = self
+                .query_opt(CostGuard::reltuples_sql(), &[&T::name()])
+                .await
+                .and_then(|row| row.map(|row| row.try_get::<_, f64>(0)).transpose())
+                .context(T::name(), Operation::Select, CostGuard::reltuples_sql())?;
+            if let Some(tuples) = tuples.filter(|tuples| *tuples >= 0.0) {
+                return Ok(tuples.round() as u64);
             }
-            info!("Types for table {} created", T::name());
         }
-        Ok(())
+
+        let condition = condition
+            .map(|condition| condition.to_sql(&T::columns()))
+            .transpose()?;
+        let query = T::select_sql(condition.as_deref(), &SelectOptions::new())?;
+        debug!(
+            "Estimating the rows count for table {}: {}",
+            T::name(),
+            query
+        );
+        let explain = CostGuard::explain_sql(&query);
+        let plan: ExplainedPlan = self
+            .query_one(&explain, params)
+            .await
+            .and_then(|row| row.try_get(0))
+            .context(T::name(), Operation::Select, &explain)?;
+        Ok(plan
+            .estimate()
+            .map_or(0, |estimate| estimate.rows.round() as u64))
     }
 
-    async fn create_indices<T, const N: usize>(&self) -> Result<(), Error>
+    async fn select_grouped<T, V, const N: usize>(
+        &self,
+        group_by: &GroupBy,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<V>, Error>
     where
         T: Table<N>,
+        V: FromRow + Send,
     {
-        let create_indices = T::create_indices_sql();
+        let query = group_by.select_sql::<T, N>()?;
+        debug!("Grouped SELECT for table {}: {}", T::name(), query);
 
-        if create_indices.is_empty() {
-            debug!("Skip the indices for a table {:?}...", T::name());
-        } else {
-            info!("Creating the indices for a table {:?}...", T::name());
-            for idx_query in create_indices {
-                let col_name = idx_query.name();
-                info!(
-                    "Creating the index {:?} for a table {:?}...",
-                    col_name,
-                    T::name()
-                );
-                let sql = idx_query.create_sql();
-                debug!("Full index query: {:?}", sql);
-                self.execute(sql, &[]).await?;
-            }
-            info!("Indices for table {} created", T::name());
-        }
-        Ok(())
+        self.query(&query, params)
+            .await
+            .and_then(|rows| rows.iter().map(|row| V::from_row(row, 0)).collect())
+            .context(T::name(), Operation::Select, &query)
     }
 
-    async fn insert_row<T, const N: usize>(&self, row: &T) -> Result<u64, Error>
+    async fn aggregate_by_time<T, V, const N: usize>(
+        &self,
+        buckets: &TimeBuckets,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<V>, Error>
     where
-        T: InsertableValues<N> + Sync,
+        T: Table<N>,
+        V: FromRow + Send,
     {
-        let query = T::insert_sql();
-        self.execute(&query, &row.values()).await
+        let query = buckets.select_sql::<T, N>()?;
+        debug!("Time-bucketed SELECT for table {}: {}", T::name(), query);
+
+        self.query(&query, params)
+            .await
+            .and_then(|rows| rows.iter().map(|row| V::from_row(row, 0)).collect())
+            .context(T::name(), Operation::Select, &query)
     }
 
-    async fn insert_rows<T, const N: usize>(&self, rows: &[T]) -> Result<u64, Error>
+    async fn run_named<T, V, const N: usize>(
+        &self,
+        name: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<V>, Error>
     where
-        T: InsertableValues<N> + Sync,
+        T: Table<N>,
+        V: FromRow + Send,
     {
-        let query = T::insert_many_sql(rows.len());
-        let params: Vec<_> = rows.iter().flat_map(|row| row.values()).collect();
-        self.execute(&query, &params).await
+        let query = T::named_query(name)?;
+        query.check_params(T::name(), params.len())?;
+        debug!("Query {:?} for table {}: {}", name, T::name(), query.sql());
+
+        self.query(query.sql(), params)
+            .await
+            .and_then(|rows| rows.iter().map(|row| V::from_row(row, 0)).collect())
+            .context(T::name(), Operation::Select, query.sql())
     }
 
-    async fn select_all<T, const N: usize>(&self) -> Result<Vec<T>, Error>
+    async fn query_scalar<V>(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<V, Error>
     where
-        T: Table<N> + TryFrom<Row, Error = Error>,
+        V: for<'a> FromSql<'a> + Send,
     {
-        self.select(None, &[]).await
+        let row = self.query_one(query, params).await?;
+        Ok(row.try_get(0)?)
     }
 
-    // TODO: make it lazy iterator
-    async fn select<T, OptionStr, const N: usize>(
+    async fn query_tuple<V>(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<V, Error>
+    where
+        V: FromRow + Send,
+    {
+        let row = self.query_one(query, params).await?;
+        Ok(V::from_row(&row, 0)?)
+    }
+
+    async fn query_tuples<V>(
         &self,
-        condition: OptionStr,
+        query: &str,
         params: &[&(dyn ToSql + Sync)],
-    ) -> Result<Vec<T>, Error>
+    ) -> Result<Vec<V>, Error>
     where
-        T: Table<N> + TryFrom<Row, Error = Error>,
-        OptionStr: Into<Option<String>> + Send,
+        V: FromRow + Send,
     {
-        let name = T::name();
-        let query = format!("SELECT * FROM {}", name);
-        let query = if let Some(condition) = condition.into() {
-            format!("{} WHERE {}", query, condition)
-        } else {
+        let rows = self.query(query, params).await?;
+        Ok(rows
+            .iter()
+            .map(|row| V::from_row(row, 0))
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn schedule_retry<T, const N: usize>(
+        &self,
+        backoff: &Backoff,
+        condition: &Expr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, Error>
+    where
+        T: Table<N>,
+    {
+        let query = backoff.schedule_retry_sql::<T, N>(condition)?;
+        debug!("Schedule retry for table {}: {}", T::name(), query);
+        self.execute(&query, params)
+            .await
+            .context(T::name(), Operation::Update, &query)
+    }
+
+    async fn select_joined<L, R, const N: usize, const M: usize>(
+        &self,
+        join: &Join,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<(L, R)>, JoinError>
+    where
+        L: Table<N> + FromRow + Send,
+        R: Table<M> + FromRow + Send,
+    {
+        let query = join.select_sql::<L, R, N, M>(JoinKind::Inner, condition)?;
+        debug!(
+            "SELECT for tables {} and {}: {}",
+            L::name(),
+            R::name(),
             query
+        );
+
+        let rows = self.query(&query, params).await?;
+        rows.iter()
+            .map(|row| Ok((L::from_row(row, 0)?, R::from_row(row, N)?)))
+            .collect()
+    }
+
+    async fn select_left_joined<L, R, const N: usize, const M: usize>(
+        &self,
+        join: &Join,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<(L, Option<R>)>, JoinError>
+    where
+        L: Table<N> + FromRow + Send,
+        R: Table<M> + FromRow + Send,
+    {
+        let query = join.select_sql::<L, R, N, M>(JoinKind::Left, condition)?;
+        debug!(
+            "SELECT for tables {} and {}: {}",
+            L::name(),
+            R::name(),
+            query
+        );
+
+        let rows = self.query(&query, params).await?;
+        rows.iter()
+            .map(|row| {
+                let left = L::from_row(row, 0)?;
+                let matched: bool = row.try_get(MATCHED_COLUMN)?;
+                let right = if matched {
+                    Some(R::from_row(row, N)?)
+                } else {
+                    None
+                };
+                Ok((left, right))
+            })
+            .collect()
+    }
+
+    async fn select_lateral<L, R, const N: usize, const M: usize>(
+        &self,
+        join: &Join,
+        condition: Option<&Expr>,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<(L, Vec<R>)>, JoinError>
+    where
+        L: Table<N> + FromRow + Send,
+        R: Table<M> + FromRow + Send,
+    {
+        let query = if join.is_lateral() {
+            join.select_sql::<L, R, N, M>(JoinKind::Left, condition)?
+        } else {
+            join.clone()
+                .lateral(SelectOptions::new())
+                .select_sql::<L, R, N, M>(JoinKind::Left, condition)?
         };
+        debug!(
+            "LATERAL SELECT for tables {} and {}: {}",
+            L::name(),
+            R::name(),
+            query
+        );
 
         let rows = self.query(&query, params).await?;
-        rows.into_iter().map(T::try_from).collect()
+        let mut grouped: Vec<(L, Vec<R>)> = Vec::new();
+        for row in &rows {
+            let position: Option<i64> = row.try_get(POSITION_COLUMN)?;
+            let right = if position.is_some() {
+                Some(R::from_row(row, N)?)
+            } else {
+                None
+            };
+            match grouped.last_mut() {
+                Some((_, matched)) if matches!(position, Some(pos) if pos > 1) => {
+                    matched.extend(right);
+                }
+                _ => grouped.push((L::from_row(row, 0)?, right.into_iter().collect())),
+            }
+        }
+        Ok(grouped)
     }
 }
 
@@ -164,40 +1537,14 @@ impl PgTableExtension for Client {
 /// ```
 #[cfg(test)]
 mod tests {
-    use std::{marker::PhantomData, sync::Once};
+    use std::marker::PhantomData;
 
     use super::*;
-    use crate::{Column, ColumnBuilder};
+    use crate::{test_helpers::get_async_client, Column, ColumnBuilder};
 
     use postgres_types::Type;
-    use uuid::Uuid;
-
-    static INIT: Once = Once::new();
-
-    /// Setup function that is only run once, even if called multiple times.
-    fn setup() {
-        INIT.call_once(|| {
-            if let Err(err) = env_logger::try_init() {
-                info!("logger probably initialized before: {}", err);
-            }
-        });
-    }
-
-    async fn get_client() -> Option<Client> {
-        setup();
-        let db_url = std::env::var("DATABASE_URL").ok()?;
-        let (client, connection) = tokio_postgres::connect(&db_url, postgres::NoTls)
-            .await
-            .unwrap();
-
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("connection error: {}", e);
-            }
-        });
 
-        Some(client)
-    }
+    use uuid::Uuid;
 
     struct Roundtrip<T, const N: usize>
     where
@@ -218,7 +1565,7 @@ mod tests {
 
         async fn drop_table() {
             // TODO: make a `Drop` type to correctly remove all the artifacts from the DB on failure
-            if let Some(client) = get_client().await {
+            if let Some(client) = get_async_client().await {
                 client
                     .execute(&format!("DROP TABLE {}", T::name()), &[])
                     .await
@@ -243,11 +1590,11 @@ mod tests {
             + InsertableValues<N>
             + PartialEq
             + std::fmt::Debug
-            + TryFrom<Row, Error = Error>
+            + TryFrom<Row, Error = DbError>
             + Sync,
     {
         async fn run(&self, items: &[T]) {
-            if let Some(client) = get_client().await {
+            if let Some(client) = get_async_client().await {
                 client.create_table::<T, N>().await.unwrap();
 
                 let inserted = if items.is_empty() {
@@ -341,7 +1688,7 @@ mod tests {
         }
 
         impl TryFrom<Row> for Buy {
-            type Error = Error;
+            type Error = DbError;
 
             fn try_from(value: Row) -> Result<Self, Self::Error> {
                 let buy_id = value.try_get("buy_id")?;
@@ -370,7 +1717,7 @@ mod tests {
                 details: None,
             };
 
-            if let Some(client) = get_client().await {
+            if let Some(client) = get_async_client().await {
                 client.create_table::<User, 1>().await.unwrap();
                 client.insert_row(&User { user_id }).await.unwrap();
 
@@ -393,7 +1740,7 @@ mod tests {
                 details: Some("the delivery should be performed".into()),
             };
 
-            if let Some(client) = get_client().await {
+            if let Some(client) = get_async_client().await {
                 client.create_table::<User, 1>().await.unwrap();
                 client.insert_row(&User { user_id }).await.unwrap();
                 Roundtrip::<_, 5>::new().run(&[b]).await;
@@ -404,6 +1751,29 @@ mod tests {
             }
         }
 
+        #[tokio::test]
+        async fn insert_in_transaction() {
+            let user_id = Uuid::new_v4();
+
+            if let Some(mut client) = get_async_client().await {
+                let tx = client.transaction().await.unwrap();
+                tx.create_table::<User, 1>().await.unwrap();
+                tx.insert_row(&User { user_id }).await.unwrap();
+                let count: i64 = tx
+                    .query_scalar(&format!("SELECT count(*) FROM {}", User::name()), &[])
+                    .await
+                    .unwrap();
+                assert_eq!(count, 1);
+                tx.rollback().await.unwrap();
+
+                let exists: bool = client
+                    .query_scalar("SELECT to_regclass($1) IS NOT NULL", &[&User::name()])
+                    .await
+                    .unwrap();
+                assert!(!exists);
+            }
+        }
+
         #[tokio::test]
         async fn insert_both() {
             let user_id = Uuid::new_v4();
@@ -424,7 +1794,7 @@ mod tests {
                 },
             ];
 
-            if let Some(client) = get_client().await {
+            if let Some(client) = get_async_client().await {
                 client.create_table::<User, 1>().await.unwrap();
                 client.insert_row(&User { user_id }).await.unwrap();
                 Roundtrip::<_, 5>::new().run(&buys).await;
@@ -436,6 +1806,111 @@ mod tests {
         }
     }
 
+    mod pipelined {
+        use super::*;
+
+        crate::gen_table! {
+            #[derive(Debug, PartialEq)]
+            struct Reading("readings") {
+                sensor: i32 = Type::INT4,
+                value: f64 = Type::FLOAT8,
+            }
+        }
+
+        #[tokio::test]
+        async fn insert_many() {
+            let Some(client) = get_async_client().await else {
+                return;
+            };
+            client.create_table::<Reading, 2>().await.unwrap();
+
+            let readings: Vec<_> = (0..500)
+                .map(|i| Reading {
+                    sensor: i % 7,
+                    value: f64::from(i) / 10.0,
+                })
+                .collect();
+            let inserted = client.insert_rows_pipelined(&readings).await.unwrap();
+            assert_eq!(inserted, 500);
+            assert_eq!(
+                client
+                    .insert_rows_pipelined::<Reading, 2>(&[])
+                    .await
+                    .unwrap(),
+                0
+            );
+
+            let sum: f64 = client
+                .query_scalar(&format!("SELECT sum(value) FROM {}", Reading::name()), &[])
+                .await
+                .unwrap();
+            assert!((sum - 12475.0).abs() < 1e-6);
+
+            client
+                .execute(&format!("DROP TABLE {}", Reading::name()), &[])
+                .await
+                .unwrap();
+        }
+    }
+
+    mod get_or_create {
+        use std::time::Duration;
+
+        use super::*;
+
+        crate::gen_table! {
+            #[derive(Debug, PartialEq)]
+            struct Tag("tags") {
+                tag_id: i32 = Type::INT4; [primary_key()],
+                name: String = Type::TEXT; [unique()],
+            }
+        }
+
+        #[tokio::test]
+        async fn lost_race_in_transaction() {
+            let (Some(mut client), Some(mut creator)) =
+                (get_async_client().await, get_async_client().await)
+            else {
+                return;
+            };
+            client.create_table::<Tag, 2>().await.unwrap();
+
+            let creator_tx = creator.transaction().await.unwrap();
+            let theirs = Tag {
+                tag_id: 1,
+                name: "sql".into(),
+            };
+            creator_tx.insert_row(&theirs).await.unwrap();
+
+            let tx = client.transaction().await.unwrap();
+            let ours = Tag {
+                tag_id: 2,
+                name: "sql".into(),
+            };
+            let (found, is_new) = tokio::join!(tx.get_or_create(&ours, &["name"]), async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                creator_tx.commit().await.unwrap();
+            })
+            .0
+            .unwrap();
+            assert_eq!(found, theirs);
+            assert!(!is_new);
+
+            // the transaction is still usable after the failed insert
+            let count: i64 = tx
+                .query_scalar(&format!("SELECT count(*) FROM {}", Tag::name()), &[])
+                .await
+                .unwrap();
+            assert_eq!(count, 1);
+            tx.commit().await.unwrap();
+
+            client
+                .execute(&format!("DROP TABLE {}", Tag::name()), &[])
+                .await
+                .unwrap();
+        }
+    }
+
     mod table_with_complex_fields {
         use super::*;
         use crate::struct_type;
@@ -477,7 +1952,7 @@ mod tests {
         }
 
         impl TryFrom<Row> for Image {
-            type Error = Error;
+            type Error = DbError;
 
             fn try_from(value: Row) -> Result<Self, Self::Error> {
                 let point_top_left = value.try_get("top_left")?;
@@ -570,7 +2045,7 @@ mod tests {
         }
 
         impl TryFrom<Row> for SingleValuedTable {
-            type Error = Error;
+            type Error = DbError;
 
             fn try_from(value: Row) -> Result<Self, Self::Error> {
                 let val = value.try_get("val")?;
@@ -630,7 +2105,7 @@ mod tests {
         }
 
         impl TryFrom<Row> for Figure {
-            type Error = Error;
+            type Error = DbError;
 
             fn try_from(value: Row) -> Result<Self, Self::Error> {
                 let name = value.try_get("name")?;
@@ -0,0 +1,366 @@
+use std::{fmt, marker::PhantomData};
+
+use itertools::Itertools as _;
+use log::info;
+
+use crate::{
+    column::Column,
+    config::unqualified,
+    dyn_table::DynTable,
+    error::{Context as _, Error, Operation},
+    ext::SyncClient,
+    table::{Table, TableSchema},
+    type_helpers::{quote_literal, validate_name, ObjectAndCreateSql},
+};
+
+/// The remote PostgreSQL database accessed with the `postgres_fdw`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignServer {
+    name: String,
+    options: Vec<(String, String)>,
+}
+
+impl ForeignServer {
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn new(name: impl Into<String>, host: &str, dbname: &str) -> Self {
+        let name = name.into();
+        validate_name("server", &name);
+        Self {
+            name,
+            options: vec![],
+        }
+        .option("host", host)
+        .option("dbname", dbname)
+    }
+
+    pub fn port(self, port: u16) -> Self {
+        self.option("port", &port.to_string())
+    }
+
+    /// Any other connection option of the `libpq` or the `postgres_fdw`
+    /// (e.g. `fetch_size` or `use_remote_estimate`).
+    ///
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn option(mut self, name: &str, value: &str) -> Self {
+        validate_name("option", name);
+        self.options.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn create_sql(&self) -> String {
+        format!(
+            "CREATE SERVER IF NOT EXISTS {} FOREIGN DATA WRAPPER postgres_fdw{}",
+            self.name,
+            options_sql(&self.options)
+        )
+    }
+}
+
+/// The credentials used by the local role to connect to the [`ForeignServer`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct UserMapping {
+    server: String,
+    local_user: Option<String>,
+    options: Vec<(String, String)>,
+}
+
+impl UserMapping {
+    /// The mapping for the `CURRENT_USER` without the credentials
+    /// (only allowed for the superusers).
+    pub fn new(server: &ForeignServer) -> Self {
+        Self {
+            server: server.name.clone(),
+            local_user: None,
+            options: vec![],
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn for_user(mut self, local_user: impl Into<String>) -> Self {
+        let local_user = local_user.into();
+        validate_name("role", &local_user);
+        self.local_user = Some(local_user);
+        self
+    }
+
+    /// The remote role to connect as.
+    pub fn credentials(mut self, user: &str, password: &str) -> Self {
+        self.options = vec![
+            ("user".into(), user.to_owned()),
+            ("password".into(), password.to_owned()),
+        ];
+        self
+    }
+
+    fn create_sql(&self) -> String {
+        format!(
+            "CREATE USER MAPPING IF NOT EXISTS FOR {} SERVER {}{}",
+            self.local_user.as_deref().unwrap_or("CURRENT_USER"),
+            self.server,
+            options_sql(&self.options)
+        )
+    }
+}
+
+// the password is not shown
+impl fmt::Debug for UserMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserMapping")
+            .field("server", &self.server)
+            .field("local_user", &self.local_user)
+            .finish_non_exhaustive()
+    }
+}
+
+fn options_sql(options: &[(String, String)]) -> String {
+    if options.is_empty() {
+        return String::new();
+    }
+    let options = options
+        .iter()
+        .map(|(name, value)| format!("{} {}", name, quote_literal(value)))
+        .join(", ");
+    format!(" OPTIONS ({})", options)
+}
+
+/// Install the `postgres_fdw` extension, create the server and the user mapping
+/// (the existing ones are left intact).
+///
+/// # Errors
+///
+/// If any of the statements fails.
+pub fn create_foreign_server<C>(
+    client: &mut C,
+    server: &ForeignServer,
+    mapping: &UserMapping,
+) -> Result<(), Error>
+where
+    C: SyncClient,
+{
+    info!("Creating the foreign server {}...", server.name);
+    let statements = [
+        "CREATE EXTENSION IF NOT EXISTS postgres_fdw".to_owned(),
+        server.create_sql(),
+        mapping.create_sql(),
+    ];
+    for sql in &statements {
+        client
+            .batch_execute(sql)
+            .context(&server.name, Operation::Create, sql)?;
+    }
+    Ok(())
+}
+
+/// The local proxy of the table `T` stored on the [`ForeignServer`]
+/// under the same name.
+///
+/// Created with the [`create_dyn_table`][crate::PgTableExtension::create_dyn_table]
+/// and queried with the other `*_dyn` extensions.
+pub struct ForeignTable<T, const N: usize> {
+    name: String,
+    server: String,
+    table: PhantomData<fn() -> T>,
+}
+
+impl<T, const N: usize> ForeignTable<T, N>
+where
+    T: Table<N>,
+{
+    /// # Panics
+    ///
+    /// If the local name (or its schema) is not a valid lowercase identifier.
+    pub fn new(name: impl Into<String>, server: &ForeignServer) -> Self {
+        let name = name.into();
+        for part in name.splitn(2, '.') {
+            validate_name("table", part);
+        }
+        Self {
+            name,
+            server: server.name.clone(),
+            table: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<T, const N: usize> Clone for ForeignTable<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            server: self.server.clone(),
+            table: PhantomData,
+        }
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for ForeignTable<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ForeignTable")
+            .field("name", &self.name)
+            .field("server", &self.server)
+            .finish()
+    }
+}
+
+/// The constraints other than `NOT NULL` are checked on the remote side,
+/// so only the types and the defaults are declared.
+fn foreign_column_sql(col: &Column) -> String {
+    let default = col
+        .default_sql()
+        .map(|default| format!(" DEFAULT ({})", default))
+        .unwrap_or_default();
+    let nullable = if col.is_nullable() {
+        "NULL"
+    } else {
+        "NOT NULL"
+    };
+    format!("{} {}{} {}", col.name(), col.type_sql(), default, nullable)
+}
+
+impl<T, const N: usize> DynTable<N> for ForeignTable<T, N>
+where
+    T: Table<N>,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn columns(&self) -> [Column; N] {
+        T::columns()
+    }
+
+    fn create_table_sql(&self) -> String {
+        let columns = self.columns().iter().map(foreign_column_sql).join(", ");
+        format!(
+            "CREATE FOREIGN TABLE IF NOT EXISTS {} ({}) SERVER {} \
+            OPTIONS (schema_name {}, table_name {});",
+            self.name,
+            columns,
+            self.server,
+            quote_literal(T::schema()),
+            quote_literal(unqualified(T::name()))
+        )
+    }
+
+    /// The foreign tables could not be indexed locally.
+    fn create_indices_sql(&self) -> Vec<ObjectAndCreateSql> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{
+        gen_table, param, test_helpers::get_client, Expr, PgTableExtension as _, SelectOptions,
+    };
+
+    gen_table!(
+        #[derive(Debug, PartialEq)]
+        struct Item("fdw_items") {
+            id: i32 = Type::INT4; [primary_key()],
+            title: String = Type::TEXT; [default_value("untitled")],
+            note: Option<String> = Type::TEXT; [nullable()],
+        }
+    );
+
+    #[test]
+    fn foreign_sql() {
+        let server = ForeignServer::new("archive", "db.example.com", "archive").port(5433);
+        assert_eq!(
+            server.create_sql(),
+            "CREATE SERVER IF NOT EXISTS archive FOREIGN DATA WRAPPER postgres_fdw \
+            OPTIONS (host 'db.example.com', dbname 'archive', port '5433')"
+        );
+        let mapping = UserMapping::new(&server)
+            .for_user("reporter")
+            .credentials("reader", "it's secret");
+        assert_eq!(
+            mapping.create_sql(),
+            "CREATE USER MAPPING IF NOT EXISTS FOR reporter SERVER archive \
+            OPTIONS (user 'reader', password 'it''s secret')"
+        );
+        assert!(!format!("{:?}", mapping).contains("secret"));
+
+        let items = ForeignTable::<Item, 3>::new("archived_items", &server);
+        assert_eq!(
+            items.create_table_sql(),
+            "CREATE FOREIGN TABLE IF NOT EXISTS archived_items \
+            (id int4 NOT NULL, title text DEFAULT ('untitled') NOT NULL, note text NULL) \
+            SERVER archive OPTIONS (schema_name 'public', table_name 'fdw_items');"
+        );
+        assert!(items.create_indices_sql().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid option name \"fetch_size 1) --\"")]
+    fn injected_option() {
+        let _ = ForeignServer::new("archive", "db.example.com", "archive")
+            .option("fetch_size 1) --", "");
+    }
+
+    /// The server pointing to the same database.
+    #[test]
+    fn loopback() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP FOREIGN TABLE IF EXISTS fdw_remote_items; \
+                DROP TABLE IF EXISTS fdw_items",
+            )
+            .unwrap();
+        client.create_table::<Item, 3>().unwrap();
+        let items = [(1, "first"), (2, "second")].map(|(id, title)| Item {
+            id,
+            title: title.into(),
+            note: None,
+        });
+        client.insert_rows(&items).unwrap();
+
+        let row = client
+            .query_one(
+                "SELECT current_database()::text, current_setting('port')",
+                &[],
+            )
+            .unwrap();
+        let (dbname, port): (String, String) = (row.get(0), row.get(1));
+        let server =
+            ForeignServer::new("fdw_loopback", "localhost", &dbname).port(port.parse().unwrap());
+        create_foreign_server(&mut client, &server, &UserMapping::new(&server)).unwrap();
+        // once again to check the existing objects are tolerated
+        create_foreign_server(&mut client, &server, &UserMapping::new(&server)).unwrap();
+
+        let remote = ForeignTable::<Item, 3>::new("fdw_remote_items", &server);
+        client.create_dyn_table(&remote).unwrap();
+        let found: Vec<Item> = client
+            .select_dyn(
+                &remote,
+                Some(&Expr::col("id").eq(param(1))),
+                &[&2_i32],
+                &SelectOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(found, items[1..]);
+
+        client
+            .batch_execute("DROP FOREIGN TABLE fdw_remote_items; DROP TABLE fdw_items")
+            .unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+use std::fmt::{self, Display, Write as _};
+
+use itertools::Itertools as _;
+use postgres_types::Type;
+
+use crate::type_helpers::{quote_literal, validate_name};
+
+/// The language of the [`SqlFunction`] body.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FunctionLanguage {
+    Sql,
+    PlPgSql,
+}
+
+impl Display for FunctionLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            FunctionLanguage::Sql => "sql",
+            FunctionLanguage::PlPgSql => "plpgsql",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+/// The guarantees of the [`SqlFunction`] the planner relies on.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Volatility {
+    #[default]
+    Volatile,
+    /// The same result for the same arguments within a statement.
+    Stable,
+    /// The same result for the same arguments forever
+    /// (required to use the function in the indexes).
+    Immutable,
+}
+
+impl Display for Volatility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            Volatility::Volatile => "VOLATILE",
+            Volatility::Stable => "STABLE",
+            Volatility::Immutable => "IMMUTABLE",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+/// The stored function or procedure shipped with the schema
+/// (see the [`Table::functions`][crate::Table::functions]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlFunction {
+    name: String,
+    args: Vec<(String, Type)>,
+    returns: Option<String>,
+    language: FunctionLanguage,
+    volatility: Volatility,
+    body: String,
+}
+
+impl SqlFunction {
+    /// The function returning the type (e.g. `int4`, `SETOF text` or `trigger`).
+    ///
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier
+    /// (optionally qualified with the schema).
+    pub fn new(
+        name: impl AsRef<str>,
+        returns: impl AsRef<str>,
+        language: FunctionLanguage,
+        body: impl AsRef<str>,
+    ) -> Self {
+        let mut function = Self::procedure(name, language, body);
+        function.returns = Some(returns.as_ref().to_owned());
+        function
+    }
+
+    /// The _PL/pgSQL_ function to be invoked by the [`Trigger`][crate::Trigger].
+    ///
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier
+    /// (optionally qualified with the schema).
+    pub fn trigger(name: impl AsRef<str>, body: impl AsRef<str>) -> Self {
+        Self::new(name, "trigger", FunctionLanguage::PlPgSql, body)
+    }
+
+    /// The procedure invoked with the `CALL` (it can commit the transactions).
+    ///
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier
+    /// (optionally qualified with the schema).
+    pub fn procedure(
+        name: impl AsRef<str>,
+        language: FunctionLanguage,
+        body: impl AsRef<str>,
+    ) -> Self {
+        let name = name.as_ref();
+        for part in name.splitn(2, '.') {
+            validate_name("function", part);
+        }
+        Self {
+            name: name.to_owned(),
+            args: vec![],
+            returns: None,
+            language,
+            volatility: Volatility::default(),
+            body: body.as_ref().to_owned(),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn arg(mut self, name: impl AsRef<str>, ty: &Type) -> Self {
+        let name = name.as_ref();
+        validate_name("argument", name);
+        self.args.push((name.to_owned(), ty.clone()));
+        self
+    }
+
+    /// Ignored by the procedures.
+    pub const fn volatility(mut self, volatility: Volatility) -> Self {
+        self.volatility = volatility;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub const fn is_procedure(&self) -> bool {
+        self.returns.is_none()
+    }
+
+    /// The name with the types of the arguments identifying the function
+    /// (e.g. `add_tax(int8,int4)`).
+    pub fn signature(&self) -> String {
+        format!(
+            "{}({})",
+            self.name,
+            self.args.iter().map(|(_, ty)| ty.name()).join(",")
+        )
+    }
+
+    /// The _CREATE FUNCTION_ (or _CREATE PROCEDURE_) statement.
+    pub fn create_sql(&self) -> String {
+        let args = self
+            .args
+            .iter()
+            .map(|(name, ty)| format!("{} {}", name, ty.name()))
+            .join(", ");
+        let mut sql = match &self.returns {
+            Some(returns) => format!(
+                "CREATE FUNCTION {}({}) RETURNS {} LANGUAGE {} {}",
+                self.name, args, returns, self.language, self.volatility
+            ),
+            None => format!(
+                "CREATE PROCEDURE {}({}) LANGUAGE {}",
+                self.name, args, self.language
+            ),
+        };
+        write!(sql, " AS $function$ {} $function$", self.body.trim()).unwrap();
+        sql
+    }
+
+    /// The function is created unless the one with the same signature exists.
+    pub(crate) fn create_if_missing_sql(&self) -> String {
+        format!(
+            "DO $pg_helper$ BEGIN \
+            IF to_regprocedure({}) IS NULL THEN {}; END IF; END $pg_helper$",
+            quote_literal(&self.signature()),
+            self.create_sql()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gen_table, test_helpers::get_client, PgTableExtension as _, Table as _, TableDef, Trigger,
+        TriggerEvent, TriggerTiming,
+    };
+
+    gen_table!(
+        struct Account("function_accounts") {
+            id: i32 = Type::INT4; [primary_key()],
+            balance: i64 = Type::INT8,
+            updates: i32 = Type::INT4,
+            => functions = [
+                SqlFunction::trigger(
+                    "function_count_updates",
+                    "BEGIN NEW.updates := OLD.updates + 1; RETURN NEW; END",
+                ),
+            ]
+            => triggers = [
+                Trigger::new(
+                    "function_accounts_updates",
+                    TriggerTiming::Before,
+                    [TriggerEvent::Update],
+                    "function_count_updates",
+                ),
+            ]
+        }
+    );
+
+    fn with_tax() -> SqlFunction {
+        SqlFunction::new(
+            "function_with_tax",
+            "int8",
+            FunctionLanguage::Sql,
+            "SELECT amount + amount * percent / 100",
+        )
+        .arg("amount", &Type::INT8)
+        .arg("percent", &Type::INT4)
+        .volatility(Volatility::Immutable)
+    }
+
+    #[test]
+    fn created_before_indices() {
+        gen_table!(
+            struct Tax("function_taxes") {
+                amount: i64 = Type::INT8; [index()],
+                => functions = [with_tax()]
+            }
+        );
+
+        let script = TableDef::of::<Tax, 1>().create_script();
+        assert_eq!(script.len(), 3);
+        assert!(script[1].contains("CREATE FUNCTION function_with_tax"));
+        assert!(script[2].starts_with("CREATE INDEX"));
+    }
+
+    #[test]
+    fn statements() {
+        let function = with_tax();
+        assert_eq!(function.signature(), "function_with_tax(int8,int4)");
+        assert_eq!(
+            function.create_sql(),
+            "CREATE FUNCTION function_with_tax(amount int8, percent int4) \
+            RETURNS int8 LANGUAGE sql IMMUTABLE \
+            AS $function$ SELECT amount + amount * percent / 100 $function$"
+        );
+        let procedure = SqlFunction::procedure(
+            "maintenance.reset",
+            FunctionLanguage::PlPgSql,
+            "BEGIN NULL; END",
+        );
+        assert!(procedure.is_procedure());
+        assert_eq!(
+            procedure.create_sql(),
+            "CREATE PROCEDURE maintenance.reset() LANGUAGE plpgsql \
+            AS $function$ BEGIN NULL; END $function$"
+        );
+        assert!(Account::functions()[0].create_if_missing_sql().starts_with(
+            "DO $pg_helper$ BEGIN IF to_regprocedure('function_count_updates()') IS NULL \
+                THEN CREATE FUNCTION function_count_updates() RETURNS trigger"
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid function name")]
+    fn invalid_name() {
+        let _ = SqlFunction::procedure("Reset", FunctionLanguage::Sql, "SELECT 1");
+    }
+
+    #[test]
+    fn shipped_with_table() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS function_accounts; \
+                DROP FUNCTION IF EXISTS function_count_updates(); \
+                DROP FUNCTION IF EXISTS function_with_tax(int8, int4)",
+            )
+            .unwrap();
+        client.create_table::<Account, 3>().unwrap();
+        // the existing function is kept
+        client.create_table::<Account, 3>().unwrap();
+        client.create_function(&with_tax()).unwrap();
+        client.create_function(&with_tax()).unwrap();
+
+        client
+            .batch_execute(
+                "INSERT INTO function_accounts VALUES (1, 100, 0); \
+                UPDATE function_accounts SET balance = function_with_tax(balance, 20); \
+                UPDATE function_accounts SET balance = function_with_tax(balance, 20)",
+            )
+            .unwrap();
+        let rows = client.select_all::<Account, 3>().unwrap();
+        assert_eq!((rows[0].balance, rows[0].updates), (144, 2));
+
+        client
+            .batch_execute(
+                "DROP TABLE function_accounts; \
+                DROP FUNCTION function_count_updates(); \
+                DROP FUNCTION function_with_tax(int8, int4)",
+            )
+            .unwrap();
+    }
+}
@@ -0,0 +1,384 @@
+use std::error::Error;
+
+use itertools::Itertools as _;
+use postgres_types::{private::BytesMut, to_sql_checked, FromSql, IsNull, ToSql, Type as DbType};
+
+use crate::{column::SqlLiteral, type_helpers::quote_literal};
+
+type DecodeResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+/// The big-endian binary input of the geometric values.
+struct Reader<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> DecodeResult<[u8; N]> {
+        if self.raw.len() < N {
+            return Err("The geometric value is truncated".into());
+        }
+        let (head, rest) = self.raw.split_at(N);
+        self.raw = rest;
+        Ok(head.try_into()?)
+    }
+
+    fn f64(&mut self) -> DecodeResult<f64> {
+        self.take().map(f64::from_be_bytes)
+    }
+
+    fn point(&mut self) -> DecodeResult<Point> {
+        Ok(Point::new(self.f64()?, self.f64()?))
+    }
+
+    fn points(&mut self) -> DecodeResult<Vec<Point>> {
+        let count = usize::try_from(self.take().map(i32::from_be_bytes)?)?;
+        (0..count).map(|_| self.point()).collect()
+    }
+
+    fn finish<T>(self, value: T) -> DecodeResult<T> {
+        if self.raw.is_empty() {
+            Ok(value)
+        } else {
+            Err("The geometric value has the trailing bytes".into())
+        }
+    }
+}
+
+fn write_points(points: &[Point], out: &mut BytesMut) -> Result<(), Box<dyn Error + Sync + Send>> {
+    out.extend_from_slice(&i32::try_from(points.len())?.to_be_bytes());
+    for point in points {
+        point.write(out);
+    }
+    Ok(())
+}
+
+fn points_sql(points: &[Point]) -> String {
+    points.iter().map(Point::sql_text).join(",")
+}
+
+/// Implement the `ToSql` and `FromSql` for the type of the column.
+macro_rules! geometric_sql {
+    ($ty:ty, $db_type:ident, |$value:ident, $out:ident| $write:expr, |$reader:ident| $read:expr) => {
+        impl ToSql for $ty {
+            fn to_sql(
+                &self,
+                _: &DbType,
+                $out: &mut BytesMut,
+            ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+                let $value = self;
+                $write;
+                Ok(IsNull::No)
+            }
+
+            fn accepts(ty: &DbType) -> bool {
+                *ty == DbType::$db_type
+            }
+
+            to_sql_checked!();
+        }
+
+        impl<'a> FromSql<'a> for $ty {
+            fn from_sql(_: &DbType, raw: &'a [u8]) -> DecodeResult<Self> {
+                let mut $reader = Reader { raw };
+                let value = $read;
+                $reader.finish(value)
+            }
+
+            fn accepts(ty: &DbType) -> bool {
+                *ty == DbType::$db_type
+            }
+        }
+    };
+}
+
+/// The `point` column.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub const fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    fn write(&self, out: &mut BytesMut) {
+        out.extend_from_slice(&self.x.to_be_bytes());
+        out.extend_from_slice(&self.y.to_be_bytes());
+    }
+
+    fn sql_text(&self) -> String {
+        format!("({},{})", self.x, self.y)
+    }
+}
+
+geometric_sql!(Point, POINT, |value, out| value.write(out), |reader| reader
+    .point()?);
+
+/// The infinite `line` column: `a*x + b*y + c = 0`.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Line {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl Line {
+    pub const fn new(a: f64, b: f64, c: f64) -> Self {
+        Self { a, b, c }
+    }
+}
+
+geometric_sql!(
+    Line,
+    LINE,
+    |value, out| {
+        for coefficient in [value.a, value.b, value.c] {
+            out.extend_from_slice(&coefficient.to_be_bytes());
+        }
+    },
+    |reader| Line::new(reader.f64()?, reader.f64()?, reader.f64()?)
+);
+
+/// The `box` column.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Rect {
+    pub high: Point,
+    pub low: Point,
+}
+
+impl Rect {
+    /// The box with any opposite corners (stored as the upper right and the lower left ones).
+    pub fn new(a: Point, b: Point) -> Self {
+        Self {
+            high: Point::new(a.x.max(b.x), a.y.max(b.y)),
+            low: Point::new(a.x.min(b.x), a.y.min(b.y)),
+        }
+    }
+}
+
+geometric_sql!(
+    Rect,
+    BOX,
+    |value, out| {
+        value.high.write(out);
+        value.low.write(out);
+    },
+    |reader| Rect {
+        high: reader.point()?,
+        low: reader.point()?,
+    }
+);
+
+/// The `path` column: the open or the closed polyline.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Path {
+    pub points: Vec<Point>,
+    pub closed: bool,
+}
+
+geometric_sql!(
+    Path,
+    PATH,
+    |value, out| {
+        out.extend_from_slice(&[u8::from(value.closed)]);
+        write_points(&value.points, out)?;
+    },
+    |reader| {
+        let [closed] = reader.take()?;
+        Path {
+            closed: closed != 0,
+            points: reader.points()?,
+        }
+    }
+);
+
+/// The `polygon` column.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polygon(pub Vec<Point>);
+
+geometric_sql!(
+    Polygon,
+    POLYGON,
+    |value, out| write_points(&value.0, out)?,
+    |reader| Polygon(reader.points()?)
+);
+
+/// The `circle` column.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f64,
+}
+
+geometric_sql!(
+    Circle,
+    CIRCLE,
+    |value, out| {
+        value.center.write(out);
+        out.extend_from_slice(&value.radius.to_be_bytes());
+    },
+    |reader| Circle {
+        center: reader.point()?,
+        radius: reader.f64()?,
+    }
+);
+
+impl SqlLiteral for Point {
+    fn to_literal(&self) -> String {
+        format!("{}::point", quote_literal(&self.sql_text()))
+    }
+}
+
+impl SqlLiteral for Line {
+    fn to_literal(&self) -> String {
+        let value = format!("{{{},{},{}}}", self.a, self.b, self.c);
+        format!("{}::line", quote_literal(&value))
+    }
+}
+
+impl SqlLiteral for Rect {
+    fn to_literal(&self) -> String {
+        let value = points_sql(&[self.high, self.low]);
+        format!("{}::box", quote_literal(&value))
+    }
+}
+
+impl SqlLiteral for Path {
+    fn to_literal(&self) -> String {
+        let points = points_sql(&self.points);
+        let value = if self.closed {
+            format!("({})", points)
+        } else {
+            format!("[{}]", points)
+        };
+        format!("{}::path", quote_literal(&value))
+    }
+}
+
+impl SqlLiteral for Polygon {
+    fn to_literal(&self) -> String {
+        let value = format!("({})", points_sql(&self.0));
+        format!("{}::polygon", quote_literal(&value))
+    }
+}
+
+impl SqlLiteral for Circle {
+    fn to_literal(&self) -> String {
+        let value = format!("<{},{}>", self.center.sql_text(), self.radius);
+        format!("{}::circle", quote_literal(&value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client, PgTableExtension as _};
+
+    gen_table!(
+        #[derive(Debug, PartialEq)]
+        struct Shape("geometry_shapes") {
+            id: i32 = DbType::INT4; [primary_key()],
+            origin: Point = DbType::POINT,
+            axis: Line = DbType::LINE,
+            bounds: Rect = DbType::BOX,
+            route: Path = DbType::PATH,
+            outline: Polygon = DbType::POLYGON,
+            area: Circle = DbType::CIRCLE,
+        }
+    );
+
+    fn shape() -> Shape {
+        let trapezoid = vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 4.0),
+            Point::new(3.0, 4.0),
+            Point::new(6.0, 0.0),
+        ];
+        Shape {
+            id: 1,
+            origin: Point::new(1.5, -2.0),
+            axis: Line::new(1.0, -1.0, 0.0),
+            bounds: Rect::new(Point::new(6.0, 0.0), Point::new(0.0, 4.0)),
+            route: Path {
+                points: trapezoid.clone(),
+                closed: false,
+            },
+            outline: Polygon(trapezoid),
+            area: Circle {
+                center: Point::new(3.0, 2.0),
+                radius: 0.5,
+            },
+        }
+    }
+
+    #[test]
+    fn literals() {
+        let shape = shape();
+        assert_eq!(shape.origin.to_literal(), "'(1.5,-2)'::point");
+        assert_eq!(shape.axis.to_literal(), "'{1,-1,0}'::line");
+        assert_eq!(shape.bounds.to_literal(), "'(6,4),(0,0)'::box");
+        assert_eq!(
+            shape.route.to_literal(),
+            "'[(0,0),(2,4),(3,4),(6,0)]'::path"
+        );
+        assert_eq!(
+            shape.outline.to_literal(),
+            "'((0,0),(2,4),(3,4),(6,0))'::polygon"
+        );
+        assert_eq!(shape.area.to_literal(), "'<(3,2),0.5>'::circle");
+    }
+
+    #[test]
+    fn truncated() {
+        let mut out = BytesMut::new();
+        Polygon(vec![Point::new(1.0, 2.0)])
+            .to_sql(&DbType::POLYGON, &mut out)
+            .unwrap();
+        assert!(Polygon::from_sql(&DbType::POLYGON, &out).is_ok());
+        assert!(Polygon::from_sql(&DbType::POLYGON, &out[..out.len() - 1]).is_err());
+        assert!(Point::from_sql(&DbType::POINT, &out).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS geometry_shapes")
+            .unwrap();
+        client.create_table::<Shape, 7>().unwrap();
+
+        client.insert_row(&shape()).unwrap();
+        assert_eq!(client.select_all::<Shape, 7>().unwrap(), [shape()]);
+
+        let row = client
+            .query_one(
+                "SELECT area(outline::path), bounds @> center(area), length(route) > 0 \
+                FROM geometry_shapes",
+                &[],
+            )
+            .unwrap();
+        assert_eq!(row.get::<_, f64>(0), 14.0);
+        assert!(row.get::<_, bool>(1));
+        assert!(row.get::<_, bool>(2));
+
+        let literals = shape();
+        let same: bool = client
+            .query_scalar(
+                &format!(
+                    "SELECT origin ~= {} AND bounds ~= {} AND outline ~= {} FROM geometry_shapes",
+                    literals.origin.to_literal(),
+                    literals.bounds.to_literal(),
+                    literals.outline.to_literal()
+                ),
+                &[],
+            )
+            .unwrap();
+        assert!(same);
+
+        client.batch_execute("DROP TABLE geometry_shapes").unwrap();
+    }
+}
@@ -0,0 +1,229 @@
+use std::{error::Error as StdError, fmt};
+
+use log::warn;
+use postgres_types::{FromSql, Type as DbType};
+use tokio_postgres::Error as DbError;
+
+use crate::error::Error;
+
+/// The planner estimates of the query (the top node of the `EXPLAIN` output).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PlanEstimate {
+    pub total_cost: f64,
+    pub rows: f64,
+}
+
+/// Thresholds on the planner estimates checked with the `EXPLAIN`
+/// before running the query (e.g. to prevent the unfiltered selects
+/// of the big tables built with the dynamic filters).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CostGuard {
+    max_cost: Option<f64>,
+    max_rows: Option<f64>,
+    reject: bool,
+}
+
+impl Default for CostGuard {
+    fn default() -> Self {
+        Self {
+            max_cost: None,
+            max_rows: None,
+            reject: true,
+        }
+    }
+}
+
+impl CostGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The limit of the estimated total cost (in the planner units).
+    pub const fn max_cost(mut self, cost: f64) -> Self {
+        self.max_cost = Some(cost);
+        self
+    }
+
+    /// The limit of the estimated number of the returned rows.
+    pub const fn max_rows(mut self, rows: f64) -> Self {
+        self.max_rows = Some(rows);
+        self
+    }
+
+    /// Only log a warning and run the query anyway if the limits are exceeded.
+    pub const fn warn_only(mut self) -> Self {
+        self.reject = false;
+        self
+    }
+
+    pub(crate) fn explain_sql(query: &str) -> String {
+        format!("EXPLAIN (FORMAT JSON) {}", query)
+    }
+
+    /// The number of rows in the table as of the last `VACUUM` or `ANALYZE`
+    /// (negative if the table has never been analyzed).
+    pub(crate) const fn reltuples_sql() -> &'static str {
+        "SELECT reltuples::float8 FROM pg_class WHERE oid = to_regclass($1)"
+    }
+
+    /// # Errors
+    ///
+    /// If the estimates exceed the limits and the guard is rejecting.
+    pub(crate) fn check(&self, query: &str, plan: &ExplainedPlan) -> Result<(), GuardError> {
+        let estimate = plan.estimate().ok_or_else(|| GuardError::NoEstimate {
+            query: query.to_owned(),
+        })?;
+        let too_expensive = matches!(self.max_cost, Some(max) if estimate.total_cost > max)
+            || matches!(self.max_rows, Some(max) if estimate.rows > max);
+        if !too_expensive {
+            return Ok(());
+        }
+
+        let err = GuardError::TooExpensive {
+            query: query.to_owned(),
+            estimate,
+        };
+        if self.reject {
+            Err(err)
+        } else {
+            warn!("{}", err);
+            Ok(())
+        }
+    }
+}
+
+/// The failure of the query checked with the [`CostGuard`].
+#[derive(Debug)]
+pub enum GuardError {
+    TooExpensive {
+        query: String,
+        estimate: PlanEstimate,
+    },
+    /// The `EXPLAIN` output has no estimates.
+    NoEstimate {
+        query: String,
+    },
+    Db(Error),
+}
+
+impl fmt::Display for GuardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooExpensive { query, estimate } => write!(
+                f,
+                "The query is too expensive (cost={}, rows={}): {}",
+                estimate.total_cost, estimate.rows, query
+            ),
+            Self::NoEstimate { query } => write!(f, "Cannot estimate the query: {}", query),
+            Self::Db(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl StdError for GuardError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::TooExpensive { .. } | Self::NoEstimate { .. } => None,
+            Self::Db(err) => Some(err),
+        }
+    }
+}
+
+impl From<Error> for GuardError {
+    fn from(err: Error) -> Self {
+        Self::Db(err)
+    }
+}
+
+impl From<DbError> for GuardError {
+    fn from(err: DbError) -> Self {
+        Self::Db(err.into())
+    }
+}
+
+/// The raw output of the `EXPLAIN (FORMAT JSON)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ExplainedPlan(String);
+
+impl ExplainedPlan {
+    /// The top node comes first and its keys precede the nested `Plans`,
+    /// so the first occurrences of the keys belong to it.
+    pub(crate) fn estimate(&self) -> Option<PlanEstimate> {
+        Some(PlanEstimate {
+            total_cost: self.number_after("\"Total Cost\":")?,
+            rows: self.number_after("\"Plan Rows\":")?,
+        })
+    }
+
+    fn number_after(&self, key: &str) -> Option<f64> {
+        let (_, rest) = self.0.split_once(key)?;
+        let rest = rest.trim_start();
+        let end = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        rest[..end].parse().ok()
+    }
+}
+
+impl<'a> FromSql<'a> for ExplainedPlan {
+    fn from_sql(_ty: &DbType, raw: &'a [u8]) -> Result<Self, Box<dyn StdError + Sync + Send>> {
+        Ok(Self(std::str::from_utf8(raw)?.to_owned()))
+    }
+
+    fn accepts(ty: &DbType) -> bool {
+        *ty == DbType::JSON
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = r#"[
+      {
+        "Plan": {
+          "Node Type": "Limit",
+          "Startup Cost": 0.00,
+          "Total Cost": 1.25e3,
+          "Plan Rows": 10,
+          "Plan Width": 36,
+          "Plans": [
+            {
+              "Node Type": "Seq Scan",
+              "Total Cost": 22.70,
+              "Plan Rows": 1270
+            }
+          ]
+        }
+      }
+    ]"#;
+
+    #[test]
+    fn top_node_estimate() {
+        let plan = ExplainedPlan(PLAN.into());
+        assert_eq!(
+            plan.estimate(),
+            Some(PlanEstimate {
+                total_cost: 1250.0,
+                rows: 10.0
+            })
+        );
+        assert_eq!(ExplainedPlan("[]".into()).estimate(), None);
+    }
+
+    #[test]
+    fn limits() {
+        let plan = ExplainedPlan(PLAN.into());
+        assert!(CostGuard::new().check("q", &plan).is_ok());
+        assert!(CostGuard::new().max_rows(10.0).check("q", &plan).is_ok());
+        assert!(matches!(
+            CostGuard::new().max_cost(1000.0).check("q", &plan),
+            Err(GuardError::TooExpensive { .. })
+        ));
+        assert!(CostGuard::new()
+            .max_cost(1000.0)
+            .warn_only()
+            .check("q", &plan)
+            .is_ok());
+    }
+}
@@ -0,0 +1,113 @@
+use http::StatusCode;
+use tokio_postgres::error::SqlState;
+
+use crate::error::Error;
+
+/// The message of the driver when the `query_one` gets no rows.
+const UNEXPECTED_ROWS: &str = "query returned an unexpected number of rows";
+
+impl Error {
+    /// The HTTP status to respond with when the request fails with the error
+    /// (enabled with the `http` feature).
+    ///
+    /// | Failure                                    | Status                      |
+    /// |--------------------------------------------|-----------------------------|
+    /// | unique or exclusion violation              | `409 Conflict`              |
+    /// | foreign key, check or not-null violation   | `422 Unprocessable Entity`  |
+    /// | invalid data (e.g. numeric out of range)   | `400 Bad Request`           |
+    /// | no row for the `query_one`                 | `404 Not Found`             |
+    /// | serialization failure or deadlock          | `409 Conflict`              |
+    /// | the connection is closed                   | `503 Service Unavailable`   |
+    /// | unknown named query or its parameters      | `400 Bad Request`           |
+    /// | anything else                              | `500 Internal Server Error` |
+    pub fn status_code(&self) -> StatusCode {
+        let Some(code) = self.code() else {
+            let Some(err) = self.db_error() else {
+                return StatusCode::BAD_REQUEST;
+            };
+            return if err.is_closed() {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else if err.to_string() == UNEXPECTED_ROWS {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+        };
+
+        if *code == SqlState::UNIQUE_VIOLATION
+            || *code == SqlState::EXCLUSION_VIOLATION
+            || *code == SqlState::T_R_SERIALIZATION_FAILURE
+            || *code == SqlState::T_R_DEADLOCK_DETECTED
+        {
+            StatusCode::CONFLICT
+        } else if *code == SqlState::FOREIGN_KEY_VIOLATION
+            || *code == SqlState::CHECK_VIOLATION
+            || *code == SqlState::NOT_NULL_VIOLATION
+        {
+            StatusCode::UNPROCESSABLE_ENTITY
+        } else if code.code().starts_with("22") {
+            // the class 22 is the data exception
+            StatusCode::BAD_REQUEST
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres::Client;
+
+    use super::*;
+
+    use crate::test_helpers::get_client;
+
+    fn status(client: &mut Client, query: &str) -> StatusCode {
+        let err: Error = client.batch_execute(query).unwrap_err().into();
+        err.status_code()
+    }
+
+    #[test]
+    fn violations() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "CREATE TEMPORARY TABLE http_parents (id int4 PRIMARY KEY); \
+                CREATE TEMPORARY TABLE http_children (\
+                    id int4 PRIMARY KEY CHECK (id > 0), \
+                    parent_id int4 NOT NULL REFERENCES http_parents(id)\
+                ); \
+                INSERT INTO http_parents VALUES (1)",
+            )
+            .unwrap();
+
+        assert_eq!(
+            status(&mut client, "INSERT INTO http_parents VALUES (1)"),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status(&mut client, "INSERT INTO http_children VALUES (1, 2)"),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            status(&mut client, "INSERT INTO http_children VALUES (-1, 1)"),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            status(&mut client, "SELECT 'x'::int4"),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(&mut client, "SELECT * FROM http_missing"),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        let err: Error = client
+            .query_one("SELECT id FROM http_parents WHERE id = 2", &[])
+            .unwrap_err()
+            .into();
+        assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,394 @@
+use std::fmt::{self, Display, Write as _};
+
+use itertools::Itertools as _;
+
+use crate::{
+    column::Column,
+    config::unqualified,
+    query::{NullsOrder, Order},
+    type_helpers::{shorten_identifier, storage_params_sql, validate_name, ObjectAndCreateSql},
+};
+
+/// The column of the [`Index`] with the order of its values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexKey {
+    column: String,
+    opclass: Option<String>,
+    order: Option<Order>,
+    nulls: Option<NullsOrder>,
+}
+
+impl IndexKey {
+    pub fn new(column: &Column) -> Self {
+        Self {
+            column: column.name().to_owned(),
+            opclass: None,
+            order: None,
+            nulls: None,
+        }
+    }
+
+    /// The operator class of the key other than the default one for its type
+    /// (e.g. the `jsonb_path_ops` for the [`IndexMethod::Gin`]).
+    pub fn opclass(mut self, opclass: impl AsRef<str>) -> Self {
+        self.opclass = Some(opclass.as_ref().to_owned());
+        self
+    }
+
+    pub const fn order(mut self, order: Order) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    pub const fn nulls(mut self, nulls: NullsOrder) -> Self {
+        self.nulls = Some(nulls);
+        self
+    }
+
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+}
+
+impl From<&Column> for IndexKey {
+    fn from(column: &Column) -> Self {
+        Self::new(column)
+    }
+}
+
+impl Display for IndexKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.column)?;
+        if let Some(opclass) = &self.opclass {
+            write!(f, " {}", opclass)?;
+        }
+        if let Some(order) = self.order {
+            write!(f, " {}", order)?;
+        }
+        if let Some(nulls) = self.nulls {
+            write!(f, " {}", nulls)?;
+        }
+        Ok(())
+    }
+}
+
+/// The index over one or more columns of the table (see the [`Table::indexes`][crate::Table::indexes]).
+#[derive(Debug, Clone)]
+pub struct Index {
+    name: Option<String>,
+    keys: Vec<IndexKey>,
+    method: IndexMethod,
+    options: IndexOptions,
+    predicate: Option<String>,
+}
+
+impl Index {
+    /// # Panics
+    ///
+    /// If no keys are given.
+    pub fn new<K>(keys: impl IntoIterator<Item = K>) -> Self
+    where
+        K: Into<IndexKey>,
+    {
+        let keys: Vec<_> = keys.into_iter().map(Into::into).collect();
+        assert!(!keys.is_empty(), "The index should have at least one key");
+        Self {
+            name: None,
+            keys,
+            method: IndexMethod::default(),
+            options: IndexOptions::default(),
+            predicate: None,
+        }
+    }
+
+    /// Use the name instead of the generated one.
+    ///
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn named(mut self, name: impl AsRef<str>) -> Self {
+        let name = name.as_ref();
+        validate_name("index", name);
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    pub const fn using(mut self, method: IndexMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn options(mut self, options: IndexOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Only index the rows matching the condition (e.g. `deleted_at IS NULL`).
+    pub fn predicate(mut self, condition: impl AsRef<str>) -> Self {
+        self.predicate = Some(condition.as_ref().to_owned());
+        self
+    }
+
+    pub fn keys(&self) -> &[IndexKey] {
+        &self.keys
+    }
+
+    pub const fn method(&self) -> IndexMethod {
+        self.method
+    }
+
+    /// The [explicit name][Self::named] or the generated one:
+    /// `{column1}[_{column2}...]_idx_{table}`, the names longer than 63 bytes
+    /// are truncated and suffixed with the hash of the full name.
+    pub fn name(&self, table_name: &str) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        // the index is created in the schema of the table
+        shorten_identifier(format!(
+            "{}_idx_{}",
+            self.keys.iter().map(IndexKey::column).join("_"),
+            unqualified(table_name)
+        ))
+    }
+
+    /// Place the index into the tablespace unless it has its own one.
+    pub(crate) fn default_tablespace(mut self, tablespace: &str) -> Self {
+        if self.options.tablespace.is_none() {
+            self.options.tablespace = Some(tablespace.to_owned());
+        }
+        self
+    }
+
+    pub fn get_options(&self) -> &IndexOptions {
+        &self.options
+    }
+
+    pub fn get_predicate(&self) -> Option<&str> {
+        self.predicate.as_deref()
+    }
+
+    pub fn create_sql(&self, table_name: &str) -> String {
+        let mut sql = format!(
+            "CREATE INDEX IF NOT EXISTS {} ON {} USING {} ({})",
+            self.name(table_name),
+            table_name,
+            self.method,
+            self.keys.iter().join(", ")
+        );
+        sql.push_str(&self.options.sql());
+        if let Some(predicate) = &self.predicate {
+            write!(sql, " WHERE ({})", predicate).unwrap();
+        }
+        sql
+    }
+
+    pub(crate) fn object_sql(&self, table_name: &str) -> ObjectAndCreateSql {
+        ObjectAndCreateSql::new(self.name(table_name), self.create_sql(table_name))
+    }
+}
+
+/// The storage parameters and the placement of the [`Index`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexOptions {
+    params: Vec<(String, String)>,
+    tablespace: Option<String>,
+}
+
+impl IndexOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The percentage of the index pages filled on its build
+    /// (the space left is used by the updates of the rows).
+    ///
+    /// # Panics
+    ///
+    /// If the value is not in the `10..=100` range.
+    pub fn fillfactor(self, percent: u8) -> Self {
+        assert!(
+            (10..=100).contains(&percent),
+            "The fillfactor should be in the range 10..=100, got {}",
+            percent
+        );
+        self.param("fillfactor", percent)
+    }
+
+    /// Any storage parameter supported by the index method
+    /// (e.g. the `fastupdate = off` for the GIN or the `pages_per_range = 32` for the BRIN).
+    ///
+    /// # Panics
+    ///
+    /// If the name of the parameter is not a valid lowercase identifier.
+    pub fn param(mut self, name: impl AsRef<str>, value: impl Display) -> Self {
+        let name = name.as_ref();
+        validate_name("storage parameter", name);
+        self.params.push((name.to_owned(), value.to_string()));
+        self
+    }
+
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn tablespace(mut self, name: impl AsRef<str>) -> Self {
+        let name = name.as_ref();
+        validate_name("tablespace", name);
+        self.tablespace = Some(name.to_owned());
+        self
+    }
+
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    pub fn get_tablespace(&self) -> Option<&str> {
+        self.tablespace.as_deref()
+    }
+
+    /// The `WITH` and the `TABLESPACE` clauses (with the leading space if any).
+    fn sql(&self) -> String {
+        let mut sql = storage_params_sql(&self.params);
+        if let Some(tablespace) = &self.tablespace {
+            write!(sql, " TABLESPACE {}", tablespace).unwrap();
+        }
+        sql
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+#[non_exhaustive]
+pub enum IndexMethod {
+    #[default]
+    BTree,
+    Hash,
+    /// The inverted index for the composite values (arrays, `jsonb`, `tsvector`).
+    Gin,
+    /// The balanced tree for the geometric types, ranges and the full-text search.
+    Gist,
+    /// The space-partitioned tree for the non-balanced data (e.g. points, `inet`).
+    SpGist,
+    /// The summaries of the block ranges for the large tables
+    /// with the values correlated with the physical order (e.g. timestamps).
+    Brin,
+}
+
+impl Display for IndexMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            IndexMethod::BTree => "btree",
+            IndexMethod::Hash => "hash",
+            IndexMethod::Gin => "gin",
+            IndexMethod::Gist => "gist",
+            IndexMethod::SpGist => "spgist",
+            IndexMethod::Brin => "brin",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::ColumnBuilder;
+
+    #[test]
+    fn multi_column() {
+        let user = ColumnBuilder::new("user_id", Type::INT4).finish();
+        let created = ColumnBuilder::new("created_at", Type::TIMESTAMPTZ)
+            .nullable()
+            .finish();
+        let index = Index::new([
+            IndexKey::from(&user),
+            IndexKey::new(&created)
+                .order(Order::Desc)
+                .nulls(NullsOrder::Last),
+        ]);
+        assert_eq!(index.name("app.events"), "user_id_created_at_idx_events");
+        assert_eq!(
+            index.create_sql("app.events"),
+            "CREATE INDEX IF NOT EXISTS user_id_created_at_idx_events ON app.events \
+            USING btree (user_id, created_at DESC NULLS LAST)"
+        );
+    }
+
+    #[test]
+    fn names() {
+        let columns: Vec<_> = ["tenant_identifier", "customer_reference", "invoice_number"]
+            .iter()
+            .map(|name| ColumnBuilder::new(*name, Type::TEXT).finish())
+            .collect();
+        let index = Index::new(&columns);
+        let name = index.name("billing.outstanding_invoices");
+        assert_eq!(name.len(), 63);
+        assert!(name.starts_with("tenant_identifier_customer_reference_invoice_num"));
+        assert!(index
+            .create_sql("billing.outstanding_invoices")
+            .contains(&name));
+
+        let named = index.named("invoices_lookup");
+        assert_eq!(
+            named.create_sql("billing.outstanding_invoices"),
+            "CREATE INDEX IF NOT EXISTS invoices_lookup ON billing.outstanding_invoices \
+            USING btree (tenant_identifier, customer_reference, invoice_number)"
+        );
+    }
+
+    #[test]
+    fn partial() {
+        let email = ColumnBuilder::new("email", Type::TEXT).finish();
+        let index = Index::new([&email]).predicate("deleted_at IS NULL");
+        assert_eq!(index.get_predicate(), Some("deleted_at IS NULL"));
+        assert_eq!(
+            index.create_sql("users"),
+            "CREATE INDEX IF NOT EXISTS email_idx_users ON users \
+            USING btree (email) WHERE (deleted_at IS NULL)"
+        );
+    }
+
+    #[test]
+    fn gin_opclass() {
+        let payload = ColumnBuilder::new("payload", Type::JSONB).finish();
+        let index =
+            Index::new([IndexKey::new(&payload).opclass("jsonb_path_ops")]).using(IndexMethod::Gin);
+        assert_eq!(
+            index.create_sql("events"),
+            "CREATE INDEX IF NOT EXISTS payload_idx_events ON events \
+            USING gin (payload jsonb_path_ops)"
+        );
+    }
+
+    #[test]
+    fn storage_options() {
+        let created = ColumnBuilder::new("created_at", Type::TIMESTAMPTZ).finish();
+        let index = Index::new([&created])
+            .using(IndexMethod::Brin)
+            .options(
+                IndexOptions::new()
+                    .fillfactor(70)
+                    .param("pages_per_range", 32)
+                    .tablespace("archive"),
+            )
+            .predicate("created_at > '2020-01-01'");
+        assert_eq!(
+            index.create_sql("events"),
+            "CREATE INDEX IF NOT EXISTS created_at_idx_events ON events \
+            USING brin (created_at) WITH (fillfactor = 70, pages_per_range = 32) \
+            TABLESPACE archive WHERE (created_at > '2020-01-01')"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The fillfactor should be in the range 10..=100, got 5")]
+    fn tiny_fillfactor() {
+        let _ = IndexOptions::new().fillfactor(5);
+    }
+
+    #[test]
+    #[should_panic(expected = "The index should have at least one key")]
+    fn no_keys() {
+        let _ = Index::new(Vec::<IndexKey>::new());
+    }
+}
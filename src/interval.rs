@@ -0,0 +1,129 @@
+use std::error::Error;
+
+use postgres_types::{private::BytesMut, to_sql_checked, FromSql, IsNull, ToSql, Type as DbType};
+
+use crate::{column::SqlLiteral, type_helpers::quote_literal};
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// The value of the `interval` column.
+///
+/// The months and the days are kept apart from the time since their length varies.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub microseconds: i64,
+}
+
+impl Interval {
+    pub const fn new(months: i32, days: i32, microseconds: i64) -> Self {
+        Self {
+            months,
+            days,
+            microseconds,
+        }
+    }
+
+    /// The length of the interval assuming the 30-day months and the 24-hour days
+    /// (as the `EXTRACT(EPOCH FROM ...)` does).
+    ///
+    /// Returns `None` on overflow.
+    pub fn total_microseconds(&self) -> Option<i64> {
+        let days = i64::from(self.months) * 30 + i64::from(self.days);
+        days.checked_mul(MICROS_PER_DAY)?
+            .checked_add(self.microseconds)
+    }
+}
+
+impl ToSql for Interval {
+    fn to_sql(
+        &self,
+        _: &DbType,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        out.extend_from_slice(&self.microseconds.to_be_bytes());
+        out.extend_from_slice(&self.days.to_be_bytes());
+        out.extend_from_slice(&self.months.to_be_bytes());
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &DbType) -> bool {
+        *ty == DbType::INTERVAL
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for Interval {
+    fn from_sql(_: &DbType, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let raw: &[u8; 16] = raw
+            .try_into()
+            .map_err(|_| format!("Invalid interval of {} bytes", raw.len()))?;
+        let (microseconds, rest) = raw.split_at(8);
+        let (days, months) = rest.split_at(4);
+        Ok(Self {
+            months: i32::from_be_bytes(months.try_into()?),
+            days: i32::from_be_bytes(days.try_into()?),
+            microseconds: i64::from_be_bytes(microseconds.try_into()?),
+        })
+    }
+
+    fn accepts(ty: &DbType) -> bool {
+        *ty == DbType::INTERVAL
+    }
+}
+
+impl SqlLiteral for Interval {
+    fn to_literal(&self) -> String {
+        let value = format!(
+            "{} mons {} days {} microseconds",
+            self.months, self.days, self.microseconds
+        );
+        format!("{}::interval", quote_literal(&value))
+    }
+}
+
+/// The [`Codec`][crate::Codec] storing the durations in the `interval` column
+/// (e.g. `#[pg(with = AsInterval)]` on the `chrono::Duration` field).
+///
+/// The months are decoded as the 30-day periods.
+#[derive(Debug, Copy, Clone)]
+pub struct AsInterval;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_helpers::get_client;
+
+    #[test]
+    fn binary_format() {
+        let interval = Interval::new(1, 2, 3);
+        let mut out = BytesMut::new();
+        interval.to_sql(&DbType::INTERVAL, &mut out).unwrap();
+        assert_eq!(
+            out.as_ref(),
+            [0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 2, 0, 0, 0, 1]
+        );
+        assert_eq!(
+            Interval::from_sql(&DbType::INTERVAL, &out).unwrap(),
+            interval
+        );
+        assert!(Interval::from_sql(&DbType::INTERVAL, &out[1..]).is_err());
+        assert_eq!(interval.total_microseconds(), Some(32 * MICROS_PER_DAY + 3));
+        assert_eq!(Interval::new(0, i32::MAX, 0).total_microseconds(), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        let interval = Interval::new(-1, 15, 1_500_000);
+        let sql = format!("SELECT {} = $1, $1", interval.to_literal());
+        let row = client.query_one(&sql, &[&interval]).unwrap();
+        assert!(row.get::<_, bool>(0));
+        assert_eq!(row.get::<_, Interval>(1), interval);
+    }
+}
@@ -0,0 +1,420 @@
+//! Read the definitions of the existing tables from the `pg_catalog`.
+
+use postgres_types::{Kind, Oid, Type as DbType};
+use tokio_postgres::{Error, GenericClient, Row};
+
+use crate::{
+    column::{Column, ColumnBuilder},
+    ext::SyncClient,
+    index::IndexMethod,
+};
+
+const COLUMNS_SQL: &str = "SELECT a.attname::text, NOT a.attnotnull, \
+    t.oid, t.typname::text, tn.nspname::text, t.typtype::text, \
+    ARRAY(SELECT e.enumlabel::text FROM pg_enum e WHERE e.enumtypid = t.oid ORDER BY e.enumsortorder), \
+    et.oid, et.typname::text, etn.nspname::text, a.atttypmod \
+    FROM pg_attribute a \
+    JOIN pg_type t ON t.oid = a.atttypid \
+    JOIN pg_namespace tn ON tn.oid = t.typnamespace \
+    LEFT JOIN pg_type et ON t.typcategory = 'A' AND et.oid = t.typelem \
+    LEFT JOIN pg_namespace etn ON etn.oid = et.typnamespace \
+    WHERE a.attrelid = to_regclass($1) AND a.attnum > 0 AND NOT a.attisdropped \
+    ORDER BY a.attnum";
+
+const CONSTRAINTS_SQL: &str = "SELECT c.conname::text, c.contype::text, \
+    ARRAY(SELECT a.attname::text FROM unnest(c.conkey) WITH ORDINALITY AS k(num, pos) \
+    JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.num ORDER BY k.pos), \
+    NULLIF(c.confrelid, 0)::regclass::text, \
+    ARRAY(SELECT a.attname::text FROM unnest(c.confkey) WITH ORDINALITY AS k(num, pos) \
+    JOIN pg_attribute a ON a.attrelid = c.confrelid AND a.attnum = k.num ORDER BY k.pos), \
+    pg_get_constraintdef(c.oid) \
+    FROM pg_constraint c WHERE c.conrelid = to_regclass($1) ORDER BY c.conname";
+
+const INDEXES_SQL: &str =
+    "SELECT ic.relname::text, am.amname::text, i.indisunique, i.indisprimary, \
+    ARRAY(SELECT a.attname::text FROM unnest(i.indkey::int2[]) WITH ORDINALITY AS k(num, pos) \
+    JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = k.num ORDER BY k.pos), \
+    pg_get_indexdef(i.indexrelid) \
+    FROM pg_index i \
+    JOIN pg_class ic ON ic.oid = i.indexrelid \
+    JOIN pg_am am ON am.oid = ic.relam \
+    WHERE i.indrelid = to_regclass($1) ORDER BY ic.relname";
+
+const TABLES_SQL: &str = "SELECT format('%I.%I', n.nspname, c.relname) \
+    FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+    WHERE n.nspname = $1 AND c.relkind IN ('r', 'p') ORDER BY c.relname";
+
+/// The definition of the existing table.
+#[derive(Debug)]
+pub struct TableInfo {
+    /// The name as it was requested (possibly schema-qualified).
+    pub name: String,
+    /// The column-level properties are only set from the single-column
+    /// constraints and indexes, all of them are also in the
+    /// [`Self::constraints`] and [`Self::indexes`].
+    pub columns: Vec<Column>,
+    pub constraints: Vec<ConstraintInfo>,
+    pub indexes: Vec<IndexInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintInfo {
+    pub name: String,
+    pub kind: ConstraintKind,
+    pub columns: Vec<String>,
+    /// The referenced table and columns of the foreign key.
+    pub references: Option<(String, Vec<String>)>,
+    /// The SQL as returned by the `pg_get_constraintdef`.
+    pub definition: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConstraintKind {
+    PrimaryKey,
+    Unique,
+    ForeignKey,
+    Check,
+    Exclusion,
+    /// Any other `pg_constraint.contype` (e.g. constraint triggers).
+    Other(char),
+}
+
+impl ConstraintKind {
+    fn from_code(code: &str) -> Self {
+        match code {
+            "p" => Self::PrimaryKey,
+            "u" => Self::Unique,
+            "f" => Self::ForeignKey,
+            "c" => Self::Check,
+            "x" => Self::Exclusion,
+            other => Self::Other(other.chars().next().unwrap_or_default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexInfo {
+    pub name: String,
+    /// The access method (`btree`, `hash`, `gin`, etc).
+    pub method: String,
+    pub unique: bool,
+    pub primary: bool,
+    /// The indexed columns (the expressions are skipped).
+    pub columns: Vec<String>,
+    /// The SQL as returned by the `pg_get_indexdef`.
+    pub definition: String,
+}
+
+/// Get the definition of the table (`None` if it does not exist).
+///
+/// The name could be schema-qualified, otherwise the table
+/// is looked up using the `search_path`.
+pub fn table<C>(client: &mut C, name: &str) -> Result<Option<TableInfo>, Error>
+where
+    C: SyncClient,
+{
+    let columns = client.query(COLUMNS_SQL, &[&name])?;
+    let constraints = client.query(CONSTRAINTS_SQL, &[&name])?;
+    let indexes = client.query(INDEXES_SQL, &[&name])?;
+    TableInfo::from_rows(name, &columns, &constraints, &indexes)
+}
+
+/// Get the definitions of all the tables in the schema ordered by name.
+pub fn schema<C>(client: &mut C, schema: &str) -> Result<Vec<TableInfo>, Error>
+where
+    C: SyncClient,
+{
+    let names = client.query(TABLES_SQL, &[&schema])?;
+    let mut tables = Vec::with_capacity(names.len());
+    for row in names {
+        let name: String = row.try_get(0)?;
+        tables.extend(table(client, &name)?);
+    }
+    Ok(tables)
+}
+
+/// The async version of the [`table`].
+pub async fn table_async<C>(client: &C, name: &str) -> Result<Option<TableInfo>, Error>
+where
+    C: GenericClient + Sync,
+{
+    let columns = client.query(COLUMNS_SQL, &[&name]).await?;
+    let constraints = client.query(CONSTRAINTS_SQL, &[&name]).await?;
+    let indexes = client.query(INDEXES_SQL, &[&name]).await?;
+    TableInfo::from_rows(name, &columns, &constraints, &indexes)
+}
+
+/// The async version of the [`schema`].
+pub async fn schema_async<C>(client: &C, schema: &str) -> Result<Vec<TableInfo>, Error>
+where
+    C: GenericClient + Sync,
+{
+    let names = client.query(TABLES_SQL, &[&schema]).await?;
+    let mut tables = Vec::with_capacity(names.len());
+    for row in names {
+        let name: String = row.try_get(0)?;
+        tables.extend(table_async(client, &name).await?);
+    }
+    Ok(tables)
+}
+
+impl TableInfo {
+    fn from_rows(
+        name: &str,
+        columns: &[Row],
+        constraints: &[Row],
+        indexes: &[Row],
+    ) -> Result<Option<Self>, Error> {
+        if columns.is_empty() {
+            return Ok(None);
+        }
+        let constraints = constraints
+            .iter()
+            .map(ConstraintInfo::from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+        let indexes = indexes
+            .iter()
+            .map(IndexInfo::from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+        let columns = columns
+            .iter()
+            .map(|row| column_from_row(row, &constraints, &indexes))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(Self {
+            name: name.to_owned(),
+            columns,
+            constraints,
+            indexes,
+        }))
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|col| col.name() == name)
+    }
+}
+
+impl ConstraintInfo {
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        let kind: String = row.try_get(1)?;
+        let ref_table: Option<String> = row.try_get(3)?;
+        let ref_columns: Vec<String> = row.try_get(4)?;
+        Ok(Self {
+            name: row.try_get(0)?,
+            kind: ConstraintKind::from_code(&kind),
+            columns: row.try_get(2)?,
+            references: ref_table.map(|table| (table, ref_columns)),
+            definition: row.try_get(5)?,
+        })
+    }
+
+    pub(crate) fn is_on_single(&self, kind: ConstraintKind, column: &str) -> bool {
+        self.kind == kind && self.columns == [column]
+    }
+}
+
+impl IndexInfo {
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        Ok(Self {
+            name: row.try_get(0)?,
+            method: row.try_get(1)?,
+            unique: row.try_get(2)?,
+            primary: row.try_get(3)?,
+            columns: row.try_get(4)?,
+            definition: row.try_get(5)?,
+        })
+    }
+}
+
+fn column_from_row(
+    row: &Row,
+    constraints: &[ConstraintInfo],
+    indexes: &[IndexInfo],
+) -> Result<Column, Error> {
+    let name: String = row.try_get(0)?;
+    let nullable: bool = row.try_get(1)?;
+    let db_type = db_type_from_row(row)?;
+
+    let numeric = db_type == DbType::NUMERIC || db_type == DbType::NUMERIC_ARRAY;
+    let mut builder = ColumnBuilder::new(&name, db_type);
+    if nullable {
+        builder = builder.nullable();
+    }
+    if let Some((precision, scale)) = numeric
+        .then(|| row.try_get(10).map(numeric_precision))
+        .transpose()?
+        .flatten()
+    {
+        builder = builder.precision(precision, scale);
+    }
+    let name = name.as_str();
+    let single = |kind| {
+        constraints
+            .iter()
+            .filter(move |c| c.is_on_single(kind, name))
+    };
+    if single(ConstraintKind::PrimaryKey).next().is_some() {
+        builder = builder.primary_key();
+    } else if single(ConstraintKind::Unique).next().is_some() {
+        builder = builder.unique();
+    }
+    if let Some((table, columns)) = single(ConstraintKind::ForeignKey)
+        .find_map(|c| c.references.as_ref())
+        .filter(|(_, columns)| columns.len() == 1)
+    {
+        builder = builder.foreign_key(table, &columns[0]);
+    }
+    let method = indexes
+        .iter()
+        .filter(|idx| !idx.unique && idx.columns == [name])
+        .find_map(|idx| match idx.method.as_str() {
+            "btree" => Some(IndexMethod::BTree),
+            "hash" => Some(IndexMethod::Hash),
+            "gin" => Some(IndexMethod::Gin),
+            "gist" => Some(IndexMethod::Gist),
+            "spgist" => Some(IndexMethod::SpGist),
+            "brin" => Some(IndexMethod::Brin),
+            _ => None,
+        });
+    if let Some(method) = method {
+        builder = builder.index_with(method);
+    }
+    Ok(builder.finish())
+}
+
+/// The precision and the scale of the `numeric` column
+/// encoded in the `atttypmod` (the negative scales are not supported).
+fn numeric_precision(typmod: i32) -> Option<(u16, u16)> {
+    // the modifier is shifted by the size of the varlena header
+    let modifier = typmod.checked_sub(4).filter(|modifier| *modifier >= 0)?;
+    let precision = u16::try_from(modifier >> 16).ok()?;
+    let scale = u16::try_from(modifier & 0xffff).ok()?;
+    (scale <= precision).then_some((precision, scale))
+}
+
+/// The built-in types are resolved by their OIDs, the enums get their variants,
+/// other custom types (e.g. composites) are represented with their names only.
+fn db_type_from_row(row: &Row) -> Result<DbType, Error> {
+    let oid: Oid = row.try_get(2)?;
+    if let Some(ty) = DbType::from_oid(oid) {
+        return Ok(ty);
+    }
+    let name: String = row.try_get(3)?;
+    let schema: String = row.try_get(4)?;
+    let type_kind: String = row.try_get(5)?;
+    let kind = if type_kind == "e" {
+        Kind::Enum(row.try_get(6)?)
+    } else if let Some(elem_oid) = row.try_get::<_, Option<Oid>>(7)? {
+        let elem = match DbType::from_oid(elem_oid) {
+            Some(elem) => elem,
+            None => DbType::new(row.try_get(8)?, elem_oid, Kind::Simple, row.try_get(9)?),
+        };
+        Kind::Array(elem)
+    } else {
+        Kind::Simple
+    };
+    Ok(DbType::new(name, oid, kind, schema))
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{ext::PgTableExtension as _, gen_table, test_helpers::get_client, Table};
+
+    gen_table!(
+        struct Shelf("shelves") {
+            id: i32 = Type::INT4; [primary_key()],
+            label: String = Type::TEXT; [unique()],
+        }
+    );
+
+    gen_table!(
+        struct Volume("volumes") {
+            id: i64 = Type::INT8; [primary_key()],
+            shelf_id: i32 = Type::INT4; [foreign_key(Shelf::name(), "id"), index()],
+            isbn: Option<String> = Type::TEXT; [nullable(), index_with(IndexMethod::Hash)],
+            tags: Vec<String> = Type::TEXT_ARRAY; [index_with(IndexMethod::Gin)],
+        }
+    );
+
+    #[test]
+    fn numeric_modifier() {
+        // numeric(12, 2)
+        assert_eq!(numeric_precision((12 << 16) + 2 + 4), Some((12, 2)));
+        assert_eq!(numeric_precision(-1), None);
+        // numeric(3, -2) is only available since Postgres 15
+        assert_eq!(numeric_precision((3 << 16) + 0x7fe + 4), None);
+    }
+
+    #[test]
+    fn existing_tables() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP SCHEMA IF EXISTS introspected CASCADE; \
+                CREATE SCHEMA introspected",
+            )
+            .unwrap();
+        client
+            .batch_execute("SET search_path = introspected")
+            .unwrap();
+        client.create_table::<Shelf, 2>().unwrap();
+        client.create_table::<Volume, 4>().unwrap();
+        client.create_indices::<Volume, 4>().unwrap();
+        client.batch_execute("RESET search_path").unwrap();
+
+        assert!(table(&mut client, "introspected.missing")
+            .unwrap()
+            .is_none());
+
+        let tables = schema(&mut client, "introspected").unwrap();
+        let names: Vec<_> = tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["introspected.shelves", "introspected.volumes"]);
+
+        let expected = Volume::columns().map(|col| col.to_string());
+        let actual: Vec<_> = tables[1]
+            .columns
+            .iter()
+            .map(|col| col.to_string())
+            .collect();
+        assert_eq!(
+            actual,
+            expected
+                .map(|col| col.replace("REFERENCES shelves", "REFERENCES introspected.shelves"))
+        );
+        let methods: Vec<_> = tables[1]
+            .columns
+            .iter()
+            .map(|col| col.get_index().map(|m| m.to_string()))
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                None,
+                Some("btree".into()),
+                Some("hash".into()),
+                Some("gin".into())
+            ]
+        );
+        assert!(tables[0].column("label").unwrap().is_unique());
+
+        let kinds: Vec<_> = tables[1].constraints.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            [ConstraintKind::PrimaryKey, ConstraintKind::ForeignKey]
+        );
+        let fkey = &tables[1].constraints[1];
+        assert_eq!(
+            fkey.definition,
+            "FOREIGN KEY (shelf_id) REFERENCES introspected.shelves(id)"
+        );
+        assert_eq!(tables[1].indexes.len(), 4);
+
+        client
+            .batch_execute("DROP SCHEMA introspected CASCADE")
+            .unwrap();
+    }
+}
@@ -0,0 +1,482 @@
+use std::fmt::{self, Display, Write as _};
+
+use itertools::Itertools as _;
+
+use tokio_postgres::Error as DbError;
+
+use crate::{
+    column::Column, config::unqualified, error::Error, expr::Expr, query::SelectOptions,
+    table::Table,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum JoinKind {
+    #[default]
+    Inner,
+    Left,
+}
+
+impl Display for JoinKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            JoinKind::Inner => "INNER JOIN",
+            JoinKind::Left => "LEFT JOIN",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+/// The way to join two tables: either on the explicit condition
+/// or on the single foreign key between them.
+///
+/// The columns in the conditions could be qualified
+/// with the table name (`table.column`) to avoid ambiguity
+/// (the unqualified columns present in both tables are rejected).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Join {
+    on: Option<Expr>,
+    cross: bool,
+    lateral: Option<SelectOptions>,
+}
+
+#[derive(Debug)]
+pub enum JoinError {
+    /// Neither an explicit condition nor a single foreign key between the tables
+    /// (use [`Join::cross`] if the cartesian product is really intended).
+    NoCondition {
+        left: &'static str,
+        right: &'static str,
+    },
+    /// Both sides have the same (unqualified) name, so their columns could not be told apart.
+    SameTable {
+        table: &'static str,
+    },
+    /// The unqualified column of a condition is present in both tables.
+    AmbiguousColumn {
+        column: String,
+    },
+    Db(Error),
+}
+
+impl Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCondition { left, right } => write!(
+                f,
+                "No condition to join {} with {}: specify it explicitly",
+                left, right
+            ),
+            Self::SameTable { table } => write!(f, "Cannot join the table {} with itself", table),
+            Self::AmbiguousColumn { column } => write!(
+                f,
+                "The column {} is present in both tables: qualify it with the table name",
+                column
+            ),
+            Self::Db(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::NoCondition { .. } | Self::SameTable { .. } | Self::AmbiguousColumn { .. } => {
+                None
+            }
+            Self::Db(err) => Some(err),
+        }
+    }
+}
+
+impl From<Error> for JoinError {
+    fn from(err: Error) -> Self {
+        Self::Db(err)
+    }
+}
+
+impl From<DbError> for JoinError {
+    fn from(err: DbError) -> Self {
+        Self::Db(err.into())
+    }
+}
+
+/// Name of the column signaling whether the right side of the _LEFT JOIN_ is found.
+pub(crate) const MATCHED_COLUMN: &str = "__matched";
+
+/// Name of the column numbering the rows of the _LATERAL_ subquery starting from 1.
+pub(crate) const POSITION_COLUMN: &str = "__position";
+
+impl Join {
+    /// Join on the foreign key.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Join on the explicit condition (even if the join was a [`Self::cross`] one).
+    pub fn on(mut self, condition: Expr) -> Self {
+        self.on = Some(condition);
+        self.cross = false;
+        self
+    }
+
+    /// Explicitly allow to join every row of the left table with every row of the right one.
+    pub const fn cross() -> Self {
+        Self {
+            on: None,
+            cross: true,
+            lateral: None,
+        }
+    }
+
+    /// Join every row of the left table with the rows of the right table
+    /// selected by the _LATERAL_ subquery with the given options
+    /// (e.g. to get the latest N rows per left row).
+    ///
+    /// The join condition is moved into the _WHERE_ of the subquery
+    /// and the result is ordered so that the right rows of the same left row
+    /// follow each other in the order of the subquery.
+    pub fn lateral(mut self, options: SelectOptions) -> Self {
+        self.lateral = Some(options);
+        self
+    }
+
+    pub(crate) const fn is_lateral(&self) -> bool {
+        self.lateral.is_some()
+    }
+
+    fn foreign_key_condition<L, R, const N: usize, const M: usize>() -> Option<Expr>
+    where
+        L: Table<N>,
+        R: Table<M>,
+    {
+        let direct = L::columns().into_iter().filter_map(|col| {
+            let (table, target) = col.foreign_key()?;
+            (table == R::name()).then(|| (col.name().to_owned(), target))
+        });
+        let reverse = R::columns().into_iter().filter_map(|col| {
+            let (table, target) = col.foreign_key()?;
+            (table == L::name()).then(|| (target, col.name().to_owned()))
+        });
+
+        let (left, right) = direct.chain(reverse).exactly_one().ok()?;
+        Some(
+            Expr::col(format!("{}.{}", L::name(), left)).eq(Expr::col(format!(
+                "{}.{}",
+                R::name(),
+                right
+            ))),
+        )
+    }
+
+    /// _SELECT_ statement returning the columns of the left table followed
+    /// by the columns of the right one (and the [`MATCHED_COLUMN`] for the _LEFT JOIN_).
+    ///
+    /// Fails if no explicit condition is given, the join is not a cross one
+    /// and there is no single foreign key between the tables.
+    /// Also fails for the self-join and for the unqualified columns of the conditions
+    /// present in both tables or referring to unknown columns.
+    pub fn select_sql<L, R, const N: usize, const M: usize>(
+        &self,
+        kind: JoinKind,
+        condition: Option<&Expr>,
+    ) -> Result<String, JoinError>
+    where
+        L: Table<N>,
+        R: Table<M>,
+    {
+        if unqualified(L::name()) == unqualified(R::name()) {
+            return Err(JoinError::SameTable { table: L::name() });
+        }
+        let on = if self.cross {
+            None
+        } else {
+            let on = self
+                .on
+                .clone()
+                .or_else(Self::foreign_key_condition::<L, R, N, M>)
+                .ok_or(JoinError::NoCondition {
+                    left: L::name(),
+                    right: R::name(),
+                })?;
+            Some(on)
+        };
+
+        // the subquery is aliased with the name of the table without the schema
+        let right_name = if self.lateral.is_some() {
+            unqualified(R::name())
+        } else {
+            R::name()
+        };
+        let tables = [
+            (L::name(), Vec::from(L::columns())),
+            (right_name, Vec::from(R::columns())),
+        ];
+        let mut columns = tables
+            .iter()
+            .flat_map(|(table, columns)| columns.iter().map(move |col| (table, col.name())))
+            .map(|(table, col)| format!("{}.{}", table, col))
+            .join(", ");
+        if self.lateral.is_some() {
+            write!(columns, ", {}.{}", right_name, POSITION_COLUMN).unwrap();
+        }
+        if kind == JoinKind::Left {
+            let right_key = if self.lateral.is_some() {
+                POSITION_COLUMN
+            } else {
+                "ctid"
+            };
+            write!(
+                columns,
+                ", {}.{} IS NOT NULL AS {}",
+                right_name, right_key, MATCHED_COLUMN
+            )
+            .unwrap();
+        }
+
+        let on = on.map(|on| qualified_to_sql(&on, &tables)).transpose()?;
+        let (right, on) = match &self.lateral {
+            None => (R::name().to_owned(), on),
+            Some(options) => {
+                let right_columns = &tables[1].1;
+                let mut subquery = format!(
+                    "SELECT {}*, row_number() OVER ({}) AS {} FROM {}",
+                    options.distinct_sql(right_columns)?,
+                    options.order_by_sql(right_columns)?,
+                    POSITION_COLUMN,
+                    R::name()
+                );
+                if let Some(on) = on {
+                    write!(subquery, " WHERE {}", on).unwrap();
+                }
+                subquery.push_str(&options.to_sql(right_columns)?);
+                (format!("LATERAL ({}) AS {}", subquery, right_name), None)
+            }
+        };
+        let mut query = format!(
+            "SELECT {} FROM {} {} {} ON {}",
+            columns,
+            L::name(),
+            kind,
+            right,
+            on.as_deref().unwrap_or("TRUE")
+        );
+        if let Some(condition) = condition {
+            write!(query, " WHERE {}", qualified_to_sql(condition, &tables)?).unwrap();
+        }
+        if self.lateral.is_some() {
+            // the rows of the different partitions could have the same `ctid`
+            let left_key = tables[0]
+                .1
+                .iter()
+                .filter(|col| col.is_primary_key())
+                .map(|col| format!("{}.{}", L::name(), col.name()))
+                .join(", ");
+            let left_key = if left_key.is_empty() {
+                format!("{0}.tableoid, {0}.ctid", L::name())
+            } else {
+                left_key
+            };
+            write!(
+                query,
+                " ORDER BY {}, {}.{}",
+                left_key, right_name, POSITION_COLUMN
+            )
+            .unwrap();
+        }
+        Ok(query)
+    }
+}
+
+/// Render the expression checking its (possibly qualified) columns.
+fn qualified_to_sql(expr: &Expr, tables: &[(&str, Vec<Column>)]) -> Result<String, JoinError> {
+    for name in expr.columns() {
+        let (table, column) = match name.rsplit_once('.') {
+            Some((table, column)) => (Some(table), column),
+            None => (None, name),
+        };
+        let found = tables
+            .iter()
+            .filter(|(t, _)| table.is_none() || table.map(unqualified) == Some(unqualified(t)))
+            .filter(|(_, columns)| columns.iter().any(|col| col.name() == column))
+            .count();
+        if found == 0 {
+            return Err(JoinError::Db(Error::UnknownColumn {
+                column: name.to_owned(),
+                purpose: "use in the expression",
+            }));
+        }
+        if found > 1 {
+            return Err(JoinError::AmbiguousColumn {
+                column: name.to_owned(),
+            });
+        }
+    }
+    Ok(expr.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gen_table, param, Order};
+
+    use postgres_types::Type;
+
+    gen_table!(
+        struct User("users") {
+            user_id: i32 = Type::INT4; [primary_key()],
+            name: String = Type::TEXT,
+        }
+    );
+
+    gen_table!(
+        struct Buy("buys") {
+            buy_id: i32 = Type::INT4; [primary_key()],
+            customer_id: i32 = Type::INT4; [foreign_key(User::name(), "user_id")],
+            total_price: f32 = Type::FLOAT4,
+        }
+    );
+
+    #[test]
+    fn foreign_key() {
+        assert_eq!(
+            Join::new()
+                .select_sql::<Buy, User, 3, 2>(JoinKind::Inner, None)
+                .unwrap(),
+            "SELECT buys.buy_id, buys.customer_id, buys.total_price, users.user_id, users.name \
+            FROM buys INNER JOIN users ON buys.customer_id = users.user_id"
+        );
+    }
+
+    #[test]
+    fn reverse_foreign_key() {
+        let cond = Expr::col("name").eq(param(1));
+        assert_eq!(
+            Join::new()
+                .select_sql::<User, Buy, 2, 3>(JoinKind::Left, Some(&cond))
+                .unwrap(),
+            "SELECT users.user_id, users.name, buys.buy_id, buys.customer_id, buys.total_price, \
+            buys.ctid IS NOT NULL AS __matched \
+            FROM users LEFT JOIN buys ON users.user_id = buys.customer_id WHERE name = $1"
+        );
+    }
+
+    #[test]
+    fn explicit_condition() {
+        let on = Expr::col("users.user_id").eq(Expr::col("buys.buy_id"));
+        assert_eq!(
+            Join::new()
+                .on(on)
+                .select_sql::<Buy, User, 3, 2>(JoinKind::Inner, None)
+                .unwrap(),
+            "SELECT buys.buy_id, buys.customer_id, buys.total_price, users.user_id, users.name \
+            FROM buys INNER JOIN users ON users.user_id = buys.buy_id"
+        );
+    }
+
+    #[test]
+    fn wrong_qualified_column() {
+        let on = Expr::col("users.buy_id").eq(Expr::col("buys.buy_id"));
+        let err = Join::new()
+            .on(on)
+            .select_sql::<Buy, User, 3, 2>(JoinKind::Inner, None)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown column to use in the expression: \"users.buy_id\""
+        );
+    }
+
+    gen_table!(
+        struct Color("colors") {
+            color: String = Type::TEXT,
+            name: String = Type::TEXT,
+        }
+    );
+
+    #[test]
+    fn no_foreign_key() {
+        let err = Join::new()
+            .select_sql::<User, Color, 2, 2>(JoinKind::Inner, None)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            JoinError::NoCondition {
+                left: "users",
+                right: "colors"
+            }
+        ));
+    }
+
+    #[test]
+    fn self_join() {
+        let err = Join::cross()
+            .select_sql::<User, User, 2, 2>(JoinKind::Inner, None)
+            .unwrap_err();
+        assert!(matches!(err, JoinError::SameTable { table: "users" }));
+    }
+
+    #[test]
+    fn ambiguous_column() {
+        let cond = Expr::col("name").eq(param(1));
+        let err = Join::cross()
+            .select_sql::<User, Color, 2, 2>(JoinKind::Inner, Some(&cond))
+            .unwrap_err();
+        assert!(matches!(err, JoinError::AmbiguousColumn { column } if column == "name"));
+
+        let cond = Expr::col("colors.name").eq(param(1));
+        assert!(Join::cross()
+            .select_sql::<User, Color, 2, 2>(JoinKind::Inner, Some(&cond))
+            .is_ok());
+    }
+
+    #[test]
+    fn lateral_without_key() {
+        let query = Join::new()
+            .lateral(SelectOptions::new())
+            .on(Expr::col("users.name").eq(Expr::col("colors.color")))
+            .select_sql::<Color, User, 2, 2>(JoinKind::Left, None)
+            .unwrap();
+        assert!(query.ends_with(" ORDER BY colors.tableoid, colors.ctid, users.__position"));
+    }
+
+    #[test]
+    fn lateral() {
+        let latest = SelectOptions::new()
+            .order_by("buy_id", Order::Desc)
+            .limit(3);
+        assert_eq!(
+            Join::new()
+                .lateral(latest)
+                .select_sql::<User, Buy, 2, 3>(JoinKind::Left, None)
+                .unwrap(),
+            "SELECT users.user_id, users.name, buys.buy_id, buys.customer_id, buys.total_price, \
+            buys.__position, buys.__position IS NOT NULL AS __matched \
+            FROM users LEFT JOIN LATERAL (\
+            SELECT *, row_number() OVER (ORDER BY buy_id DESC) AS __position FROM buys \
+            WHERE users.user_id = buys.customer_id ORDER BY buy_id DESC LIMIT 3\
+            ) AS buys ON TRUE ORDER BY users.user_id, buys.__position"
+        );
+    }
+
+    #[test]
+    fn cross_join() {
+        assert!(Join::new()
+            .select_sql::<User, Color, 2, 2>(JoinKind::Inner, None)
+            .is_err());
+        assert_eq!(
+            Join::cross()
+                .select_sql::<User, Color, 2, 2>(JoinKind::Inner, None)
+                .unwrap(),
+            "SELECT users.user_id, users.name, colors.color, colors.name \
+            FROM users INNER JOIN colors ON TRUE"
+        );
+        assert_eq!(
+            Join::cross()
+                .on(Expr::col("users.name").eq(Expr::col("colors.color")))
+                .select_sql::<User, Color, 2, 2>(JoinKind::Inner, None)
+                .unwrap(),
+            "SELECT users.user_id, users.name, colors.color, colors.name \
+            FROM users INNER JOIN colors ON users.name = colors.color"
+        );
+    }
+}
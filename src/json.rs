@@ -0,0 +1,154 @@
+use postgres_types::Json;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{column::SqlLiteral, type_helpers::quote_literal};
+
+// the untyped literal is converted to the type of the `json` or `jsonb` column
+
+impl SqlLiteral for Value {
+    fn to_literal(&self) -> String {
+        quote_literal(&self.to_string())
+    }
+}
+
+impl<T: Serialize> SqlLiteral for Json<T> {
+    /// # Panics
+    ///
+    /// If the value cannot be serialized (e.g. the map has the non-string keys).
+    fn to_literal(&self) -> String {
+        let json = serde_json::to_string(&self.0).expect("The value is not serializable to JSON");
+        quote_literal(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres::Client;
+    use postgres_types::{ToSql, Type};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        gen_table, param, test_helpers::get_client, ColumnBuilder, Expr, IndexMethod,
+        PgTableExtension as _, SelectOptions, Table as _,
+    };
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        theme: String,
+        volume: u8,
+    }
+
+    gen_table!(
+        #[derive(Debug, PartialEq)]
+        struct Profile("json_profiles") {
+            id: i32 = Type::INT4; [primary_key()],
+            settings: Json<Settings> = Type::JSONB,
+            raw: Value = Type::JSON,
+        }
+    );
+
+    gen_table!(
+        struct Document("json_documents") {
+            id: i32 = Type::INT4; [primary_key()],
+            body: Value = Type::JSONB; [index_with(IndexMethod::Gin), index_opclass("jsonb_path_ops")],
+        }
+    );
+
+    #[test]
+    fn literals() {
+        let settings = Json(Settings {
+            theme: "it's dark".into(),
+            volume: 3,
+        });
+        assert_eq!(
+            settings.to_literal(),
+            r#"'{"theme":"it''s dark","volume":3}'"#
+        );
+        let column = ColumnBuilder::new("tags", Type::JSONB)
+            .default_value(json!([]))
+            .finish();
+        assert_eq!(column.to_string(), "tags jsonb DEFAULT ('[]') NOT NULL");
+    }
+
+    #[test]
+    fn round_trip() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS json_profiles")
+            .unwrap();
+        client.create_table::<Profile, 3>().unwrap();
+
+        let profile = Profile {
+            id: 1,
+            settings: Json(Settings {
+                theme: "dark".into(),
+                volume: 7,
+            }),
+            raw: json!({"b": [1, null], "a": "x"}),
+        };
+        client.insert_row(&profile).unwrap();
+        assert_eq!(client.select_all::<Profile, 3>().unwrap(), [profile]);
+
+        let theme: String = client
+            .query_scalar("SELECT settings->>'theme' FROM json_profiles", &[])
+            .unwrap();
+        assert_eq!(theme, "dark");
+
+        client.batch_execute("DROP TABLE json_profiles").unwrap();
+    }
+
+    fn selected_ids(
+        client: &mut Client,
+        condition: &Expr,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Vec<i32> {
+        client
+            .select_where::<Document, 2>(condition, params, &SelectOptions::new())
+            .unwrap()
+            .into_iter()
+            .map(|doc| doc.id)
+            .collect()
+    }
+
+    #[test]
+    fn indexed_queries() {
+        assert_eq!(
+            Document::create_indices_sql()[0].create_sql(),
+            "CREATE INDEX IF NOT EXISTS body_idx_json_documents \
+            ON json_documents USING gin (body jsonb_path_ops)"
+        );
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS json_documents")
+            .unwrap();
+        client.create_table::<Document, 2>().unwrap();
+        let docs = [
+            json!({"tags": ["a", "b"], "owner": {"name": "ann"}}),
+            json!({"tags": ["b"]}),
+        ];
+        for (id, body) in (1..).zip(docs) {
+            client.insert_row(&Document { id, body }).unwrap();
+        }
+
+        let tagged = Expr::col("body").jsonb_contains(param(1));
+        assert_eq!(
+            selected_ids(&mut client, &tagged, &[&json!({"tags": ["b"]})]),
+            [1, 2]
+        );
+        assert_eq!(
+            selected_ids(&mut client, &tagged, &[&json!({"tags": ["a"]})]),
+            [1]
+        );
+        let owned = Expr::col("body").jsonb_path("$.owner.name");
+        assert_eq!(selected_ids(&mut client, &owned, &[]), [1]);
+
+        client.batch_execute("DROP TABLE json_documents").unwrap();
+    }
+}
@@ -1,20 +1,139 @@
+#![forbid(unsafe_code)]
+
+#[cfg(feature = "axum")]
+pub mod admin;
+mod aggregate;
+mod backoff;
+mod batch;
+mod cache;
+mod checksum;
+#[cfg(feature = "chrono")]
+mod chrono_types;
+#[cfg(feature = "cli")]
+pub mod cli;
+mod codec;
+pub mod codegen;
 mod column;
+mod config;
 mod constraint;
+#[cfg(feature = "cron")]
+mod cron;
+#[cfg(feature = "decimal")]
+mod decimal;
+mod diff;
+mod dyn_table;
+mod error;
+mod expr;
 mod ext;
 mod ext_async;
+mod fdw;
+mod function;
+mod geometry;
+mod guard;
+#[cfg(feature = "http")]
+mod http;
+mod index;
+mod interval;
+pub mod introspect;
+mod join;
+#[cfg(feature = "json")]
+mod json;
 mod macros;
+mod migrate;
+#[cfg(feature = "network")]
+mod network;
+mod partition;
+mod policy;
+#[cfg(feature = "deadpool")]
+pub mod pool;
+mod progress;
+mod projection;
+mod query;
+mod reconcile;
+mod recording;
+#[cfg(feature = "registry")]
+mod registry;
+mod script;
+mod self_check;
 mod serial;
 mod table;
+mod table_options;
+#[cfg(test)]
+mod test_helpers;
+pub mod testing;
+#[cfg(feature = "time")]
+mod time_types;
+mod transaction;
+mod trigger;
 mod type_helpers;
+mod value_objects;
+mod verify;
+mod view;
 
+#[cfg(feature = "cron")]
+pub use self::cron::{schedule_job, scheduled_jobs, unschedule_job, CronCommand, CronJob};
+#[doc(hidden)]
+pub use self::macros::{__ColumnName, __has_unique_columns, __has_unique_names, __prefixed};
+#[cfg(feature = "registry")]
+pub use self::registry::{Registration, Schema};
 pub use self::{
-    column::{Column, ColumnBuilder, IndexMethod},
+    aggregate::{Bucket, GroupBy, TimeBuckets},
+    backoff::Backoff,
+    batch::BatchPlan,
+    cache::{RowChange, RowChangeHook},
+    checksum::TableChecksum,
+    codec::{Codec, Encoded, OrDefault, With},
+    column::{Column, ColumnBuilder, ColumnMeta, Compression, SqlLiteral, Storage},
+    config::TableConfig,
     constraint::{
-        CheckConstraint, Constraint, ForeignKeyConstraint, PrimaryKeyConstraint, UniqueConstraint,
+        CheckConstraint, Constraint, ExclusionConstraint, ForeignKeyAction, ForeignKeyConstraint,
+        ForeignKeyMatch, PrimaryKeyConstraint, UniqueConstraint,
     },
-    ext::PgTableExtension,
+    diff::{diff, AlterStatement, TableDef},
+    dyn_table::{DynTable, TableRef},
+    error::{Error, Operation},
+    expr::{param, BinaryOp, Expr},
+    ext::{PgTableExtension, SyncClient},
     ext_async::PgTableExtension as PgTableAsync,
+    fdw::{create_foreign_server, ForeignServer, ForeignTable, UserMapping},
+    function::{FunctionLanguage, SqlFunction, Volatility},
+    geometry::{Circle, Line, Path, Point, Polygon, Rect},
+    guard::{CostGuard, GuardError, PlanEstimate},
+    index::{Index, IndexKey, IndexMethod, IndexOptions},
+    interval::{AsInterval, Interval},
+    join::{Join, JoinError, JoinKind},
+    migrate::SyncOptions,
+    partition::{
+        Expiration, PartitionBounds, PartitionChanges, PartitionInterval, PartitionSchedule,
+        PartitionStrategy,
+    },
+    policy::{Policy, PolicyCommand},
+    progress::{BatchOptions, CancelToken, Progress},
+    projection::{Projection, PROJECTIONS_TABLE},
+    query::{Distinct, NamedQuery, NullsOrder, Order, OrderBy, Redaction, SelectOptions},
+    reconcile::{diff_tables, RowsDiff},
+    recording::{RecordedStatement, RecordingClient},
+    script::{create_tables, schema_script},
+    self_check::{self_check, Capability, CapabilityReport, CheckStatus},
     serial::Serial,
-    table::{Insertable, InsertableValues, Table},
-    type_helpers::{array_type, enum_type, struct_type},
+    table::{
+        DynamicTable, FieldValue, Flatten, FromRow, Insertable, InsertableValues, RowsValues,
+        Table, TableSchema,
+    },
+    table_options::{OnCommit, TableOptions},
+    transaction::{
+        PgSavepoint, PgSavepointAsync, PgSnapshot, PgSnapshotAsync, PgTransaction,
+        PgTransactionAsync, RetryPolicy, SnapshotId, SynchronousCommit, TransactionFuture,
+        TransactionOptions,
+    },
+    trigger::{Trigger, TriggerEvent, TriggerTiming},
+    type_helpers::{array_type, enum_type, struct_type, PgEnum},
+    value_objects::{Address, Cents, Currency, Money},
+    verify::{Mismatch, SchemaReport},
+    view::View,
 };
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+pub use inventory as __inventory;
+#[cfg(feature = "json")]
+pub use postgres_types::Json;
@@ -1,44 +1,239 @@
+use std::borrow::Cow;
+
 #[macro_export]
 macro_rules! count {
     () => (0_usize);
     ( $x:tt $($xs:tt)* ) => (1_usize + $crate::count!($($xs)*));
 }
 
+/// Compile-time check for the `pg_enum` macro:
+/// whether all the enum labels are different.
+#[doc(hidden)]
+pub const fn __has_unique_names(names: &[&str]) -> bool {
+    const fn str_eq(a: &str, b: &str) -> bool {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    let mut i = 0;
+    while i < names.len() {
+        let mut j = i + 1;
+        while j < names.len() {
+            if str_eq(names[i], names[j]) {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// The name of the column or the prefix of the flattened struct with its column names
+/// (see the [`Flatten::NAMES`][crate::Flatten::NAMES]).
+#[doc(hidden)]
+#[derive(Debug, Copy, Clone)]
+pub enum __ColumnName {
+    Plain(&'static str),
+    Flatten(&'static str, &'static [__ColumnName]),
+}
+
+/// The maximum length of the identifier (the longer ones are truncated by the Postgres).
+const MAX_NAME_LEN: usize = 63;
+
+type NameBuf = ([u8; MAX_NAME_LEN], usize);
+
+const fn names_count(names: &[__ColumnName]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < names.len() {
+        count += match names[i] {
+            __ColumnName::Plain(_) => 1,
+            __ColumnName::Flatten(_, inner) => names_count(inner),
+        };
+        i += 1;
+    }
+    count
+}
+
+const fn append_name(buf: NameBuf, part: &str) -> NameBuf {
+    let (mut bytes, mut len) = buf;
+    let part = part.as_bytes();
+    let mut i = 0;
+    while i < part.len() && len < MAX_NAME_LEN {
+        bytes[len] = part[i];
+        len += 1;
+        i += 1;
+    }
+    (bytes, len)
+}
+
+/// The full name of the column with the given `index` appended to the `buf`.
+const fn nth_name(names: &[__ColumnName], mut index: usize, buf: NameBuf) -> NameBuf {
+    let mut i = 0;
+    while i < names.len() {
+        match names[i] {
+            __ColumnName::Plain(name) => {
+                if index == 0 {
+                    return append_name(buf, name);
+                }
+                index -= 1;
+            }
+            __ColumnName::Flatten(prefix, inner) => {
+                let count = names_count(inner);
+                if index < count {
+                    return nth_name(inner, index, append_name(buf, prefix));
+                }
+                index -= count;
+            }
+        }
+        i += 1;
+    }
+    panic!("The column index is out of range")
+}
+
+/// Compile-time check for the `gen_table` macro:
+/// whether all the column names (including the prefixed names of the flattened columns) are different.
+#[doc(hidden)]
+pub const fn __has_unique_columns(names: &[__ColumnName]) -> bool {
+    const fn buf_eq(a: &NameBuf, b: &NameBuf) -> bool {
+        if a.1 != b.1 {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.1 {
+            if a.0[i] != b.0[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    let empty = ([0; MAX_NAME_LEN], 0);
+    let count = names_count(names);
+    let mut i = 0;
+    while i < count {
+        let name = nth_name(names, i, empty);
+        let mut j = i + 1;
+        while j < count {
+            if buf_eq(&name, &nth_name(names, j, empty)) {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Column name with the prefix of the flattened struct.
+#[doc(hidden)]
+pub fn __prefixed<'a>(prefix: &str, name: &'a str) -> Cow<'a, str> {
+    if prefix.is_empty() {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(format!("{}{}", prefix, name))
+    }
+}
+
 #[macro_export]
 macro_rules! gen_table {
     (
         $(#[$outer:meta])*
         $struct_vis:vis struct $TableName:ident ($sql_name:literal) {
             $(
-                $(#[$inner:ident $($args:tt)*])*
-                $field:ident: $field_ty:ty = $sql_ty:expr $(;[$($prop:ident($($prop_arg:expr),*)),+ $(,)?])?
+                $(#[$($field_attr:tt)*])*
+                $field:ident: $field_ty:ty
+                    $(= $sql_ty:expr $(;[$($prop:ident($($prop_arg:expr),*)),+ $(,)?])?)?
             ),+ $(,)?
+            $(=> schema = $schema:literal)?
             $(=> constraints = [$($constraint:expr),+ $(,)?])?
+            $(=> indexes = [$($index:expr),+ $(,)?])?
+            $(=> options = $options:expr)?
+            $(=> functions = [$($function:expr),+ $(,)?])?
+            $(=> policies = [$($policy:expr),+ $(,)?])?
+            $(=> triggers = [$($trigger:expr),+ $(,)?])?
+            $(=> queries = [$($query:expr),+ $(,)?])?
+            $(=> cluster_on = $cluster:literal)?
+            $(=> registered = $registered:tt)?
         }
     ) => {
-        $(#[$outer])*
-        $struct_vis struct $TableName {
-            $(
-                $(#[$inner $($args)*])*
-                $field: $field_ty,
-            )+
+        $crate::__table_struct! {
+            [$(#[$outer])*] $struct_vis $TableName {}
+            $([] [$(#[$($field_attr)*])*] $field: $field_ty,)+
+        }
+
+        const _: () = assert!(
+            $crate::__has_unique_columns(<$TableName as $crate::Flatten>::NAMES),
+            concat!("Duplicate column names in the table ", stringify!($TableName)),
+        );
+
+        impl $crate::Flatten for $TableName {
+            const COLUMNS: usize = 0 $(
+                + $crate::__with_pg_attr!([$(#[$($field_attr)*])*] __field_count!($field_ty))
+            )+;
+
+            const NAMES: &'static [$crate::__ColumnName] = &[$(
+                $crate::__with_pg_attr!([$(#[$($field_attr)*])*] __field_name!($field: $field_ty))
+            ),+];
+
+            fn flat_columns(prefix: &str) -> Vec<$crate::Column> {
+                let mut columns = Vec::with_capacity(Self::COLUMNS);
+                $(
+                    $crate::__with_pg_attr!([$(#[$($field_attr)*])*] __field_columns!(
+                        columns, prefix, $field: $field_ty
+                        $(= $sql_ty $(;[$($prop($($prop_arg),*)),+])?)?
+                    ));
+                )+
+                columns
+            }
+
+            fn flat_values<'a, F>(&'a self, out: &mut F)
+            where
+                F: FnMut($crate::FieldValue<'a>),
+            {
+                $(
+                    $crate::__with_pg_attr!(
+                        [$(#[$($field_attr)*])*] __field_value!(out, &self.$field)
+                    );
+                )+
+            }
+
+            fn from_prefixed_row(
+                row: &tokio_postgres::Row,
+                prefix: &str,
+            ) -> Result<Self, tokio_postgres::Error> {
+                $(
+                    let $field = $crate::__with_pg_attr!([$(#[$($field_attr)*])*] __field_get!(
+                        row, prefix, $field: $field_ty
+                    ))?;
+                )+
+
+                Ok(Self { $($field,)+ })
+            }
         }
 
-        impl $crate::Table< {$crate::count!($($field)+)} > for $TableName {
+        impl $crate::Table< {<$TableName as $crate::Flatten>::COLUMNS} > for $TableName {
             fn name() -> &'static str {
-                $sql_name
+                $crate::__table_name!($sql_name $(, $schema)?)
             }
 
-            fn columns() -> [$crate::Column; $crate::count!($($field)+)] {
-                [
-                    $(
-                        // $field
-                        $crate::ColumnBuilder::new(
-                            stringify!($field), $sql_ty)
-                        $($(.$prop($($prop_arg),*))+)?
-                        .finish(),
-                    )+
-                ]
+            fn columns() -> [$crate::Column; <$TableName as $crate::Flatten>::COLUMNS] {
+                match <Self as $crate::Flatten>::flat_columns("").try_into() {
+                    Ok(columns) => columns,
+                    Err(_) => unreachable!("The number of columns is known"),
+                }
             }
 
             $(
@@ -48,20 +243,104 @@ macro_rules! gen_table {
                     ])
                 }
             )?
+
+            $(
+                fn indexes() -> Vec<$crate::Index> {
+                    vec![$($index),+]
+                }
+            )?
+
+            $(
+                fn options() -> $crate::TableOptions {
+                    $options
+                }
+            )?
+
+            $(
+                fn functions() -> Vec<$crate::SqlFunction> {
+                    vec![$($function),+]
+                }
+            )?
+
+            $(
+                fn policies() -> Vec<$crate::Policy> {
+                    vec![$($policy),+]
+                }
+            )?
+
+            $(
+                fn triggers() -> Vec<$crate::Trigger> {
+                    vec![$($trigger),+]
+                }
+            )?
+
+            $(
+                fn custom_queries() -> Vec<$crate::NamedQuery> {
+                    vec![$($query),+]
+                }
+            )?
+
+            $(
+                fn cluster_on() -> Option<&'static str> {
+                    Some($cluster)
+                }
+            )?
         }
 
-        impl $crate::InsertableValues< {$crate::count!($($field)+)} > for $TableName {
-            fn values(&self) -> [&(dyn postgres_types::ToSql + Sync); $crate::count!($($field)+)] {
-                [$(&self.$field,)+]
+        impl $crate::InsertableValues< {<$TableName as $crate::Flatten>::COLUMNS} > for $TableName {
+            fn values(
+                &self,
+            ) -> [&(dyn tokio_postgres::types::ToSql + Sync); <$TableName as $crate::Flatten>::COLUMNS] {
+                let mut values = [&None::<bool> as &(dyn tokio_postgres::types::ToSql + Sync);
+                    <$TableName as $crate::Flatten>::COLUMNS];
+                let mut slots = values.iter_mut();
+                <Self as $crate::Flatten>::flat_values(self, &mut |value| {
+                    *slots.next().unwrap() = match value {
+                        $crate::FieldValue::Field(value) => value,
+                        $crate::FieldValue::Encoded(value) => panic!(
+                            "The value {:?} of the table {} has a codec, use the `encoded_values`",
+                            value,
+                            stringify!($TableName),
+                        ),
+                    };
+                });
+                values
+            }
+
+            fn encoded_values(
+                &self,
+            ) -> [$crate::FieldValue<'_>; <$TableName as $crate::Flatten>::COLUMNS] {
+                // every placeholder is overwritten with the field values
+                let mut values = [$crate::FieldValue::Field(&None::<bool>);
+                    <$TableName as $crate::Flatten>::COLUMNS];
+                let mut slots = values.iter_mut();
+                <Self as $crate::Flatten>::flat_values(self, &mut |value| {
+                    *slots.next().unwrap() = value;
+                });
+                values
             }
         }
 
+        $crate::impl_dynamic_table!($TableName, <$TableName as $crate::Flatten>::COLUMNS);
+        $crate::__register_table!(
+            $TableName, <$TableName as $crate::Flatten>::COLUMNS $(, $registered)?
+        );
+
         impl TryFrom<tokio_postgres::Row> for $TableName {
             type Error = tokio_postgres::Error;
 
             fn try_from(value: tokio_postgres::Row) -> Result<Self, Self::Error> {
+                <Self as $crate::Flatten>::from_prefixed_row(&value, "")
+            }
+        }
+
+        impl $crate::FromRow for $TableName {
+            fn from_row(row: &tokio_postgres::Row, offset: usize) -> Result<Self, tokio_postgres::Error> {
+                let mut indices = offset..;
                 $(
-                    let $field = value.try_get(stringify!($field))?;
+                    let $field = $crate::__with_pg_attr!([$(#[$($field_attr)*])*] __field_get_at!(
+                        row, indices, $field_ty
+                    ))?;
                 )+
 
                 Ok(Self { $($field,)+ })
@@ -70,6 +349,244 @@ macro_rules! gen_table {
     };
 }
 
+/// Implement the [`DynamicTable`][crate::DynamicTable] for the type
+/// implementing the `Table<N>` and the `InsertableValues<N>`.
+#[macro_export]
+macro_rules! impl_dynamic_table {
+    ($ty:ty, $n:expr) => {
+        impl $crate::DynamicTable for $ty {
+            fn dynamic_name() -> &'static str {
+                <Self as $crate::Table<{ $n }>>::name()
+            }
+
+            fn dynamic_columns() -> Vec<$crate::Column> {
+                Vec::from(<Self as $crate::Table<{ $n }>>::columns())
+            }
+
+            fn dynamic_values(&self) -> Vec<$crate::FieldValue<'_>> {
+                Vec::from(<Self as $crate::InsertableValues<{ $n }>>::encoded_values(
+                    self,
+                ))
+            }
+
+            fn table_def() -> $crate::TableDef {
+                $crate::TableDef::of::<Self, { $n }>()
+            }
+        }
+    };
+}
+
+/// Submit the `gen_table` to the [`Schema::global`][crate::Schema::global]
+/// (unless the `registered = false`).
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_table {
+    ($ty:ty, $n:expr) => {
+        $crate::__register_table!($ty, $n, true);
+    };
+    ($ty:ty, $n:expr, true) => {
+        $crate::__inventory::submit! {
+            $crate::Registration::new(|| $crate::TableDef::of::<$ty, { $n }>())
+        }
+    };
+    ($ty:ty, $n:expr, false) => {};
+}
+
+#[cfg(not(feature = "registry"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_table {
+    ($ty:ty, $n:expr $(, $registered:tt)?) => {};
+}
+
+/// The name of the `gen_table` qualified with its schema if any.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __table_name {
+    ($sql_name:literal) => {
+        $crate::TableConfig::table_name($sql_name)
+    };
+    ($sql_name:literal, $schema:literal) => {
+        $crate::TableConfig::table_name_in($schema, $sql_name)
+    };
+}
+
+/// Define the struct of the `gen_table` macro
+/// stripping the `#[pg(...)]` attributes of the fields.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __table_struct {
+    ([$($outer:tt)*] $vis:vis $TableName:ident { $($fields:tt)* }) => {
+        $($outer)*
+        $vis struct $TableName {
+            $($fields)*
+        }
+    };
+    (
+        [$($outer:tt)*] $vis:vis $TableName:ident { $($fields:tt)* }
+        [$($kept:tt)*] [#[pg $($_pg:tt)*] $($attrs:tt)*] $field:ident: $field_ty:ty, $($rest:tt)*
+    ) => {
+        $crate::__table_struct! {
+            [$($outer)*] $vis $TableName { $($fields)* }
+            [$($kept)*] [$($attrs)*] $field: $field_ty, $($rest)*
+        }
+    };
+    (
+        [$($outer:tt)*] $vis:vis $TableName:ident { $($fields:tt)* }
+        [$($kept:tt)*] [#[$($attr:tt)*] $($attrs:tt)*] $field:ident: $field_ty:ty, $($rest:tt)*
+    ) => {
+        $crate::__table_struct! {
+            [$($outer)*] $vis $TableName { $($fields)* }
+            [$($kept)* #[$($attr)*]] [$($attrs)*] $field: $field_ty, $($rest)*
+        }
+    };
+    (
+        [$($outer:tt)*] $vis:vis $TableName:ident { $($fields:tt)* }
+        [$($kept:tt)*] [] $field:ident: $field_ty:ty, $($rest:tt)*
+    ) => {
+        $crate::__table_struct! {
+            [$($outer)*] $vis $TableName { $($fields)* $($kept)* $field: $field_ty, }
+            $($rest)*
+        }
+    };
+}
+
+/// Find the `#[pg(...)]` attribute of the field and call the `callback` macro
+/// with one of the `plain`, `with(Codec)` or `flatten("prefix")` as the first argument.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __with_pg_attr {
+    ([] $callback:ident!($($args:tt)*)) => {
+        $crate::$callback!(plain $($args)*)
+    };
+    ([#[pg(with = $codec:path)] $($attrs:tt)*] $callback:ident!($($args:tt)*)) => {
+        $crate::$callback!(with($codec) $($args)*)
+    };
+    ([#[pg(flatten)] $($attrs:tt)*] $callback:ident!($($args:tt)*)) => {
+        $crate::$callback!(flatten("") $($args)*)
+    };
+    ([#[pg(flatten, prefix = $prefix:literal)] $($attrs:tt)*] $callback:ident!($($args:tt)*)) => {
+        $crate::$callback!(flatten($prefix) $($args)*)
+    };
+    ([#[$($attr:tt)*] $($attrs:tt)*] $callback:ident!($($args:tt)*)) => {
+        $crate::__with_pg_attr!([$($attrs)*] $callback!($($args)*))
+    };
+}
+
+/// The number of columns of the field.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_count {
+    (flatten($prefix:literal) $field_ty:ty) => {
+        <$field_ty as $crate::Flatten>::COLUMNS
+    };
+    (plain $field_ty:ty) => {
+        1
+    };
+    (with($codec:path) $field_ty:ty) => {
+        1
+    };
+}
+
+/// The name of the field column (or the names of the flattened ones).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_name {
+    (flatten($prefix:literal) $field:ident: $field_ty:ty) => {
+        $crate::__ColumnName::Flatten($prefix, <$field_ty as $crate::Flatten>::NAMES)
+    };
+    (plain $field:ident: $field_ty:ty) => {
+        $crate::__ColumnName::Plain(stringify!($field))
+    };
+    (with($codec:path) $field:ident: $field_ty:ty) => {
+        $crate::__ColumnName::Plain(stringify!($field))
+    };
+}
+
+/// Add the definitions of the field columns.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_columns {
+    (with($codec:path) $($rest:tt)*) => {
+        $crate::__field_columns!(plain $($rest)*)
+    };
+    (flatten($prefix:literal) $columns:ident, $outer_prefix:ident, $field:ident: $field_ty:ty) => {
+        $columns.extend(<$field_ty as $crate::Flatten>::flat_columns(&$crate::__prefixed(
+            $outer_prefix,
+            $prefix,
+        )))
+    };
+    (
+        plain $columns:ident, $prefix:ident, $field:ident: $field_ty:ty
+        = $sql_ty:expr $(;[$($prop:ident($($prop_arg:expr),*)),+])?
+    ) => {
+        $columns.push(
+            $crate::ColumnBuilder::new($crate::__prefixed($prefix, stringify!($field)), $sql_ty)
+            .rust_type(::std::any::type_name::<$field_ty>())
+            $($(.$prop($($prop_arg),*))+)?
+            .finish(),
+        )
+    };
+}
+
+/// Pass the reference to the field value (wrapped into the codec if any) to the `out`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_value {
+    (plain $out:ident, $value:expr) => {
+        $out($crate::FieldValue::Field($value))
+    };
+    (with($codec:path) $out:ident, $value:expr) => {
+        $out($crate::With::<$codec, _>::field_value($value))
+    };
+    (flatten($prefix:literal) $out:ident, $value:expr) => {
+        $crate::Flatten::flat_values($value, $out)
+    };
+}
+
+/// Get the field value from the row by the column name
+/// (decoding it with the codec if any).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_get {
+    (plain $row:ident, $prefix:ident, $field:ident: $field_ty:ty) => {
+        $row.try_get::<_, $field_ty>(&*$crate::__prefixed($prefix, stringify!($field)))
+    };
+    (with($codec:path) $row:ident, $prefix:ident, $field:ident: $field_ty:ty) => {
+        $row.try_get::<_, $crate::With<$codec, $field_ty>>(&*$crate::__prefixed(
+            $prefix,
+            stringify!($field),
+        ))
+        .map($crate::With::into_inner)
+    };
+    (flatten($inner_prefix:literal) $row:ident, $prefix:ident, $field:ident: $field_ty:ty) => {
+        <$field_ty as $crate::Flatten>::from_prefixed_row(
+            $row,
+            &$crate::__prefixed($prefix, $inner_prefix),
+        )
+    };
+}
+
+/// Get the field value from the row by the next column index(es).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_get_at {
+    (plain $row:ident, $indices:ident, $field_ty:ty) => {
+        $row.try_get::<_, $field_ty>($indices.next().unwrap())
+    };
+    (with($codec:path) $row:ident, $indices:ident, $field_ty:ty) => {
+        $row.try_get::<_, $crate::With<$codec, $field_ty>>($indices.next().unwrap())
+            .map($crate::With::into_inner)
+    };
+    (flatten($prefix:literal) $row:ident, $indices:ident, $field_ty:ty) => {{
+        let offset = $indices.start;
+        // skip all the columns of the field
+        let _ = $indices.nth(<$field_ty as $crate::Flatten>::COLUMNS - 1);
+        <$field_ty as $crate::FromRow>::from_row($row, offset)
+    }};
+}
+
 #[macro_export]
 macro_rules! primary_key_with_indices {
     ($name:expr => [$($idx:literal),+ $(,)?]) => {
@@ -101,6 +618,72 @@ macro_rules! unique_with_indices {
     };
 }
 
+#[macro_export]
+macro_rules! index_with_indices {
+    ([$($idx:literal),+ $(,)?]) => {
+         $crate::index_with_indices!(Self[$($idx),+])
+    };
+    ($table:ident [$($idx:literal),+ $(,)?]) => {
+         $crate::Index::new([$(&$table::columns()[$idx]),+])
+    };
+}
+
+/// Define the enum mapped to the PostgreSQL enum type with the [`PgEnum`][crate::PgEnum]
+/// and the derived `ToSql` and `FromSql` (requires the `postgres_types` dependency).
+///
+/// ```ignore
+/// pg_enum! {
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///     pub enum Mood("mood") {
+///         Sad = "sad",
+///         Happy = "happy",
+///     }
+/// }
+///
+/// gen_table!(
+///     struct Person("persons") {
+///         name: String = Type::TEXT,
+///         mood: Mood = Mood::sql_type(),
+///     }
+/// );
+/// ```
+#[macro_export]
+macro_rules! pg_enum {
+    (
+        $(#[$outer:meta])*
+        $vis:vis enum $Enum:ident ($sql_name:literal) {
+            $($(#[$variant_attr:meta])* $variant:ident = $label:literal),+ $(,)?
+        }
+    ) => {
+        $(#[$outer])*
+        #[derive(postgres_types::ToSql, postgres_types::FromSql)]
+        #[postgres(name = $sql_name)]
+        $vis enum $Enum {
+            $(
+                $(#[$variant_attr])*
+                #[postgres(name = $label)]
+                $variant,
+            )+
+        }
+
+        const _: () = assert!(
+            $crate::__has_unique_names(&[$($label),+]),
+            concat!("Duplicate labels in the enum ", stringify!($Enum)),
+        );
+
+        impl $crate::PgEnum for $Enum {
+            const TYPE_NAME: &'static str = $sql_name;
+            const LABELS: &'static [&'static str] = &[$($label),+];
+
+            fn label(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $label,)+
+                }
+            }
+        }
+    };
+}
+
 #[test]
 fn constraints_are_compiled() {
     use crate::Table as _;
@@ -140,4 +723,133 @@ fn constraints_are_compiled() {
                CONSTRAINT pk PRIMARY KEY (x), \
                CONSTRAINT fk FOREIGN KEY (x, y) REFERENCES bar (y, z));"
     );
+
+    gen_table!(
+        pub struct Baz("baz") {
+            x: Option<i16> = postgres::types::Type::INT2; [nullable()],
+            y: Option<i16> = postgres::types::Type::INT2; [nullable()],
+            => constraints = [
+                foreign_key_with_indices!("fk" => Bar [0=>1, 1=>2])
+                    .match_mode(crate::ForeignKeyMatch::Full)
+                    .on_delete(crate::ForeignKeyAction::Cascade)
+                    .on_update(crate::ForeignKeyAction::SetNull),
+            ]
+        }
+    );
+    assert_eq!(
+        Baz::create_table_sql(),
+        "CREATE TABLE IF NOT EXISTS baz \
+               (x int2 NULL, y int2 NULL, \
+               CONSTRAINT fk FOREIGN KEY (x, y) REFERENCES bar (y, z) \
+               MATCH FULL ON DELETE CASCADE ON UPDATE SET NULL);"
+    );
+}
+
+#[test]
+fn indexes_are_compiled() {
+    use crate::{IndexKey, Order, Table as _};
+
+    gen_table!(
+        pub struct Visit("visits") {
+            user_id: i32 = postgres::types::Type::INT4; [index()],
+            day: i32 = postgres::types::Type::INT4,
+            page: String = postgres::types::Type::TEXT,
+            => indexes = [
+                index_with_indices!([0, 1]),
+                crate::Index::new([
+                    IndexKey::from(&Self::columns()[2]),
+                    IndexKey::from(&Self::columns()[1]).order(Order::Desc),
+                ]),
+            ]
+        }
+    );
+    let indices: Vec<_> = Visit::create_indices_sql()
+        .into_iter()
+        .map(|idx| (idx.name().to_owned(), idx.create_sql().to_owned()))
+        .collect();
+    assert_eq!(
+        indices,
+        [
+            (
+                "user_id_idx_visits".to_owned(),
+                "CREATE INDEX IF NOT EXISTS user_id_idx_visits ON visits \
+                USING btree (user_id)"
+                    .to_owned()
+            ),
+            (
+                "user_id_day_idx_visits".to_owned(),
+                "CREATE INDEX IF NOT EXISTS user_id_day_idx_visits ON visits \
+                USING btree (user_id, day)"
+                    .to_owned()
+            ),
+            (
+                "page_day_idx_visits".to_owned(),
+                "CREATE INDEX IF NOT EXISTS page_day_idx_visits ON visits \
+                USING btree (page, day DESC)"
+                    .to_owned()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn unique_names() {
+    assert!(__has_unique_names(&[]));
+    assert!(__has_unique_names(&["id", "name", "names"]));
+    assert!(!__has_unique_names(&["id", "name", "id"]));
+}
+
+#[test]
+fn unique_columns() {
+    use __ColumnName::{Flatten, Plain};
+
+    let address = &[Plain("city"), Plain("street")];
+    assert!(__has_unique_columns(&[
+        Plain("shipping"),
+        Flatten("shipping_", address),
+        Flatten("billing_", address),
+    ]));
+    assert!(!__has_unique_columns(&[
+        Plain("shipping_city"),
+        Flatten("shipping_", address),
+    ]));
+    assert!(!__has_unique_columns(&[
+        Flatten("", address),
+        Plain("city")
+    ]));
+    // the same after the truncation
+    let long = "x".repeat(MAX_NAME_LEN);
+    let long: &'static str = Box::leak(long.into_boxed_str());
+    assert!(!__has_unique_columns(&[
+        Flatten(long, &[Plain("a")]),
+        Flatten(long, &[Plain("b")]),
+    ]));
+}
+
+#[test]
+fn enums_are_compiled() {
+    use crate::{PgEnum, Table as _};
+
+    pg_enum! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum Mood("macro_mood") {
+            Sad = "sad",
+            /// Neither sad nor happy.
+            Fine = "it's fine",
+            Happy = "happy",
+        }
+    }
+
+    gen_table!(
+        struct Person("persons") {
+            mood: Mood = Mood::sql_type(),
+        }
+    );
+
+    assert_eq!(Mood::LABELS, ["sad", "it's fine", "happy"]);
+    assert_eq!(Mood::Fine.label(), "it's fine");
+    assert_eq!(
+        Person::create_types_sql()[0].create_sql(),
+        "CREATE TYPE \"macro_mood\" AS ENUM ('sad', 'it''s fine', 'happy')"
+    );
 }
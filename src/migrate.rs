@@ -0,0 +1,111 @@
+use std::fmt::Write as _;
+
+use crate::{introspect::TableInfo, table::Table};
+
+/// What [`sync_table`][crate::PgTableExtension::sync_table] should do
+/// besides adding the missing columns.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SyncOptions {
+    create_types: bool,
+    create_indices: bool,
+}
+
+impl SyncOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the missing custom types before adding the columns.
+    pub const fn create_types(mut self) -> Self {
+        self.create_types = true;
+        self
+    }
+
+    /// Create the missing indices after adding the columns.
+    pub const fn create_indices(mut self) -> Self {
+        self.create_indices = true;
+        self
+    }
+
+    pub(crate) const fn types_enabled(&self) -> bool {
+        self.create_types
+    }
+
+    pub(crate) const fn indices_enabled(&self) -> bool {
+        self.create_indices
+    }
+}
+
+/// The names of the columns missing in the live table
+/// with the statements to add them.
+///
+/// Adding a `NOT NULL` column to a non-empty table fails
+/// since the existing rows have no value for it.
+pub(crate) fn add_columns_sql<T, const N: usize>(live: &TableInfo) -> Vec<(String, String)>
+where
+    T: Table<N>,
+{
+    T::columns()
+        .iter()
+        .filter(|col| live.column(col.name()).is_none())
+        .map(|col| {
+            let mut sql = format!("ALTER TABLE {} ADD COLUMN IF NOT EXISTS {}", T::name(), col);
+            // the storage of the new column is set in the same statement
+            for action in col.storage_actions() {
+                write!(sql, ", {}", action).unwrap();
+            }
+            (col.name().to_owned(), sql)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, ColumnBuilder, Storage};
+
+    gen_table!(
+        struct Profile("profiles") {
+            id: i32 = Type::INT4; [primary_key()],
+            nickname: String = Type::TEXT,
+            bio: Option<String> = Type::TEXT; [nullable()],
+            email: Option<String> = Type::TEXT; [nullable(), unique()],
+            avatar: Option<Vec<u8>> = Type::BYTEA; [nullable(), storage(Storage::External)],
+        }
+    );
+
+    #[test]
+    fn missing_columns() {
+        let live = TableInfo {
+            name: "profiles".into(),
+            columns: vec![
+                ColumnBuilder::new("id", Type::INT4).primary_key().finish(),
+                ColumnBuilder::new("nickname", Type::TEXT).finish(),
+            ],
+            constraints: vec![],
+            indexes: vec![],
+        };
+        assert_eq!(
+            add_columns_sql::<Profile, 5>(&live),
+            [
+                (
+                    "bio".to_owned(),
+                    "ALTER TABLE profiles ADD COLUMN IF NOT EXISTS bio text NULL".to_owned()
+                ),
+                (
+                    "email".to_owned(),
+                    "ALTER TABLE profiles ADD COLUMN IF NOT EXISTS email text NULL UNIQUE"
+                        .to_owned()
+                ),
+                (
+                    "avatar".to_owned(),
+                    "ALTER TABLE profiles ADD COLUMN IF NOT EXISTS avatar bytea NULL, \
+                    ALTER COLUMN avatar SET STORAGE EXTERNAL"
+                        .to_owned()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use cidr::{IpCidr, IpInet};
+use eui48::MacAddress;
+
+use crate::{column::SqlLiteral, type_helpers::quote_literal};
+
+impl SqlLiteral for IpCidr {
+    fn to_literal(&self) -> String {
+        format!("{}::cidr", quote_literal(&self.to_string()))
+    }
+}
+
+impl SqlLiteral for IpInet {
+    fn to_literal(&self) -> String {
+        format!("{}::inet", quote_literal(&self.to_string()))
+    }
+}
+
+impl SqlLiteral for MacAddress {
+    fn to_literal(&self) -> String {
+        format!("{}::macaddr", quote_literal(&self.to_hex_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client, ColumnBuilder, PgTableExtension as _};
+
+    gen_table!(
+        #[derive(Debug, PartialEq)]
+        struct Access("network_accesses") {
+            id: i32 = Type::INT4; [primary_key()],
+            client: IpAddr = Type::INET,
+            interface: IpInet = Type::INET,
+            subnet: IpCidr = Type::CIDR,
+            device: MacAddress = Type::MACADDR,
+        }
+    );
+
+    fn access() -> Access {
+        Access {
+            id: 1,
+            client: IpAddr::V6(Ipv6Addr::LOCALHOST),
+            interface: "10.1.2.3/16".parse().unwrap(),
+            subnet: "10.1.0.0/16".parse().unwrap(),
+            device: MacAddress::parse_str("08:00:2b:01:02:03").unwrap(),
+        }
+    }
+
+    #[test]
+    fn literals() {
+        let access = access();
+        assert_eq!(access.client.to_literal(), "'::1'::inet");
+        assert_eq!(access.interface.to_literal(), "'10.1.2.3/16'::inet");
+        assert_eq!(access.subnet.to_literal(), "'10.1.0.0/16'::cidr");
+        assert_eq!(access.device.to_literal(), "'08:00:2b:01:02:03'::macaddr");
+
+        let column = ColumnBuilder::new("gateway", Type::INET)
+            .default_value(IpAddr::V4(Ipv4Addr::new(10, 1, 0, 1)))
+            .finish();
+        assert_eq!(
+            column.to_string(),
+            "gateway inet DEFAULT ('10.1.0.1'::inet) NOT NULL"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS network_accesses")
+            .unwrap();
+        client.create_table::<Access, 5>().unwrap();
+
+        client.insert_row(&access()).unwrap();
+        assert_eq!(client.select_all::<Access, 5>().unwrap(), [access()]);
+
+        let in_subnet: bool = client
+            .query_scalar("SELECT interface <<= subnet FROM network_accesses", &[])
+            .unwrap();
+        assert!(in_subnet);
+
+        client.batch_execute("DROP TABLE network_accesses").unwrap();
+    }
+}
@@ -0,0 +1,488 @@
+use std::fmt::{self, Display};
+
+use itertools::Itertools as _;
+
+use crate::{
+    column::{Column, SqlLiteral},
+    config::unqualified,
+    table::Table,
+    type_helpers::validate_name,
+};
+
+/// How the rows of the partitioned table are distributed among its partitions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PartitionStrategy {
+    /// By the non-overlapping ranges of the keys (e.g. the months of the timestamps).
+    Range,
+    /// By the explicit lists of the key values.
+    List,
+    /// By the remainder of the hash of the keys.
+    Hash,
+}
+
+impl Display for PartitionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            PartitionStrategy::Range => "RANGE",
+            PartitionStrategy::List => "LIST",
+            PartitionStrategy::Hash => "HASH",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+/// The `PARTITION BY` clause of the table (see the [`TableOptions::partition_by`][crate::TableOptions::partition_by]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PartitionKey {
+    strategy: PartitionStrategy,
+    columns: Vec<String>,
+}
+
+impl PartitionKey {
+    pub(crate) fn new(strategy: PartitionStrategy, columns: &[&Column]) -> Self {
+        assert!(
+            !columns.is_empty(),
+            "The partition key should have at least one column"
+        );
+        Self {
+            strategy,
+            columns: columns.iter().map(|col| col.name().to_owned()).collect(),
+        }
+    }
+}
+
+impl Display for PartitionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PARTITION BY {} ({})",
+            self.strategy,
+            self.columns.iter().join(", ")
+        )
+    }
+}
+
+/// The values of the partition key stored in the partition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartitionBounds {
+    /// The keys from the lower bound (inclusive) to the upper one (exclusive)
+    /// rendered as the SQL literals.
+    Range {
+        from: String,
+        to: String,
+    },
+    List(Vec<String>),
+    Hash {
+        modulus: u32,
+        remainder: u32,
+    },
+    /// The rows not fitting into any other partition.
+    Default,
+}
+
+impl PartitionBounds {
+    pub fn range(from: impl SqlLiteral, to: impl SqlLiteral) -> Self {
+        Self::Range {
+            from: from.to_literal(),
+            to: to.to_literal(),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If no values are given.
+    pub fn list<V>(values: impl IntoIterator<Item = V>) -> Self
+    where
+        V: SqlLiteral,
+    {
+        let values: Vec<_> = values.into_iter().map(|v| v.to_literal()).collect();
+        assert!(
+            !values.is_empty(),
+            "The list partition should have at least one value"
+        );
+        Self::List(values)
+    }
+
+    /// # Panics
+    ///
+    /// If the remainder is not less than the modulus.
+    pub fn hash(modulus: u32, remainder: u32) -> Self {
+        assert!(
+            remainder < modulus,
+            "The remainder {} should be less than the modulus {}",
+            remainder,
+            modulus
+        );
+        Self::Hash { modulus, remainder }
+    }
+}
+
+impl Display for PartitionBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Range { from, to } => write!(f, "FOR VALUES FROM ({}) TO ({})", from, to),
+            Self::List(values) => write!(f, "FOR VALUES IN ({})", values.iter().join(", ")),
+            Self::Hash { modulus, remainder } => write!(
+                f,
+                "FOR VALUES WITH (MODULUS {}, REMAINDER {})",
+                modulus, remainder
+            ),
+            Self::Default => write!(f, "DEFAULT"),
+        }
+    }
+}
+
+/// The name of the partition `{table}_{suffix}` (in the schema of the table).
+///
+/// # Panics
+///
+/// If the resulting name is not a valid lowercase identifier.
+pub(crate) fn partition_name<T, const N: usize>(suffix: &str) -> String
+where
+    T: Table<N>,
+{
+    let name = format!("{}_{}", T::name(), suffix);
+    validate_name("partition", unqualified(&name));
+    name
+}
+
+/// # Panics
+///
+/// If the table is not partitioned.
+pub(crate) fn create_partition_sql<T, const N: usize>(
+    name: &str,
+    bounds: &PartitionBounds,
+) -> String
+where
+    T: Table<N>,
+{
+    assert!(
+        T::options().partition_key().is_some(),
+        "Table {} is not partitioned",
+        T::name()
+    );
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} PARTITION OF {} {}",
+        name,
+        T::name(),
+        bounds
+    )
+}
+
+/// The length of the time ranges of the partitions
+/// (see the [`ensure_partitions`][crate::PgTableExtension::ensure_partitions]).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PartitionInterval {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl PartitionInterval {
+    /// The field of the `date_trunc` the ranges start at.
+    pub(crate) const fn unit(self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Year => "year",
+        }
+    }
+}
+
+/// What to do with the partitions older than the retention period.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Expiration {
+    /// Keep the partition as a standalone table (e.g. to archive it).
+    Detach,
+    Drop,
+}
+
+/// The time partitions to maintain: the current one, the ones `ahead` of it
+/// and (optionally) the ones to expire after the retention period.
+///
+/// The partitions are named `{table}_p{YYYYMMDD}` after the start of their ranges.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PartitionSchedule {
+    interval: PartitionInterval,
+    ahead: u32,
+    retention: Option<(u32, Expiration)>,
+}
+
+impl PartitionSchedule {
+    /// The current and the next partitions without the retention.
+    pub const fn new(interval: PartitionInterval) -> Self {
+        Self {
+            interval,
+            ahead: 1,
+            retention: None,
+        }
+    }
+
+    /// The number of the partitions to create after the current one.
+    pub const fn ahead(mut self, count: u32) -> Self {
+        self.ahead = count;
+        self
+    }
+
+    /// Expire the partitions ending more than `count` intervals before the current one.
+    pub const fn retain(mut self, count: u32, expiration: Expiration) -> Self {
+        self.retention = Some((count, expiration));
+        self
+    }
+
+    pub const fn interval(&self) -> PartitionInterval {
+        self.interval
+    }
+
+    pub const fn get_ahead(&self) -> u32 {
+        self.ahead
+    }
+
+    pub const fn retention(&self) -> Option<(u32, Expiration)> {
+        self.retention
+    }
+}
+
+/// The partitions changed with the [`ensure_partitions`][crate::PgTableExtension::ensure_partitions].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartitionChanges {
+    created: Vec<String>,
+    expired: Vec<String>,
+}
+
+impl PartitionChanges {
+    pub fn created(&self) -> &[String] {
+        &self.created
+    }
+
+    /// The partitions detached or dropped.
+    pub fn expired(&self) -> &[String] {
+        &self.expired
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.expired.is_empty()
+    }
+
+    pub(crate) fn push_created(&mut self, name: String) {
+        self.created.push(name);
+    }
+
+    pub(crate) fn push_expired(&mut self, name: String) {
+        self.expired.push(name);
+    }
+}
+
+/// The suffixes and the bounds of the current partition and the `$2` next ones
+/// for the `date_trunc` field `$1`.
+pub(crate) const UPCOMING_PARTITIONS_SQL: &str = "\
+    SELECT 'p' || to_char(b, 'YYYYMMDD'), b::text, (b + ('1 ' || $1::text)::interval)::text \
+    FROM generate_series(\
+        date_trunc($1::text, now()), \
+        date_trunc($1::text, now()) + $2::int4 * ('1 ' || $1::text)::interval, \
+        ('1 ' || $1::text)::interval\
+    ) b";
+
+/// The names of the partitions of the table `$1` following the naming of the
+/// [`PartitionSchedule`] and starting more than `$3` intervals of the field `$2` ago.
+pub(crate) const EXPIRED_PARTITIONS_SQL: &str = "\
+    SELECT c.relname::text FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid \
+    WHERE i.inhparent = $1::text::regclass \
+    AND c.relname ~ '_p[0-9]{8}$' \
+    AND right(c.relname, 8) < to_char(\
+        date_trunc($2::text, now()) - $3::int4 * ('1 ' || $2::text)::interval, 'YYYYMMDD'\
+    ) \
+    ORDER BY 1";
+
+/// The name of the partition qualified with the schema of the table.
+pub(crate) fn qualified<T, const N: usize>(name: &str) -> String
+where
+    T: Table<N>,
+{
+    match T::name().rsplit_once('.') {
+        Some((schema, _)) => format!("{}.{}", schema, name),
+        None => name.to_owned(),
+    }
+}
+
+pub(crate) fn expire_sql<T, const N: usize>(name: &str, expiration: Expiration) -> String
+where
+    T: Table<N>,
+{
+    match expiration {
+        Expiration::Detach => format!("ALTER TABLE {} DETACH PARTITION {}", T::name(), name),
+        Expiration::Drop => format!("DROP TABLE {}", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client, PgTableExtension as _, TableOptions};
+
+    gen_table!(
+        struct Reading("partition_readings") {
+            sensor: i32 = Type::INT4,
+            day: i32 = Type::INT4,
+            value: f64 = Type::FLOAT8,
+            => options = TableOptions::new()
+                .partition_by(PartitionStrategy::Range, &[&Self::columns()[1]])
+        }
+    );
+
+    gen_table!(
+        struct Unpartitioned("partition_plain") {
+            id: i32 = Type::INT4,
+        }
+    );
+
+    #[test]
+    fn bounds() {
+        assert_eq!(
+            Reading::create_table_sql(),
+            "CREATE TABLE IF NOT EXISTS partition_readings \
+            (sensor int4 NOT NULL, day int4 NOT NULL, value float8 NOT NULL) \
+            PARTITION BY RANGE (day);"
+        );
+        assert_eq!(
+            PartitionBounds::range(1, 10).to_string(),
+            "FOR VALUES FROM (1) TO (10)"
+        );
+        assert_eq!(
+            PartitionBounds::list(["eu", "us"]).to_string(),
+            "FOR VALUES IN ('eu', 'us')"
+        );
+        assert_eq!(
+            PartitionBounds::hash(4, 1).to_string(),
+            "FOR VALUES WITH (MODULUS 4, REMAINDER 1)"
+        );
+        let name = partition_name::<Reading, 3>("early");
+        assert_eq!(
+            create_partition_sql::<Reading, 3>(&name, &PartitionBounds::Default),
+            "CREATE TABLE IF NOT EXISTS partition_readings_early \
+            PARTITION OF partition_readings DEFAULT"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Table partition_plain is not partitioned")]
+    fn not_partitioned() {
+        let _ = create_partition_sql::<Unpartitioned, 1>("p", &PartitionBounds::Default);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid partition name \"partition_readings_Q1\"")]
+    fn invalid_suffix() {
+        let _ = partition_name::<Reading, 3>("Q1");
+    }
+
+    #[test]
+    fn routed_rows() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS partition_readings")
+            .unwrap();
+        client.create_table::<Reading, 3>().unwrap();
+        let first = client
+            .create_partition::<Reading, 3>("d1", &PartitionBounds::range(1, 10))
+            .unwrap();
+        assert_eq!(first, "partition_readings_d1");
+        client
+            .create_partition::<Reading, 3>("other", &PartitionBounds::Default)
+            .unwrap();
+
+        let readings = [(1, 5), (2, 50)].map(|(sensor, day)| Reading {
+            sensor,
+            day,
+            value: 0.5,
+        });
+        client.insert_rows(&readings).unwrap();
+        let counts: Vec<(String, i64)> = client
+            .query(
+                "SELECT tableoid::regclass::text, count(*) FROM partition_readings \
+                GROUP BY 1 ORDER BY 1",
+                &[],
+            )
+            .unwrap()
+            .iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("partition_readings_d1".to_owned(), 1),
+                ("partition_readings_other".to_owned(), 1)
+            ]
+        );
+        client
+            .batch_execute("DROP TABLE partition_readings")
+            .unwrap();
+    }
+
+    gen_table!(
+        struct Event("partition_events") {
+            id: i32 = Type::INT4,
+            at: std::time::SystemTime = Type::TIMESTAMPTZ,
+            => options = TableOptions::new()
+                .partition_by(PartitionStrategy::Range, &[&Self::columns()[1]])
+        }
+    );
+
+    #[test]
+    fn scheduled() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS partition_events, partition_events_p20000101")
+            .unwrap();
+        client.create_table::<Event, 2>().unwrap();
+        client
+            .create_partition::<Event, 2>(
+                "p20000101",
+                &PartitionBounds::range("2000-01-01", "2000-02-01"),
+            )
+            .unwrap();
+
+        let schedule = PartitionSchedule::new(PartitionInterval::Month).ahead(2);
+        let changes = client.ensure_partitions::<Event, 2>(&schedule).unwrap();
+        assert_eq!(changes.created().len(), 3);
+        assert!(changes.expired().is_empty());
+        client
+            .insert_row(&Event {
+                id: 1,
+                at: std::time::SystemTime::now(),
+            })
+            .unwrap();
+        let current: String = client
+            .query_scalar("SELECT tableoid::regclass::text FROM partition_events", &[])
+            .unwrap();
+        assert_eq!(current, changes.created()[0]);
+
+        let schedule = schedule.retain(12, Expiration::Detach);
+        let changes = client.ensure_partitions::<Event, 2>(&schedule).unwrap();
+        assert!(changes.created().is_empty());
+        assert_eq!(changes.expired(), ["partition_events_p20000101"]);
+        let detached: Option<String> = client
+            .query_scalar(
+                "SELECT to_regclass('partition_events_p20000101')::text",
+                &[],
+            )
+            .unwrap();
+        assert!(detached.is_some());
+        assert!(client
+            .ensure_partitions::<Event, 2>(&schedule)
+            .unwrap()
+            .is_empty());
+
+        client
+            .batch_execute("DROP TABLE partition_events, partition_events_p20000101")
+            .unwrap();
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt::{self, Display, Write as _};
+
+use itertools::Itertools as _;
+
+use crate::type_helpers::{quote_literal, validate_name};
+
+/// The statements the [`Policy`] applies to.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum PolicyCommand {
+    #[default]
+    All,
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
+impl Display for PolicyCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            PolicyCommand::All => "ALL",
+            PolicyCommand::Select => "SELECT",
+            PolicyCommand::Insert => "INSERT",
+            PolicyCommand::Update => "UPDATE",
+            PolicyCommand::Delete => "DELETE",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+/// The row-level security policy of the table (see the [`Table::policies`][crate::Table::policies]).
+///
+/// The rows are visible (and modifiable) if any of the permissive policies
+/// and all the restrictive ones allow it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+    name: String,
+    command: PolicyCommand,
+    restrictive: bool,
+    roles: Vec<String>,
+    using: Option<String>,
+    with_check: Option<String>,
+}
+
+impl Policy {
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn new(name: impl AsRef<str>) -> Self {
+        let name = name.as_ref();
+        validate_name("policy", name);
+        Self {
+            name: name.to_owned(),
+            command: PolicyCommand::default(),
+            restrictive: false,
+            roles: vec![],
+            using: None,
+            with_check: None,
+        }
+    }
+
+    pub const fn command(mut self, command: PolicyCommand) -> Self {
+        self.command = command;
+        self
+    }
+
+    pub const fn restrictive(mut self) -> Self {
+        self.restrictive = true;
+        self
+    }
+
+    /// Only apply the policy to the role (all the roles by default).
+    ///
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn to_role(mut self, role: impl AsRef<str>) -> Self {
+        let role = role.as_ref();
+        validate_name("role", role);
+        self.roles.push(role.to_owned());
+        self
+    }
+
+    /// The condition of the existing rows to be visible
+    /// (e.g. `tenant_id = current_setting('app.tenant')::int`).
+    pub fn using(mut self, condition: impl AsRef<str>) -> Self {
+        self.using = Some(condition.as_ref().to_owned());
+        self
+    }
+
+    /// The condition of the inserted or updated rows to be accepted.
+    pub fn with_check(mut self, condition: impl AsRef<str>) -> Self {
+        self.with_check = Some(condition.as_ref().to_owned());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The _CREATE POLICY_ statement.
+    pub fn create_sql(&self, table_name: &str) -> String {
+        let mut sql = format!(
+            "CREATE POLICY {} ON {} AS {} FOR {}",
+            self.name,
+            table_name,
+            if self.restrictive {
+                "RESTRICTIVE"
+            } else {
+                "PERMISSIVE"
+            },
+            self.command
+        );
+        if !self.roles.is_empty() {
+            write!(sql, " TO {}", self.roles.iter().join(", ")).unwrap();
+        }
+        if let Some(using) = &self.using {
+            write!(sql, " USING ({})", using).unwrap();
+        }
+        if let Some(with_check) = &self.with_check {
+            write!(sql, " WITH CHECK ({})", with_check).unwrap();
+        }
+        sql
+    }
+
+    /// The policy is created unless the table already has one with the same name
+    /// (there is no `CREATE POLICY IF NOT EXISTS`).
+    pub(crate) fn create_if_missing_sql(&self, table_name: &str) -> String {
+        format!(
+            "DO $pg_helper$ BEGIN \
+            IF NOT EXISTS (SELECT FROM pg_policy WHERE polrelid = {}::regclass AND polname = {}) \
+            THEN {}; END IF; END $pg_helper$",
+            quote_literal(table_name),
+            quote_literal(&self.name),
+            self.create_sql(table_name)
+        )
+    }
+}
+
+/// Enable the row-level security of the table and create its policies.
+pub(crate) fn row_security_sql(table_name: &str, policies: &[Policy]) -> Vec<String> {
+    if policies.is_empty() {
+        return vec![];
+    }
+    std::iter::once(format!(
+        "ALTER TABLE {} ENABLE ROW LEVEL SECURITY",
+        table_name
+    ))
+    .chain(
+        policies
+            .iter()
+            .map(|policy| policy.create_if_missing_sql(table_name)),
+    )
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client, PgTableExtension as _, Table as _};
+
+    gen_table!(
+        struct Note("rls_notes") {
+            id: i32 = Type::INT4; [primary_key()],
+            tenant: String = Type::TEXT,
+            => policies = [
+                Policy::new("tenant_isolation")
+                    .to_role("rls_tenant")
+                    .using("tenant = current_user")
+                    .with_check("tenant = current_user"),
+                Policy::new("no_deletes")
+                    .command(PolicyCommand::Delete)
+                    .restrictive()
+                    .using("false"),
+            ]
+        }
+    );
+
+    #[test]
+    fn statements() {
+        assert_eq!(
+            Note::row_security_sql()[..2],
+            [
+                "ALTER TABLE rls_notes ENABLE ROW LEVEL SECURITY".to_owned(),
+                "DO $pg_helper$ BEGIN IF NOT EXISTS (SELECT FROM pg_policy \
+                WHERE polrelid = 'rls_notes'::regclass AND polname = 'tenant_isolation') \
+                THEN CREATE POLICY tenant_isolation ON rls_notes AS PERMISSIVE FOR ALL \
+                TO rls_tenant USING (tenant = current_user) WITH CHECK (tenant = current_user); \
+                END IF; END $pg_helper$"
+                    .to_owned(),
+            ]
+        );
+        assert_eq!(
+            Note::policies()[1].create_sql("rls_notes"),
+            "CREATE POLICY no_deletes ON rls_notes AS RESTRICTIVE FOR DELETE USING (false)"
+        );
+    }
+
+    #[test]
+    fn isolated_tenants() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS rls_notes; \
+                DROP ROLE IF EXISTS rls_tenant; \
+                CREATE ROLE rls_tenant",
+            )
+            .unwrap();
+        client.create_table::<Note, 2>().unwrap();
+        // the policies already exist
+        client.create_table::<Note, 2>().unwrap();
+        client
+            .batch_execute(
+                "INSERT INTO rls_notes VALUES (1, 'rls_tenant'), (2, 'other'); \
+                GRANT SELECT, DELETE ON rls_notes TO rls_tenant; \
+                SET ROLE rls_tenant",
+            )
+            .unwrap();
+
+        let visible = client.select_all::<Note, 2>().unwrap();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].id, 1);
+        let deleted = client.execute("DELETE FROM rls_notes", &[]).unwrap();
+        assert_eq!(deleted, 0);
+
+        client
+            .batch_execute(
+                "RESET ROLE; \
+                DROP TABLE rls_notes; \
+                DROP ROLE rls_tenant",
+            )
+            .unwrap();
+    }
+}
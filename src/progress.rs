@@ -0,0 +1,220 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use postgres_types::private::BytesMut;
+
+use crate::table::{InsertableValues, Table};
+
+/// The flag shared with the long-running operation to stop it
+/// at the next chunk boundary (e.g. from the signal handler).
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl From<Arc<AtomicBool>> for CancelToken {
+    fn from(flag: Arc<AtomicBool>) -> Self {
+        Self(flag)
+    }
+}
+
+/// How to split the bulk operation into the chunks.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    chunk_size: usize,
+    cancel: Option<CancelToken>,
+}
+
+impl BatchOptions {
+    /// # Panics
+    ///
+    /// If the `chunk_size` is zero.
+    pub fn new(chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "The chunk size should be positive");
+        Self {
+            chunk_size,
+            cancel: None,
+        }
+    }
+
+    /// Stop before the next chunk once the `token` is cancelled
+    /// (the chunks done so far are kept).
+    pub fn cancel_on(mut self, token: impl Into<CancelToken>) -> Self {
+        self.cancel = Some(token.into());
+        self
+    }
+
+    pub const fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        matches!(&self.cancel, Some(token) if token.is_cancelled())
+    }
+}
+
+/// The state of the bulk operation reported after every chunk.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Progress {
+    rows: u64,
+    total: u64,
+    bytes: u64,
+    elapsed: Duration,
+    chunk_rows: u64,
+    chunk_elapsed: Duration,
+}
+
+impl Progress {
+    /// The number of the rows written so far.
+    pub const fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// The number of the rows to write.
+    pub const fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// The size of the encoded values written so far.
+    pub const fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// The time since the operation has started.
+    pub const fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The rows per second written in the last chunk.
+    pub fn throughput(&self) -> f64 {
+        let secs = self.chunk_elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.chunk_rows as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    pub const fn is_done(&self) -> bool {
+        self.rows >= self.total
+    }
+}
+
+pub(crate) struct ProgressTracker {
+    started: Instant,
+    last: Instant,
+    rows: u64,
+    total: u64,
+    bytes: u64,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(total: usize) -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            last: now,
+            rows: 0,
+            total: total as u64,
+            bytes: 0,
+        }
+    }
+
+    pub(crate) fn chunk_done(&mut self, rows: u64, bytes: u64) -> Progress {
+        let now = Instant::now();
+        let chunk_elapsed = now - self.last;
+        self.last = now;
+        self.rows += rows;
+        self.bytes += bytes;
+        Progress {
+            rows: self.rows,
+            total: self.total,
+            bytes: self.bytes,
+            elapsed: now - self.started,
+            chunk_rows: rows,
+            chunk_elapsed,
+        }
+    }
+}
+
+/// The size of the values in the binary format
+/// (the values failed to encode are not counted).
+pub(crate) fn encoded_size<T, const N: usize>(rows: &[T]) -> u64
+where
+    T: Table<N> + InsertableValues<N>,
+{
+    let columns = T::columns();
+    let mut buf = BytesMut::new();
+    for row in rows {
+        for (value, col) in row.encoded_values().iter().zip(&columns) {
+            // the errors are reported by the insert itself
+            let _ = value.as_sql().to_sql_checked(col.db_type(), &mut buf);
+        }
+    }
+    buf.len() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::gen_table;
+
+    gen_table!(
+        struct Sample("progress_samples") {
+            id: i32 = Type::INT4,
+            label: String = Type::TEXT,
+        }
+    );
+
+    #[test]
+    fn cancel_flag() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let options = BatchOptions::new(10).cancel_on(Arc::clone(&flag));
+        assert!(!options.is_cancelled());
+        flag.store(true, Ordering::SeqCst);
+        assert!(options.is_cancelled());
+
+        let token = CancelToken::new();
+        let options = BatchOptions::new(10).cancel_on(token.clone());
+        token.cancel();
+        assert!(options.is_cancelled());
+        assert!(!BatchOptions::new(10).is_cancelled());
+    }
+
+    #[test]
+    fn tracking() {
+        let rows = [(1, "a"), (2, "bcd")].map(|(id, label)| Sample {
+            id,
+            label: label.into(),
+        });
+        assert_eq!(encoded_size::<Sample, 2>(&rows), 4 + 1 + 4 + 3);
+
+        let mut tracker = ProgressTracker::new(3);
+        let first = tracker.chunk_done(2, 12);
+        assert_eq!((first.rows(), first.bytes()), (2, 12));
+        assert!(!first.is_done());
+        let second = tracker.chunk_done(1, 5);
+        assert_eq!((second.rows(), second.total(), second.bytes()), (3, 3, 17));
+        assert!(second.is_done());
+        assert!(second.elapsed() >= first.elapsed());
+    }
+}
@@ -0,0 +1,172 @@
+use postgres::{Error, Row};
+
+use crate::{
+    expr::{param, Expr},
+    query::{Order, SelectOptions},
+    table::{InsertableValues, Table},
+};
+
+/// The table storing the last processed position of every projection.
+pub const PROJECTIONS_TABLE: &str = "pg_helper_projections";
+
+/// The batch of the source rows transformed by the [`Projection`].
+pub(crate) struct ProjectedBatch<Dst> {
+    pub(crate) derived: Vec<Dst>,
+    pub(crate) processed: usize,
+    /// The position to save as the progress of the projection.
+    pub(crate) last_position: i64,
+}
+
+/// Derived (read-model) table maintained from the rows appended to the source table.
+///
+/// The changes are read in the order of the increasing integer `cursor` column
+/// (e.g. `BIGSERIAL` primary key of the events table), so the source table
+/// is expected to be append-only. The position of the last processed row
+/// is saved in the [`PROJECTIONS_TABLE`] in the same transaction
+/// as the derived rows, so no source row is projected twice.
+///
+/// # Single writer
+///
+/// The source rows should be appended by a single writer at a time
+/// (e.g. under the lock). The sequence values are assigned before the commit,
+/// so the row with the lower cursor committed after the higher one
+/// has been processed by the projection is skipped forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Projection {
+    name: String,
+    cursor: String,
+    batch_size: u64,
+}
+
+impl Projection {
+    pub fn new(name: impl AsRef<str>, cursor: impl AsRef<str>) -> Self {
+        Self {
+            name: name.as_ref().to_owned(),
+            cursor: cursor.as_ref().to_owned(),
+            batch_size: 1000,
+        }
+    }
+
+    /// The maximum number of the source rows processed in a single transaction.
+    ///
+    /// # Panics
+    ///
+    /// If the `batch_size` is zero.
+    pub fn batch_size(mut self, batch_size: u64) -> Self {
+        assert!(batch_size > 0, "Batch size should be positive");
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn create_progress_table_sql() -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (name text PRIMARY KEY, position int8 NOT NULL)",
+            PROJECTIONS_TABLE
+        )
+    }
+
+    /// Register the projection (if not yet) and lock its progress row
+    /// to prevent the concurrent processing of the same batch.
+    pub(crate) fn lock_position_sql() -> [String; 2] {
+        [
+            format!(
+                "INSERT INTO {} (name, position) VALUES ($1, 0) ON CONFLICT (name) DO NOTHING",
+                PROJECTIONS_TABLE
+            ),
+            format!(
+                "SELECT position FROM {} WHERE name = $1 FOR UPDATE",
+                PROJECTIONS_TABLE
+            ),
+        ]
+    }
+
+    pub(crate) fn save_position_sql() -> String {
+        format!(
+            "UPDATE {} SET position = $2 WHERE name = $1",
+            PROJECTIONS_TABLE
+        )
+    }
+
+    /// Fails if the source table has no cursor column.
+    pub(crate) fn select_changes_sql<T, const N: usize>(&self) -> Result<String, crate::Error>
+    where
+        T: Table<N>,
+    {
+        let condition = Expr::col(&self.cursor).gt(param(1)).to_sql(&T::columns())?;
+        let options = SelectOptions::new()
+            .order_by(&self.cursor, Order::Asc)
+            .limit(self.batch_size);
+        T::select_sql(Some(&condition), &options)
+    }
+
+    fn position(&self, row: &Row) -> Result<i64, Error> {
+        row.try_get::<_, i64>(self.cursor.as_str())
+            .or_else(|_| row.try_get::<_, i32>(self.cursor.as_str()).map(i64::from))
+    }
+
+    /// Transform the changes selected with the [`Self::select_changes_sql`]
+    /// (`None` when there are no changes).
+    pub(crate) fn transform<Src, Dst, F, const N: usize, const M: usize>(
+        &self,
+        rows: Vec<Row>,
+        mut transform: F,
+    ) -> Result<Option<ProjectedBatch<Dst>>, Error>
+    where
+        Src: Table<N> + TryFrom<Row, Error = Error>,
+        Dst: InsertableValues<M>,
+        F: FnMut(Src) -> Option<Dst>,
+    {
+        let Some(last) = rows.last() else {
+            return Ok(None);
+        };
+        let last_position = self.position(last)?;
+        let processed = rows.len();
+
+        let derived = rows
+            .into_iter()
+            .map(Src::try_from)
+            .filter_map(|src| src.map(&mut transform).transpose())
+            .collect::<Result<_, _>>()?;
+        Ok(Some(ProjectedBatch {
+            derived,
+            processed,
+            last_position,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::gen_table;
+
+    gen_table!(
+        struct Event("events") {
+            id: i64 = Type::INT8; [primary_key()],
+            payload: String = Type::TEXT,
+        }
+    );
+
+    #[test]
+    fn select_changes() {
+        let projection = Projection::new("totals", "id").batch_size(50);
+        assert_eq!(
+            projection.select_changes_sql::<Event, 2>().unwrap(),
+            "SELECT * FROM events WHERE id > $1 ORDER BY id ASC LIMIT 50"
+        );
+    }
+
+    #[test]
+    fn unknown_cursor() {
+        let err = Projection::new("totals", "seq")
+            .select_changes_sql::<Event, 2>()
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::UnknownColumn { column, .. } if column == "seq"));
+    }
+}
@@ -0,0 +1,424 @@
+use std::fmt::{self, Display, Write as _};
+
+use itertools::Itertools as _;
+
+use crate::{column::Column, error::Error};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            Order::Asc => "ASC",
+            Order::Desc => "DESC",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+/// Placement of the NULL values.
+/// By default, they are considered larger than any other value,
+/// i.e. come last for the [`Order::Asc`] and first for the [`Order::Desc`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+impl Display for NullsOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            NullsOrder::First => "NULLS FIRST",
+            NullsOrder::Last => "NULLS LAST",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderBy {
+    column: String,
+    order: Order,
+    nulls: Option<NullsOrder>,
+    lower: bool,
+    collation: Option<String>,
+}
+
+impl OrderBy {
+    pub fn new(column: impl AsRef<str>, order: Order) -> Self {
+        Self {
+            column: column.as_ref().to_owned(),
+            order,
+            nulls: None,
+            lower: false,
+            collation: None,
+        }
+    }
+
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    pub const fn nulls(mut self, nulls: NullsOrder) -> Self {
+        self.nulls = Some(nulls);
+        self
+    }
+
+    /// Case-insensitive ordering by the `lower(column)`.
+    pub const fn lower(mut self) -> Self {
+        self.lower = true;
+        self
+    }
+
+    /// Order using the given collation (e.g. `C` or `und-x-icu`).
+    pub fn collate(mut self, collation: impl AsRef<str>) -> Self {
+        self.collation = Some(collation.as_ref().to_owned());
+        self
+    }
+}
+
+impl Display for OrderBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.lower {
+            write!(f, "lower({})", self.column)?;
+        } else {
+            write!(f, "{}", self.column)?;
+        }
+        if let Some(collation) = &self.collation {
+            write!(f, " COLLATE \"{}\"", collation.replace('"', "\"\""))?;
+        }
+        write!(f, " {}", self.order)?;
+        if let Some(nulls) = self.nulls {
+            write!(f, " {}", nulls)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Distinct {
+    /// Remove all the duplicate rows.
+    Rows,
+    /// Keep only the first row of each set of rows where the given columns are equal.
+    On(Vec<String>),
+}
+
+/// Ensure the column name is one of the `columns`.
+pub(crate) fn check_column(
+    columns: &[Column],
+    name: &str,
+    purpose: &'static str,
+) -> Result<(), Error> {
+    if columns.iter().any(|col| col.name() == name) {
+        return Ok(());
+    }
+    Err(Error::UnknownColumn {
+        column: name.to_owned(),
+        purpose,
+    })
+}
+
+/// How to hide the values of the [sensitive][crate::ColumnBuilder::sensitive] columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Redaction {
+    /// Do not read the value at all,
+    /// so the field is decoded into the `Default` (`0`, `""`, `false`, `None`, etc).
+    Omit,
+    /// Replace the values of the text columns with the mask, e.g. `"***"`
+    /// (the other columns are [omitted][Self::Omit]).
+    Mask(String),
+}
+
+/// Additional clauses of a _SELECT_ statement.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectOptions {
+    distinct: Option<Distinct>,
+    order_by: Vec<OrderBy>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    redaction: Option<Redaction>,
+}
+
+impl SelectOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn distinct(mut self) -> Self {
+        self.distinct = Some(Distinct::Rows);
+        self
+    }
+
+    pub fn distinct_on(mut self, columns: &[impl AsRef<str>]) -> Self {
+        let columns = columns.iter().map(|c| c.as_ref().to_owned()).collect();
+        self.distinct = Some(Distinct::On(columns));
+        self
+    }
+
+    pub fn order_by(mut self, column: impl AsRef<str>, order: Order) -> Self {
+        self.order_by.push(OrderBy::new(column, order));
+        self
+    }
+
+    /// Order with the additional options (NULLs placement, collation, etc).
+    pub fn order_by_key(mut self, key: OrderBy) -> Self {
+        self.order_by.push(key);
+        self
+    }
+
+    pub const fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub const fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Hide the values of the sensitive columns,
+    /// e.g. for the logging or the support tools.
+    pub fn redacted(mut self, redaction: Redaction) -> Self {
+        self.redaction = Some(redaction);
+        self
+    }
+
+    /// Render the select list: all the `columns` with the sensitive ones redacted.
+    pub(crate) fn select_list_sql(&self, columns: &[Column]) -> String {
+        match &self.redaction {
+            Some(redaction) if columns.iter().any(Column::is_sensitive) => columns
+                .iter()
+                .map(|col| col.redacted_sql(redaction))
+                .join(", "),
+            _ => "*".into(),
+        }
+    }
+
+    /// Render the _DISTINCT_ clause as an SQL prefix of a select list
+    /// (with a trailing space if not empty).
+    ///
+    /// Fails if any of the _DISTINCT ON_ columns is not one of the `columns`.
+    pub(crate) fn distinct_sql(&self, columns: &[Column]) -> Result<String, Error> {
+        Ok(match &self.distinct {
+            None => String::new(),
+            Some(Distinct::Rows) => "DISTINCT ".into(),
+            Some(Distinct::On(on)) => {
+                for name in on {
+                    check_column(columns, name, "distinct on")?;
+                }
+                format!("DISTINCT ON ({}) ", on.join(", "))
+            }
+        })
+    }
+
+    /// Render the _ORDER BY_ clause alone (empty if no ordering specified).
+    ///
+    /// Fails if any of the ordering columns is not one of the `columns`.
+    pub(crate) fn order_by_sql(&self, columns: &[Column]) -> Result<String, Error> {
+        if self.order_by.is_empty() {
+            return Ok(String::new());
+        }
+        for order_by in &self.order_by {
+            check_column(columns, order_by.column(), "order by")?;
+        }
+        Ok(format!("ORDER BY {}", self.order_by.iter().join(", ")))
+    }
+
+    /// Render the clauses following the _WHERE_ condition as an SQL suffix
+    /// (with a leading space if not empty).
+    ///
+    /// Fails if any of the ordering columns is not one of the `columns`.
+    pub(crate) fn to_sql(&self, columns: &[Column]) -> Result<String, Error> {
+        let mut sql = String::new();
+        if !self.order_by.is_empty() {
+            write!(sql, " {}", self.order_by_sql(columns)?).unwrap();
+        }
+        if let Some(limit) = self.limit {
+            write!(sql, " LIMIT {}", limit).unwrap();
+        }
+        if let Some(offset) = self.offset {
+            write!(sql, " OFFSET {}", offset).unwrap();
+        }
+        Ok(sql)
+    }
+}
+
+/// SQL template registered with the [`Table::custom_queries`][crate::Table::custom_queries].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedQuery {
+    name: &'static str,
+    sql: String,
+    params: usize,
+}
+
+impl NamedQuery {
+    /// # Panics
+    ///
+    /// If the placeholders of the `sql` are not exactly `$1..=$params`.
+    pub fn new(name: &'static str, sql: impl AsRef<str>, params: usize) -> Self {
+        let sql = sql.as_ref().to_owned();
+        let used = placeholders(&sql);
+        assert!(
+            used.len() == params && used.iter().copied().eq(1..=params),
+            "Query {:?} should use exactly {} parameter(s), found: {:?}",
+            name,
+            params,
+            used
+        );
+        Self { name, sql, params }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    pub const fn params(&self) -> usize {
+        self.params
+    }
+
+    /// Fails unless the query is run with exactly the declared number of the parameters.
+    pub(crate) fn check_params(&self, table: &str, count: usize) -> Result<(), Error> {
+        if count == self.params {
+            return Ok(());
+        }
+        Err(Error::NamedQuery {
+            table: table.to_owned(),
+            name: self.name.to_owned(),
+            reason: format!("expected {} parameter(s), got {}", self.params, count),
+        })
+    }
+}
+
+/// Sorted unique indices of the positional parameters (`$n`)
+/// outside of the string literals, quoted identifiers, comments
+/// and dollar-quoted bodies.
+fn placeholders(sql: &str) -> Vec<usize> {
+    let mut indices = Vec::new();
+    let mut rest = sql;
+    while let Some(ch) = rest.chars().next() {
+        let after = &rest[ch.len_utf8()..];
+        rest = match ch {
+            '\'' => skip_past(after, "'"),
+            '"' => skip_past(after, "\""),
+            '-' if after.starts_with('-') => skip_past(after, "\n"),
+            '/' if after.starts_with('*') => skip_comment(&after[1..]),
+            '$' => {
+                let digits =
+                    after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                if digits > 0 {
+                    if let Ok(index) = after[..digits].parse() {
+                        indices.push(index);
+                    }
+                    &after[digits..]
+                } else {
+                    let tag = after.len()
+                        - after
+                            .trim_start_matches(|c: char| c.is_alphanumeric() || c == '_')
+                            .len();
+                    if after[tag..].starts_with('$') {
+                        skip_past(&after[tag + 1..], &rest[..tag + 2])
+                    } else {
+                        after
+                    }
+                }
+            }
+            _ => after,
+        };
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+/// The rest of the `sql` after the first `end` (nothing if there is no such).
+fn skip_past<'a>(sql: &'a str, end: &str) -> &'a str {
+    sql.find(end).map_or("", |pos| &sql[pos + end.len()..])
+}
+
+/// The rest of the `sql` after the end of the (possibly nested) block comment.
+fn skip_comment(mut sql: &str) -> &str {
+    let mut depth = 1;
+    while depth > 0 {
+        let Some(close) = sql.find("*/") else {
+            return "";
+        };
+        match sql.find("/*") {
+            Some(open) if open < close => {
+                depth += 1;
+                sql = &sql[open + 2..];
+            }
+            _ => {
+                depth -= 1;
+                sql = &sql[close + 2..];
+            }
+        }
+    }
+    sql
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_by_options() {
+        let key = OrderBy::new("name", Order::Desc)
+            .lower()
+            .collate("und-x-icu")
+            .nulls(NullsOrder::Last);
+        assert_eq!(
+            key.to_string(),
+            "lower(name) COLLATE \"und-x-icu\" DESC NULLS LAST"
+        );
+        assert_eq!(
+            OrderBy::new("id", Order::Asc)
+                .nulls(NullsOrder::First)
+                .to_string(),
+            "id ASC NULLS FIRST"
+        );
+    }
+
+    #[test]
+    fn named_query_placeholders() {
+        let query = NamedQuery::new(
+            "recent_by_customer",
+            "SELECT * FROM buys WHERE customer_id = $1 AND note <> '$3' \
+            ORDER BY buy_id DESC LIMIT $2",
+            2,
+        );
+        assert_eq!(query.params(), 2);
+        assert_eq!(placeholders("SELECT $1, $1, $10, \"$2\""), [1, 10]);
+    }
+
+    #[test]
+    fn placeholders_in_comments() {
+        assert_eq!(placeholders("SELECT $1 -- or $2\n, $3"), [1, 3]);
+        assert_eq!(placeholders("SELECT $1 -- trailing $2"), [1]);
+        assert_eq!(placeholders("SELECT /* $2 */ $1"), [1]);
+        assert_eq!(placeholders("SELECT /* a /* $2 */ $3 */ $1"), [1]);
+        assert_eq!(placeholders("SELECT $1 - $2 / $3"), [1, 2, 3]);
+    }
+
+    #[test]
+    fn placeholders_in_dollar_quotes() {
+        assert_eq!(placeholders("SELECT $$ $2 $$, $1"), [1]);
+        assert_eq!(placeholders("SELECT $body$ $2 $$ $3 $body$, $1"), [1]);
+        assert!(placeholders("SELECT $a$ $2 $a$ || $b_1$ $3 $b_1$").is_empty());
+        assert_eq!(placeholders("SELECT $1::text, $2"), [1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Query \"gap\" should use exactly 2 parameter(s), found: [1, 3]")]
+    fn named_query_with_gap() {
+        let _ = NamedQuery::new("gap", "SELECT $1, $3", 2);
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use itertools::Itertools as _;
+use log::debug;
+
+use crate::{
+    error::{Context as _, Error, Operation},
+    ext::SyncClient,
+    table::Table,
+};
+
+/// The rows differing between two copies of the table
+/// identified with the text representation of their primary keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowsDiff {
+    missing: Vec<String>,
+    extra: Vec<String>,
+    mismatched: Vec<String>,
+    compared: u64,
+    chunks: usize,
+}
+
+impl RowsDiff {
+    /// The rows of the source absent from the target.
+    pub fn missing(&self) -> &[String] {
+        &self.missing
+    }
+
+    /// The rows of the target absent from the source.
+    pub fn extra(&self) -> &[String] {
+        &self.extra
+    }
+
+    /// The rows with the same key but different values.
+    pub fn mismatched(&self) -> &[String] {
+        &self.mismatched
+    }
+
+    /// The number of the distinct keys seen in both tables.
+    pub fn compared(&self) -> u64 {
+        self.compared
+    }
+
+    pub fn chunks(&self) -> usize {
+        self.chunks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+struct ChunkQueries {
+    /// The key closing the chunk of the `$2` rows after the `$1` key.
+    upper_bound: String,
+    /// The keys and the hashes of the rows in the `($1, $2]` range.
+    rows: String,
+}
+
+impl ChunkQueries {
+    fn new<T, const N: usize>() -> Self
+    where
+        T: Table<N>,
+    {
+        let columns = T::columns();
+        let mut keys = columns.iter().filter(|col| col.is_primary_key());
+        let (Some(key), None) = (keys.next(), keys.next()) else {
+            panic!(
+                "Table {} should have a single primary key column",
+                T::name()
+            );
+        };
+        let (key, ty) = (key.name(), key.db_type().name());
+        let after = format!("($1::text IS NULL OR {} > $1::text::{})", key, ty);
+
+        let upper_bound = format!(
+            "SELECT {0}::text FROM {1} WHERE {2} ORDER BY {0} OFFSET $2::int8 - 1 LIMIT 1",
+            key,
+            T::name(),
+            after
+        );
+        let rows = format!(
+            "SELECT {0}::text, md5(ROW({1})::text) FROM {2} \
+            WHERE {3} AND ($2::text IS NULL OR {0} <= $2::text::{4}) ORDER BY {0}",
+            key,
+            columns.iter().map(|col| col.name()).join(", "),
+            T::name(),
+            after,
+            ty
+        );
+        Self { upper_bound, rows }
+    }
+}
+
+/// Compare the rows of the table in two databases chunk by chunk
+/// (at most `chunk_size` rows of each side are loaded at once),
+/// e.g. to verify the copy made by the migration or the replication.
+///
+/// Only the keys and the hashes of the rows are transferred.
+///
+/// # Errors
+///
+/// If any of the queries fails.
+///
+/// # Panics
+///
+/// If the table has no or multiple primary key columns or the `chunk_size` is zero.
+pub fn diff_tables<T, S, D, const N: usize>(
+    source: &mut S,
+    target: &mut D,
+    chunk_size: u32,
+) -> Result<RowsDiff, Error>
+where
+    T: Table<N>,
+    S: SyncClient,
+    D: SyncClient,
+{
+    assert!(chunk_size > 0, "The chunk size should be positive");
+    let queries = ChunkQueries::new::<T, N>();
+    let chunk_size = i64::from(chunk_size);
+    let mut diff = RowsDiff::default();
+    let mut after: Option<String> = None;
+
+    loop {
+        // the chunk ends at the closest of the bounds, so neither side exceeds the chunk size
+        let mut upper: Option<String> = None;
+        for client in [&mut *source as &mut dyn SyncClient, &mut *target] {
+            let bound = client
+                .query_opt(&queries.upper_bound, &[&after, &chunk_size])
+                .and_then(|row| row.map(|row| row.try_get(0)).transpose())
+                .context(T::name(), Operation::Select, &queries.upper_bound)?;
+            upper = closest::<T, N>(client, upper, bound)?;
+        }
+
+        let source_rows = chunk_rows::<T, N>(source, &queries.rows, &after, &upper)?;
+        let mut target_rows: HashMap<_, _> =
+            chunk_rows::<T, N>(target, &queries.rows, &after, &upper)?
+                .into_iter()
+                .collect();
+        debug!(
+            "Comparing the chunk of table {} after {:?}: {} vs {} rows",
+            T::name(),
+            after,
+            source_rows.len(),
+            target_rows.len()
+        );
+        diff.chunks += 1;
+
+        for (key, hash) in source_rows {
+            diff.compared += 1;
+            match target_rows.remove(&key) {
+                None => diff.missing.push(key),
+                Some(target_hash) if target_hash != hash => diff.mismatched.push(key),
+                Some(_) => {}
+            }
+        }
+        for (key, _) in target_rows {
+            diff.compared += 1;
+            diff.extra.push(key);
+        }
+
+        match upper {
+            Some(upper) => after = Some(upper),
+            None => break,
+        }
+    }
+    diff.extra.sort();
+    Ok(diff)
+}
+
+/// The smaller of the keys (compared with the type of the key column).
+fn closest<T, const N: usize>(
+    client: &mut dyn SyncClient,
+    current: Option<String>,
+    bound: Option<String>,
+) -> Result<Option<String>, Error>
+where
+    T: Table<N>,
+{
+    let (Some(current), Some(bound)) = (&current, &bound) else {
+        return Ok(current.or(bound));
+    };
+    let ty = T::columns()
+        .into_iter()
+        .find(|col| col.is_primary_key())
+        .map(|col| col.db_type().name().to_owned())
+        .unwrap_or_default();
+    let query = format!("SELECT least($1::text::{0}, $2::text::{0})::text", ty);
+    let closest = client
+        .query_one(&query, &[current, bound])
+        .and_then(|row| row.try_get(0))
+        .context(T::name(), Operation::Select, &query)?;
+    Ok(Some(closest))
+}
+
+fn chunk_rows<T, const N: usize>(
+    client: &mut dyn SyncClient,
+    query: &str,
+    after: &Option<String>,
+    upper: &Option<String>,
+) -> Result<Vec<(String, String)>, Error>
+where
+    T: Table<N>,
+{
+    client
+        .query(query, &[after, upper])
+        .and_then(|rows| {
+            rows.iter()
+                .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+                .collect()
+        })
+        .context(T::name(), Operation::Select, query)
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres::Client;
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client, PgTableExtension as _};
+
+    gen_table!(
+        struct Item("reconcile_items") {
+            id: i32 = Type::INT4; [primary_key()],
+            name: String = Type::TEXT,
+        }
+    );
+
+    fn connect(schema: &str) -> Option<Client> {
+        let mut client = get_client()?;
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {0} CASCADE; CREATE SCHEMA {0}; SET search_path = {0}",
+                schema
+            ))
+            .unwrap();
+        client.create_table::<Item, 2>().unwrap();
+        Some(client)
+    }
+
+    #[test]
+    fn chunked() {
+        let (Some(mut source), Some(mut target)) =
+            (connect("reconcile_source"), connect("reconcile_target"))
+        else {
+            return;
+        };
+        source
+            .batch_execute(
+                "INSERT INTO reconcile_items SELECT i, 'item ' || i FROM generate_series(1, 25) i",
+            )
+            .unwrap();
+        target
+            .batch_execute(
+                "INSERT INTO reconcile_items SELECT i, 'item ' || i FROM generate_series(1, 25) i \
+                WHERE i NOT IN (3, 17); \
+                UPDATE reconcile_items SET name = 'changed' WHERE id = 10; \
+                INSERT INTO reconcile_items VALUES (26, 'new'), (100, 'far')",
+            )
+            .unwrap();
+
+        let diff = diff_tables::<Item, _, _, 2>(&mut source, &mut target, 4).unwrap();
+        assert_eq!(diff.missing(), ["3", "17"]);
+        assert_eq!(diff.extra(), ["100", "26"]);
+        assert_eq!(diff.mismatched(), ["10"]);
+        assert_eq!(diff.compared(), 27);
+        assert!(diff.chunks() > 1, "{:?}", diff);
+
+        let same = diff_tables::<Item, _, _, 2>(&mut source, &mut source_copy(), 10).unwrap();
+        assert!(same.is_empty());
+        assert_eq!(same.compared(), 25);
+
+        for (mut client, schema) in [(source, "reconcile_source"), (target, "reconcile_target")] {
+            client
+                .batch_execute(&format!("DROP SCHEMA {} CASCADE", schema))
+                .unwrap();
+        }
+    }
+
+    fn source_copy() -> Client {
+        let mut client = get_client().unwrap();
+        client
+            .batch_execute("SET search_path = reconcile_source")
+            .unwrap();
+        client
+    }
+
+    #[test]
+    #[should_panic(expected = "Table reconcile_pairs should have a single primary key column")]
+    fn composite_key() {
+        gen_table!(
+            struct Pair("reconcile_pairs") {
+                a: i32 = Type::INT4; [primary_key()],
+                b: i32 = Type::INT4; [primary_key()],
+            }
+        );
+
+        let _ = ChunkQueries::new::<Pair, 2>();
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+
+use postgres::{Error, Row};
+use postgres_types::ToSql;
+
+use crate::ext::{private, SyncClient};
+
+/// The statement captured by the [`RecordingClient`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedStatement {
+    pub sql: String,
+    /// The `Debug` representations of the parameters.
+    pub params: Vec<String>,
+}
+
+impl fmt::Display for RecordedStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.sql.trim_end_matches(';'))?;
+        if !self.params.is_empty() {
+            write!(f, "; -- {}", self.params.join(", "))
+        } else {
+            write!(f, ";")
+        }
+    }
+}
+
+/// The client capturing the statements instead of executing them
+/// (the dry-run of the [`PgTableExtension`][crate::PgTableExtension] methods,
+/// e.g. for the golden-file tests or to review the migration before applying it).
+///
+/// Nothing is sent to the database: the statements affect no rows
+/// and the queries return no rows.
+///
+/// # Panics
+///
+/// The methods requiring exactly one row to be returned
+/// (e.g. the `query_scalar` or the `get_or_create`) panic.
+#[derive(Debug, Default)]
+pub struct RecordingClient {
+    statements: Vec<RecordedStatement>,
+}
+
+impl RecordingClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn statements(&self) -> &[RecordedStatement] {
+        &self.statements
+    }
+
+    /// Take the recorded statements leaving the client empty.
+    pub fn take(&mut self) -> Vec<RecordedStatement> {
+        std::mem::take(&mut self.statements)
+    }
+
+    /// The recorded statements one per line with the parameters in the comments.
+    pub fn script(&self) -> String {
+        self.statements
+            .iter()
+            .map(|statement| format!("{}\n", statement))
+            .collect()
+    }
+
+    fn record(&mut self, query: &str, params: &[&(dyn ToSql + Sync)]) {
+        self.statements.push(RecordedStatement {
+            sql: query.to_owned(),
+            params: params.iter().map(|param| format!("{:?}", param)).collect(),
+        });
+    }
+}
+
+impl private::Sealed for RecordingClient {}
+
+impl SyncClient for RecordingClient {
+    fn execute(&mut self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        self.record(query, params);
+        Ok(0)
+    }
+
+    fn query(&mut self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error> {
+        self.record(query, params);
+        Ok(vec![])
+    }
+
+    fn query_one(&mut self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Row, Error> {
+        self.record(query, params);
+        panic!("No row is returned in the dry-run for the query: {}", query)
+    }
+
+    fn query_opt(
+        &mut self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error> {
+        self.record(query, params);
+        Ok(None)
+    }
+
+    fn batch_execute(&mut self, query: &str) -> Result<(), Error> {
+        self.record(query, &[]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::{FromSql, Type};
+
+    use super::*;
+    use crate::{enum_type, gen_table, PgTableExtension as _};
+
+    #[derive(Debug, ToSql, FromSql)]
+    #[postgres(name = "pet_kind")]
+    enum PetKind {
+        #[postgres(name = "cat")]
+        Cat,
+        #[postgres(name = "dog")]
+        Dog,
+    }
+
+    gen_table!(
+        struct Pet("pets") {
+            id: i32 = Type::INT4; [primary_key()],
+            kind: PetKind = enum_type("pet_kind", &["cat", "dog"]); [index()],
+            name: Option<String> = Type::TEXT; [nullable()],
+        }
+    );
+
+    #[test]
+    fn dry_run() {
+        let mut client = RecordingClient::new();
+        client.create_table::<Pet, 3>().unwrap();
+        let pets = [
+            Pet {
+                id: 1,
+                kind: PetKind::Cat,
+                name: Some("Tom".into()),
+            },
+            Pet {
+                id: 2,
+                kind: PetKind::Dog,
+                name: None,
+            },
+        ];
+        assert_eq!(client.insert_rows(&pets).unwrap(), 0);
+        assert!(client.select_all::<Pet, 3>().unwrap().is_empty());
+
+        assert_eq!(
+            client.script(),
+            "SELECT oid FROM pg_catalog.pg_type WHERE typname = $1; -- \"pet_kind\"
+CREATE TYPE \"pet_kind\" AS ENUM ('cat', 'dog');
+CREATE TABLE IF NOT EXISTS pets \
+(id int4 NOT NULL UNIQUE PRIMARY KEY, kind pet_kind NOT NULL, name text NULL);
+CREATE INDEX IF NOT EXISTS kind_idx_pets ON pets USING btree (kind);
+INSERT INTO pets (id, kind, name) VALUES ($1, $2, $3), ($4, $5, $6); \
+-- 1, Cat, Some(\"Tom\"), 2, Dog, None
+SELECT * FROM pets;
+"
+        );
+        assert_eq!(client.take().len(), 6);
+        assert!(client.statements().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "No row is returned in the dry-run for the query: SELECT 1")]
+    fn single_row() {
+        let _: Result<i32, _> = RecordingClient::new().query_scalar("SELECT 1", &[]);
+    }
+}
@@ -0,0 +1,137 @@
+use postgres::Client;
+
+use crate::{
+    diff::TableDef, error::Error, introspect, script::create_tables, verify::SchemaReport,
+};
+
+/// The table submitted with the `gen_table!` (see the [`Schema::global`]).
+#[doc(hidden)]
+pub struct Registration(fn() -> TableDef);
+
+impl Registration {
+    pub const fn new(def: fn() -> TableDef) -> Self {
+        Self(def)
+    }
+}
+
+inventory::collect!(Registration);
+
+/// The set of the tables managed together.
+pub struct Schema {
+    tables: Vec<TableDef>,
+}
+
+impl Schema {
+    pub fn new(tables: Vec<TableDef>) -> Self {
+        Self { tables }
+    }
+
+    /// All the tables of the binary defined with the `gen_table!`
+    /// (except the ones marked with the `registered = false`) ordered by their names.
+    ///
+    /// The definitions are collected on every call,
+    /// so the [`TableConfig`][crate::TableConfig] installed beforehand is respected.
+    pub fn global() -> Self {
+        let mut tables: Vec<_> = inventory::iter::<Registration>
+            .into_iter()
+            .map(|registration| (registration.0)())
+            .collect();
+        tables.sort_by(|a, b| a.name().cmp(b.name()));
+        tables.dedup_by(|a, b| a.name() == b.name());
+        Self { tables }
+    }
+
+    pub fn tables(&self) -> &[TableDef] {
+        &self.tables
+    }
+
+    pub fn table(&self, name: &str) -> Option<&TableDef> {
+        self.tables.iter().find(|table| table.name() == name)
+    }
+
+    /// Create the missing schemas, types, tables and indices
+    /// in the order of the foreign keys.
+    ///
+    /// # Errors
+    ///
+    /// If any of the statements fails.
+    ///
+    /// # Panics
+    ///
+    /// If the tables reference each other in a cycle.
+    pub fn create_all(&self, client: &mut Client) -> Result<(), Error> {
+        create_tables(client, &self.tables)
+    }
+
+    /// Compare every table with the live schema.
+    ///
+    /// # Errors
+    ///
+    /// If the live schema cannot be queried.
+    pub fn verify_schema_all(&self, client: &mut Client) -> Result<Vec<SchemaReport>, Error> {
+        self.tables
+            .iter()
+            .map(|table| {
+                let live = introspect::table(client, table.name())?;
+                Ok(SchemaReport::compare_def(table, live.as_ref()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client};
+
+    gen_table!(
+        struct Widget("registry_widgets") {
+            id: i32 = Type::INT4; [primary_key()],
+            name: String = Type::TEXT; [index()],
+        }
+    );
+
+    gen_table!(
+        struct Part("registry_parts") {
+            id: i32 = Type::INT4; [primary_key()],
+            widget_id: i32 = Type::INT4; [foreign_key("registry_widgets", "id")],
+        }
+    );
+
+    #[test]
+    fn registered_tables() {
+        let schema = Schema::global();
+        assert!(schema.table("registry_widgets").is_some());
+        assert!(schema.table("registry_parts").is_some());
+        assert!(schema.table("pg_helper_self_check").is_none());
+
+        let names: Vec<_> = schema.tables().iter().map(TableDef::name).collect();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn create_and_verify() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS registry_parts, registry_widgets")
+            .unwrap();
+
+        // the referencing table goes first to check the ordering
+        let schema = Schema::new(vec![TableDef::of::<Part, 2>(), TableDef::of::<Widget, 2>()]);
+        schema.create_all(&mut client).unwrap();
+        let reports = schema.verify_schema_all(&mut client).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(SchemaReport::is_ok));
+
+        client
+            .batch_execute("DROP TABLE registry_parts, registry_widgets")
+            .unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+use itertools::Itertools as _;
+use postgres::Client;
+
+use crate::{diff::TableDef, error::Error, ext::create_from_def, type_helpers::quote_ident};
+
+/// The full DDL of the tables (their schemas, types, the tables themselves and the indices)
+/// in the order of the foreign keys, e.g. to feed it to the external migration tools.
+///
+/// # Panics
+///
+/// If the tables reference each other in a cycle.
+pub fn schema_script(tables: &[TableDef]) -> String {
+    let mut statements: Vec<String> = tables
+        .iter()
+        .map(TableDef::schema)
+        .filter(|&schema| schema != "public")
+        .unique()
+        .map(|schema| format!("CREATE SCHEMA IF NOT EXISTS {}", quote_ident(schema)))
+        .collect();
+    for ty in tables.iter().flat_map(TableDef::types) {
+        let sql = ty.create_sql();
+        if !statements.iter().any(|statement| statement == sql) {
+            statements.push(sql.to_owned());
+        }
+    }
+    for table in dependency_order(tables) {
+        statements.extend(table.create_script());
+    }
+
+    statements
+        .into_iter()
+        .map(|sql| format!("{};\n", sql))
+        .collect()
+}
+
+/// Create the missing schemas and types and then the tables with their indices
+/// in the order of the foreign keys.
+///
+/// # Errors
+///
+/// If any of the statements fails.
+///
+/// # Panics
+///
+/// If the tables reference each other in a cycle.
+pub fn create_tables(client: &mut Client, tables: &[TableDef]) -> Result<(), Error> {
+    for table in dependency_order(tables) {
+        create_from_def(client, table)?;
+    }
+    Ok(())
+}
+
+/// The referenced tables go before the referencing ones.
+///
+/// # Panics
+///
+/// If the tables reference each other in a cycle.
+pub(crate) fn dependency_order(tables: &[TableDef]) -> Vec<&TableDef> {
+    let mut pending: Vec<_> = tables.iter().collect();
+    let mut ordered: Vec<&TableDef> = vec![];
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|table| {
+            table.dependencies().iter().all(|dep| {
+                ordered.iter().any(|t| t.name() == dep) || !tables.iter().any(|t| t.name() == dep)
+            })
+        });
+        let Some(ready) = ready else {
+            let names: Vec<_> = pending.iter().map(|table| table.name()).collect();
+            panic!("Cyclic foreign keys between the tables {:?}", names);
+        };
+        ordered.push(pending.remove(ready));
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{enum_type, gen_table, test_helpers::get_client, ForeignKeyConstraint, Table};
+
+    fn status_type() -> Type {
+        enum_type("shipment_status", &["pending", "delivered"])
+    }
+
+    gen_table!(
+        struct Warehouse("warehouses") {
+            id: i32 = Type::INT4; [primary_key()],
+        }
+    );
+
+    gen_table!(
+        struct Parcel("parcels") {
+            id: i32 = Type::INT4; [primary_key()],
+            shipment_id: i32 = Type::INT4; [foreign_key(Shipment::name(), "id")],
+        }
+    );
+
+    gen_table!(
+        struct Shipment("shipments") {
+            id: i32 = Type::INT4; [primary_key()],
+            warehouse_id: i32 = Type::INT4; [index()],
+            status: String = status_type(),
+            => constraints = [
+                ForeignKeyConstraint::new(
+                    "shipment_warehouse",
+                    Warehouse::name(),
+                    &[(&Shipment::columns()[1], &Warehouse::columns()[0])],
+                ),
+            ]
+            => cluster_on = "warehouse_id"
+        }
+    );
+
+    fn tables() -> [TableDef; 3] {
+        [
+            TableDef::of::<Parcel, 2>(),
+            TableDef::of::<Shipment, 3>(),
+            TableDef::of::<Warehouse, 1>(),
+        ]
+    }
+
+    #[test]
+    fn dependency_order() {
+        let script = schema_script(&tables());
+        let lines: Vec<_> = script.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "CREATE TYPE \"shipment_status\" AS ENUM ('pending', 'delivered');",
+                "CREATE TABLE IF NOT EXISTS warehouses (id int4 NOT NULL UNIQUE PRIMARY KEY);",
+                "CREATE TABLE IF NOT EXISTS shipments (\
+                id int4 NOT NULL UNIQUE PRIMARY KEY, \
+                warehouse_id int4 NOT NULL, \
+                status shipment_status NOT NULL, \
+                CONSTRAINT shipment_warehouse FOREIGN KEY (warehouse_id) REFERENCES warehouses (id));",
+                "CREATE INDEX IF NOT EXISTS warehouse_id_idx_shipments \
+                ON shipments USING btree (warehouse_id);",
+                "ALTER TABLE shipments CLUSTER ON warehouse_id_idx_shipments;",
+                "CREATE TABLE IF NOT EXISTS parcels (\
+                id int4 NOT NULL UNIQUE PRIMARY KEY, \
+                shipment_id int4 NOT NULL REFERENCES shipments(id));",
+            ]
+        );
+    }
+
+    #[test]
+    fn schemas() {
+        gen_table!(
+            struct Invoice("invoices") {
+                id: i32 = Type::INT4; [primary_key()],
+                => schema = "billing"
+            }
+        );
+
+        let script = schema_script(&[TableDef::of::<Warehouse, 1>(), TableDef::of::<Invoice, 1>()]);
+        assert_eq!(
+            script.lines().take(2).collect::<Vec<_>>(),
+            [
+                "CREATE SCHEMA IF NOT EXISTS \"billing\";",
+                "CREATE TABLE IF NOT EXISTS warehouses (id int4 NOT NULL UNIQUE PRIMARY KEY);",
+            ]
+        );
+        assert!(script.ends_with(
+            "CREATE TABLE IF NOT EXISTS billing.invoices (id int4 NOT NULL UNIQUE PRIMARY KEY);\n"
+        ));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Cyclic foreign keys between the tables [\"parcels\", \"shipments\"]"
+    )]
+    fn cyclic_references() {
+        gen_table!(
+            struct Shipment("shipments") {
+                id: i32 = Type::INT4; [primary_key()],
+                main_parcel: i32 = Type::INT4; [foreign_key(Parcel::name(), "id")],
+            }
+        );
+
+        let _ = schema_script(&[TableDef::of::<Parcel, 2>(), TableDef::of::<Shipment, 2>()]);
+    }
+
+    #[test]
+    fn run_script() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP SCHEMA IF EXISTS scripted CASCADE; \
+                CREATE SCHEMA scripted; \
+                SET search_path = scripted",
+            )
+            .unwrap();
+        client.batch_execute(&schema_script(&tables())).unwrap();
+        let count: i64 = client
+            .query_one(
+                "SELECT count(*) FROM pg_tables WHERE schemaname = 'scripted'",
+                &[],
+            )
+            .unwrap()
+            .get(0);
+        assert_eq!(count, 3);
+        client
+            .batch_execute("RESET search_path; DROP SCHEMA scripted CASCADE")
+            .unwrap();
+    }
+}
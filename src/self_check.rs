@@ -0,0 +1,254 @@
+use std::fmt;
+
+use log::{debug, info};
+use postgres::{Client, Transaction};
+use postgres_types::Type;
+
+use crate::{
+    error::Error, ext::PgTableExtension as _, gen_table, table::Table,
+    transaction::PgSavepoint as _,
+};
+
+const SCRATCH_TYPE: &str = "pg_helper_self_check_kind";
+
+gen_table!(
+    #[derive(Debug)]
+    struct Scratch("pg_helper_self_check") {
+        id: i32 = Type::INT4; [primary_key()],
+        note: String = Type::TEXT,
+        => registered = false
+    }
+);
+
+/// The operation the connected role is expected to be allowed to do.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Capability {
+    CreateType,
+    CreateTable,
+    Insert,
+    Select,
+    DropTable,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            Self::CreateType => "create a type",
+            Self::CreateTable => "create a table",
+            Self::Insert => "insert the rows",
+            Self::Select => "select the rows",
+            Self::DropTable => "drop a table",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+/// The outcome of a single step of the [`self_check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    Failed(String),
+    /// The step requires the previous ones which have failed.
+    Skipped,
+}
+
+/// The environment of the connection and the operations allowed in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityReport {
+    user: String,
+    search_path: String,
+    schema: Option<String>,
+    checks: Vec<(Capability, CheckStatus)>,
+}
+
+impl CapabilityReport {
+    /// The role the statements are executed as (`current_user`).
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    pub fn search_path(&self) -> &str {
+        &self.search_path
+    }
+
+    /// The schema the unqualified tables are created in
+    /// (`None` if no schema from the `search_path` exists).
+    pub fn schema(&self) -> Option<&str> {
+        self.schema.as_deref()
+    }
+
+    pub fn checks(&self) -> &[(Capability, CheckStatus)] {
+        &self.checks
+    }
+
+    pub fn status(&self, capability: Capability) -> Option<&CheckStatus> {
+        self.checks
+            .iter()
+            .find(|(cap, _)| *cap == capability)
+            .map(|(_, status)| status)
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|(_, status)| *status == CheckStatus::Passed)
+    }
+}
+
+impl fmt::Display for CapabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Connected as {} with the search_path {:?} (schema: {})",
+            self.user,
+            self.search_path,
+            self.schema.as_deref().unwrap_or("<none>")
+        )?;
+        for (capability, status) in &self.checks {
+            match status {
+                CheckStatus::Passed => write!(f, "\n- can {}", capability)?,
+                CheckStatus::Failed(err) => write!(f, "\n- cannot {}: {}", capability, err)?,
+                CheckStatus::Skipped => write!(f, "\n- skipped to {}", capability)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Exercise a scratch table and type in a transaction which is rolled back afterwards
+/// to find out which operations are permitted for the connection.
+///
+/// The failed operations are reported rather than returned as errors.
+///
+/// # Errors
+///
+/// If the transaction itself cannot be started or rolled back.
+pub fn self_check(client: &mut Client) -> Result<CapabilityReport, Error> {
+    info!("Running the self-check on the table {}...", Scratch::name());
+    let mut tx = client.transaction()?;
+    let (user, search_path, schema) = tx.query_tuple(
+        "SELECT current_user::text, current_setting('search_path'), current_schema()::text",
+        &[],
+    )?;
+
+    let mut checks = vec![];
+    let create_type = format!("CREATE TYPE {} AS ENUM ('ok')", SCRATCH_TYPE);
+    checks.push((
+        Capability::CreateType,
+        run_step(&mut tx, |sp| Ok(sp.batch_execute(&create_type)?)),
+    ));
+
+    let created = run_step(&mut tx, |sp| sp.create_table::<Scratch, 2>());
+    let table_exists = created == CheckStatus::Passed;
+    checks.push((Capability::CreateTable, created));
+    if table_exists {
+        let row = Scratch {
+            id: 1,
+            note: "ok".into(),
+        };
+        checks.push((
+            Capability::Insert,
+            run_step(&mut tx, |sp| sp.insert_row(&row).map(drop)),
+        ));
+        checks.push((
+            Capability::Select,
+            run_step(&mut tx, |sp| sp.select_all::<Scratch, 2>().map(drop)),
+        ));
+        let drop_table = format!("DROP TABLE {}", Scratch::name());
+        checks.push((
+            Capability::DropTable,
+            run_step(&mut tx, |sp| Ok(sp.batch_execute(&drop_table)?)),
+        ));
+    } else {
+        for capability in [
+            Capability::Insert,
+            Capability::Select,
+            Capability::DropTable,
+        ] {
+            checks.push((capability, CheckStatus::Skipped));
+        }
+    }
+
+    tx.rollback()?;
+    Ok(CapabilityReport {
+        user,
+        search_path,
+        schema,
+        checks,
+    })
+}
+
+fn run_step<F>(tx: &mut Transaction<'_>, f: F) -> CheckStatus
+where
+    F: FnOnce(&mut Transaction<'_>) -> Result<(), Error>,
+{
+    match tx.with_savepoint("pg_helper_self_check", f) {
+        Ok(()) => CheckStatus::Passed,
+        Err(err) => {
+            debug!("The self-check step has failed: {}", err);
+            CheckStatus::Failed(err.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::get_client;
+
+    #[test]
+    fn all_allowed() {
+        if let Some(mut client) = get_client() {
+            let report = self_check(&mut client).unwrap();
+            assert!(report.is_ok(), "{}", report);
+            assert_eq!(report.schema(), Some("public"));
+            assert_eq!(report.checks().len(), 5);
+
+            let leftover: Option<String> = client
+                .query_scalar("SELECT to_regclass('pg_helper_self_check')::text", &[])
+                .unwrap();
+            assert_eq!(leftover, None);
+        }
+    }
+
+    #[test]
+    fn restricted_role() {
+        if let Some(mut client) = get_client() {
+            client
+                .batch_execute(
+                    "DROP SCHEMA IF EXISTS self_check CASCADE; \
+                    CREATE SCHEMA self_check; \
+                    DROP ROLE IF EXISTS self_check_reader; \
+                    CREATE ROLE self_check_reader; \
+                    GRANT USAGE ON SCHEMA self_check TO self_check_reader; \
+                    SET search_path = self_check; \
+                    SET ROLE self_check_reader",
+                )
+                .unwrap();
+
+            let report = self_check(&mut client).unwrap();
+            assert!(!report.is_ok());
+            assert_eq!(report.user(), "self_check_reader");
+            assert_eq!(report.search_path(), "self_check");
+            assert!(matches!(
+                report.status(Capability::CreateTable),
+                Some(CheckStatus::Failed(err)) if err.contains("permission denied for schema self_check")
+            ));
+            assert_eq!(
+                report.status(Capability::Select),
+                Some(&CheckStatus::Skipped)
+            );
+            assert!(report
+                .to_string()
+                .contains("\n- cannot create a table: Failed to create the table"));
+
+            client
+                .batch_execute(
+                    "RESET ROLE; RESET search_path; \
+                    DROP SCHEMA self_check CASCADE; \
+                    DROP ROLE self_check_reader",
+                )
+                .unwrap();
+        }
+    }
+}
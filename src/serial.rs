@@ -11,7 +11,7 @@ pub enum Serial<T> {
 }
 
 impl<T: Default + Clone> Serial<T> {
-    pub fn value_or_default(&self) -> Cow<T> {
+    pub fn value_or_default(&self) -> Cow<'_, T> {
         match self {
             Serial::Default => Cow::Owned(T::default()),
             Serial::Value(val) => Cow::Borrowed(val),
@@ -38,7 +38,14 @@ where
     where
         Self: Sized,
     {
-        T::accepts(ty)
+        // the pseudo-types used for the column definition only
+        let underlying = match ty.name() {
+            "serial2" => &DbType::INT2,
+            "serial4" => &DbType::INT4,
+            "serial8" => &DbType::INT8,
+            _ => ty,
+        };
+        T::accepts(underlying)
     }
 
     to_sql_checked!();
@@ -1,11 +1,34 @@
-use std::fmt::Write as _;
+use std::{
+    array,
+    fmt::{self, Write as _},
+    slice,
+};
 
 use itertools::Itertools as _;
-use postgres_types::ToSql;
+use postgres_types::{
+    private::BytesMut, to_sql_checked, FromSql, IsNull, ToSql, Type as DbType, WrongType,
+};
+use tokio_postgres::{Error, Row};
 
-use crate::{column::Column, constraint::Constraint, type_helpers::ObjectAndCreateSql};
+use crate::{
+    cache,
+    codec::Encoded,
+    column::{Column, ColumnMeta},
+    constraint::Constraint,
+    diff::TableDef,
+    function::SqlFunction,
+    index::Index,
+    macros::__ColumnName,
+    policy::{self, Policy},
+    query::{NamedQuery, SelectOptions},
+    table_options::TableOptions,
+    trigger::Trigger,
+    type_helpers::ObjectAndCreateSql,
+};
 
 pub trait Table<const N: usize> {
+    /// The name of the table used in all the statements
+    /// (qualified with the schema if it is not in the `search_path`).
     fn name() -> &'static str;
 
     fn columns() -> [Column; N];
@@ -14,13 +37,81 @@ pub trait Table<const N: usize> {
         None
     }
 
+    fn options() -> TableOptions {
+        TableOptions::default()
+    }
+
+    /// Description of the columns for the generic code.
+    fn columns_meta() -> [ColumnMeta; N] {
+        Self::columns().map(|col| col.meta())
+    }
+
+    /// The indexes over multiple columns (or with the custom order of the values)
+    /// created along with the ones declared on the columns.
+    fn indexes() -> Vec<Index> {
+        vec![]
+    }
+
     fn create_indices_sql() -> Vec<ObjectAndCreateSql> {
+        create_indices_sql(
+            Self::name(),
+            &Self::columns(),
+            Self::indexes(),
+            &Self::options(),
+        )
+    }
+
+    /// The storage settings of the columns applied after the table creation.
+    fn alter_storage_sql() -> Vec<String> {
         Self::columns()
             .iter()
-            .filter_map(|col| col.create_index_sql(Self::name()))
+            .filter_map(|col| col.alter_storage_sql(Self::name()))
+            .collect()
+    }
+
+    /// The functions created with the table unless they exist
+    /// (e.g. the ones invoked by the [triggers][Self::triggers]).
+    fn functions() -> Vec<SqlFunction> {
+        vec![]
+    }
+
+    /// The row-level security policies (the security is only enabled if there are any).
+    fn policies() -> Vec<Policy> {
+        vec![]
+    }
+
+    /// Enable the row-level security and create the missing [policies][Self::policies].
+    fn row_security_sql() -> Vec<String> {
+        policy::row_security_sql(Self::name(), &Self::policies())
+    }
+
+    fn triggers() -> Vec<Trigger> {
+        vec![]
+    }
+
+    fn create_triggers_sql() -> Vec<String> {
+        Self::triggers()
+            .iter()
+            .map(|trigger| trigger.create_sql(Self::name()))
             .collect()
     }
 
+    /// The column whose index defines the physical order of the rows
+    /// (for the tables with the heavy range scans).
+    fn cluster_on() -> Option<&'static str> {
+        None
+    }
+
+    /// The name of the index to [`cluster_on`][Self::cluster_on].
+    ///
+    /// # Panics
+    ///
+    /// If the column is unknown or has no index.
+    fn cluster_index() -> Option<String> {
+        let column = Self::cluster_on()?;
+        Some(cluster_index(Self::name(), &Self::columns(), column))
+    }
+
     fn create_types_sql() -> Vec<ObjectAndCreateSql> {
         Self::columns()
             .iter()
@@ -30,69 +121,458 @@ pub trait Table<const N: usize> {
     }
 
     fn create_table_sql() -> String {
-        let columns: String = Self::columns().iter().map(|col| col.to_string()).join(", ");
-        let mut query = columns;
-
-        if let Some(constraints) = Self::constraints() {
-            let constraints = constraints
-                .iter()
-                .map(|constraint| constraint.as_sql())
-                .join(", ");
-            if !constraints.is_empty() {
-                write!(query, ", {}", constraints).unwrap();
+        create_table_sql(
+            Self::name(),
+            &Self::columns(),
+            &Self::constraints().unwrap_or_default(),
+            &Self::options(),
+        )
+    }
+
+    /// Fails if the `options` refer to the unknown columns.
+    fn select_sql(
+        condition: Option<&str>,
+        options: &SelectOptions,
+    ) -> Result<String, crate::Error> {
+        select_sql(Self::name(), &Self::columns(), condition, options)
+    }
+
+    /// Bespoke SQL templates to run with the `run_named` of the extensions.
+    fn custom_queries() -> Vec<NamedQuery> {
+        vec![]
+    }
+
+    /// Fails if the table has no query with such a name.
+    fn named_query(name: &str) -> Result<NamedQuery, crate::Error> {
+        Self::custom_queries()
+            .into_iter()
+            .find(|query| query.name() == name)
+            .ok_or_else(|| crate::Error::NamedQuery {
+                table: Self::name().to_owned(),
+                name: name.to_owned(),
+                reason: "no such query".to_owned(),
+            })
+    }
+}
+
+pub(crate) fn cluster_index(name: &str, columns: &[Column], column: &str) -> String {
+    columns
+        .iter()
+        .find(|col| col.name() == column)
+        .and_then(|col| col.index_name(name))
+        .unwrap_or_else(|| {
+            panic!(
+                "Table {} has no index on the column {:?} to cluster on",
+                name, column
+            )
+        })
+}
+
+pub(crate) fn create_table_sql(
+    name: &str,
+    columns: &[Column],
+    constraints: &[Box<dyn Constraint>],
+    options: &TableOptions,
+) -> String {
+    let mut query = columns.iter().map(|col| col.to_string()).join(", ");
+    let constraints = constraints
+        .iter()
+        .map(|constraint| constraint.as_sql())
+        .join(", ");
+    if !constraints.is_empty() {
+        write!(query, ", {}", constraints).unwrap();
+    }
+
+    format!(
+        "CREATE {}TABLE IF NOT EXISTS {} ({}){};",
+        options.kind_sql(),
+        name,
+        query,
+        options.suffix_sql()
+    )
+}
+
+/// The indexes declared on the columns followed by the table-level ones.
+pub(crate) fn create_indices_sql(
+    name: &str,
+    columns: &[Column],
+    indexes: Vec<Index>,
+    options: &TableOptions,
+) -> Vec<ObjectAndCreateSql> {
+    columns
+        .iter()
+        .filter_map(Column::own_index)
+        .chain(indexes)
+        .map(|idx| options.apply_to_index(idx).object_sql(name))
+        .collect()
+}
+
+pub(crate) fn select_sql(
+    name: &str,
+    columns: &[Column],
+    condition: Option<&str>,
+    options: &SelectOptions,
+) -> Result<String, crate::Error> {
+    let mut query = format!(
+        "SELECT {}{} FROM {}",
+        options.distinct_sql(columns)?,
+        options.select_list_sql(columns),
+        name
+    );
+    if let Some(condition) = condition {
+        write!(query, " WHERE {}", condition).unwrap();
+    }
+    query.push_str(&options.to_sql(columns)?);
+    Ok(query)
+}
+
+pub(crate) fn insert_many_sql(name: &str, columns: &[Column], rows_number: usize) -> String {
+    if rows_number == 0 {
+        return String::new();
+    }
+    let n = columns.len();
+    let columns_names = columns.iter().map(|c| c.name()).join(", ");
+    // the placeholders are written in place since there are thousands of them for the big inserts
+    let mut query = format!("INSERT INTO {} ({}) VALUES ", name, columns_names);
+    for row_idx in 0..rows_number {
+        if row_idx > 0 {
+            query.push_str(", ");
+        }
+        query.push('(');
+        for (x, col) in columns.iter().enumerate() {
+            if x > 0 {
+                query.push_str(", ");
             }
+            col.write_insert_placeholder(&mut query, n * row_idx + x + 1);
         }
+        query.push(')');
+    }
+    query.push(';');
+    query
+}
 
-        format!("CREATE TABLE IF NOT EXISTS {} ({});", Self::name(), query)
+/// The schema of the table taken from its qualified [name][Table::name]
+/// (the unqualified tables are considered to be in the `public`).
+///
+/// Implemented for every table so the schema can not disagree with the name
+/// used in the statements: qualify the name (or use the `=> schema` of the `gen_table!`)
+/// to put the table into another schema.
+pub trait TableSchema<const N: usize>: Table<N> {
+    fn schema() -> &'static str {
+        schema_of(Self::name())
     }
 }
 
+impl<T, const N: usize> TableSchema<N> for T where T: Table<N> {}
+
+/// The schema of the table with the (possibly qualified) `name`.
+pub(crate) fn schema_of(name: &str) -> &str {
+    name.rsplit_once('.').map_or("public", |(schema, _)| schema)
+}
+
 pub trait Insertable<const N: usize> {
+    /// The name of the table to report the failed inserts
+    /// (the name of the Rust type if not specified).
+    fn table_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
     fn insert_sql() -> String {
         Self::insert_many_sql(1)
     }
 
     fn insert_many_sql(rows_number: usize) -> String;
+
+    /// Check that the values of a single row can be stored into the columns
+    /// at the same positions (used in debug builds to catch the drift
+    /// between the columns and the values order).
+    fn check_values(_values: &[FieldValue<'_>]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The positions and the types of the primary key columns among the values
+    /// (the key of the row in the external caches).
+    fn key_columns() -> Vec<(usize, DbType)> {
+        vec![]
+    }
 }
 
 impl<T, const N: usize> Insertable<N> for T
 where
     T: Table<N>,
 {
+    fn table_name() -> &'static str {
+        Self::name()
+    }
+
+    fn key_columns() -> Vec<(usize, DbType)> {
+        cache::key_columns(&Self::columns())
+    }
+
     fn insert_many_sql(rows_number: usize) -> String {
-        if rows_number == 0 {
-            return String::new();
-        }
-        let columns_names = Self::columns().iter().map(|c| c.name()).join(", ");
-        let placeholder_values = (0..rows_number)
-            .map(|row_idx| {
-                let row_placeholders = (1..=N)
-                    .map(|x| {
-                        let abs_index = N * row_idx + x;
-                        format!("${}", abs_index)
-                    })
-                    .join(", ");
-                format!("({})", row_placeholders)
-            })
-            .join(", ");
+        insert_many_sql(Self::name(), &Self::columns(), rows_number)
+    }
 
-        format!(
-            "INSERT INTO {} ({}) VALUES {};",
-            Self::name(),
-            columns_names,
-            placeholder_values,
-        )
+    fn check_values(values: &[FieldValue<'_>]) -> Result<(), String> {
+        check_values(Self::name(), &Self::columns(), values)
+    }
+}
+
+/// Check that the values of a single row can be stored into the `columns`.
+pub(crate) fn check_values(
+    table: &str,
+    columns: &[Column],
+    values: &[FieldValue<'_>],
+) -> Result<(), String> {
+    if values.len() != columns.len() {
+        return Err(format!(
+            "Table {} has {} columns but {} values given",
+            table,
+            columns.len(),
+            values.len()
+        ));
+    }
+
+    let mut buf = BytesMut::new();
+    for (col, value) in columns.iter().zip(values) {
+        buf.clear();
+        if let Err(err) = value.to_sql_checked(col.db_type(), &mut buf) {
+            if err.is::<WrongType>() {
+                return Err(format!(
+                    "The value for the column {}.{} does not match its type: {}",
+                    table,
+                    col.name(),
+                    err
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The value of a column to pass as a query parameter.
+#[derive(Clone, Copy)]
+pub enum FieldValue<'a> {
+    /// The field itself.
+    Field(&'a (dyn ToSql + Sync)),
+    /// The field encoded with its [`Codec`][crate::Codec].
+    Encoded(Encoded<'a>),
+}
+
+impl<'a> FieldValue<'a> {
+    pub fn as_sql(&self) -> &(dyn ToSql + Sync) {
+        match self {
+            Self::Field(value) => *value,
+            Self::Encoded(value) => value,
+        }
+    }
+}
+
+impl<'a, T> From<&'a T> for FieldValue<'a>
+where
+    T: ToSql + Sync,
+{
+    fn from(value: &'a T) -> Self {
+        Self::Field(value)
+    }
+}
+
+impl fmt::Debug for FieldValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_sql().fmt(f)
+    }
+}
+
+impl ToSql for FieldValue<'_> {
+    fn to_sql(
+        &self,
+        ty: &DbType,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        self.as_sql().to_sql_checked(ty, out)
+    }
+
+    /// The type is checked by the value itself.
+    fn accepts(_: &DbType) -> bool {
+        true
     }
+
+    to_sql_checked!();
+}
+
+/// The references to the values to pass as the parameters of a query.
+pub(crate) fn params<'a>(values: &'a [FieldValue<'_>]) -> Vec<&'a (dyn ToSql + Sync)> {
+    values.iter().map(FieldValue::as_sql).collect()
 }
 
 pub trait InsertableValues<const N: usize>: Insertable<N> {
     fn values(&self) -> [&(dyn ToSql + Sync); N];
+
+    /// The values to pass as the query parameters with the fields having
+    /// a [`Codec`][crate::Codec] encoded (the same as the [`Self::values`] by default).
+    fn encoded_values(&self) -> [FieldValue<'_>; N] {
+        self.values().map(FieldValue::Field)
+    }
+
+    /// The stable key of the row to use in the external caches, e.g. `buys:42`
+    /// (`None` if the table has no primary key columns).
+    ///
+    /// The key values are in their Postgres text form (e.g. `2024-01-31 12:00:00`),
+    /// the strings are not escaped.
+    fn cache_key(&self) -> Option<String> {
+        let key_columns = Self::key_columns();
+        if key_columns.is_empty() {
+            return None;
+        }
+        let values = self.encoded_values();
+        Some(cache::values_key(Self::table_name(), &key_columns, &values))
+    }
+}
+
+/// The table with the number of its columns erased,
+/// so the extension methods taking it need no `N` to be specified.
+///
+/// Implemented by the `gen_table!`, use the [`impl_dynamic_table!`][crate::impl_dynamic_table]
+/// for the manually defined tables (a blanket implementation for every `Table<N>`
+/// is impossible while the `N` is not constrained by the type itself).
+pub trait DynamicTable {
+    fn dynamic_name() -> &'static str;
+
+    fn dynamic_columns() -> Vec<Column>;
+
+    fn dynamic_values(&self) -> Vec<FieldValue<'_>>;
+
+    /// The complete definition to create the table with.
+    fn table_def() -> TableDef;
+}
+
+/// Set of columns which could be embedded into a table
+/// (implemented by the `gen_table!` and used with `#[pg(flatten)]` on a field).
+///
+/// The `gen_table!` fails to compile if the prefixed names clash with the other columns:
+///
+/// ```compile_fail
+/// use pg_helper::gen_table;
+/// use postgres_types::Type;
+///
+/// gen_table!(
+///     struct Address("addresses") {
+///         city: String = Type::TEXT,
+///     }
+/// );
+///
+/// gen_table!(
+///     struct Parcel("parcels") {
+///         shipping_city: String = Type::TEXT,
+///         #[pg(flatten, prefix = "shipping_")]
+///         shipping: Address,
+///     }
+/// );
+/// ```
+pub trait Flatten: Sized {
+    /// The number of columns.
+    const COLUMNS: usize;
+
+    /// The names of the columns (to check them at compile time).
+    const NAMES: &'static [__ColumnName];
+
+    /// The columns having their names starting with the `prefix`.
+    fn flat_columns(prefix: &str) -> Vec<Column>;
+
+    /// Pass the references to all the [`Self::COLUMNS`] values to the `out` one by one.
+    fn flat_values<'a, F>(&'a self, out: &mut F)
+    where
+        F: FnMut(FieldValue<'a>);
+
+    /// Get the values from the columns having their names starting with the `prefix`.
+    fn from_prefixed_row(row: &Row, prefix: &str) -> Result<Self, Error>;
+}
+
+/// Decode a value from the consecutive columns of a row
+/// (unlike `TryFrom<Row>` it allows to decode multiple values from a single row).
+pub trait FromRow: Sized {
+    fn from_row(row: &Row, offset: usize) -> Result<Self, Error>;
+}
+
+macro_rules! tuple_from_row {
+    ($($name:ident),+) => {
+        impl<$($name),+> FromRow for ($($name,)+)
+        where
+            $($name: for<'a> FromSql<'a>),+
+        {
+            fn from_row(row: &Row, offset: usize) -> Result<Self, Error> {
+                let mut indices = offset..;
+                Ok(($(row.try_get::<_, $name>(indices.next().unwrap())?,)+))
+            }
+        }
+    };
+}
+
+tuple_from_row!(A);
+tuple_from_row!(A, B);
+tuple_from_row!(A, B, C);
+tuple_from_row!(A, B, C, D);
+tuple_from_row!(A, B, C, D, E);
+tuple_from_row!(A, B, C, D, E, F);
+tuple_from_row!(A, B, C, D, E, F, G);
+tuple_from_row!(A, B, C, D, E, F, G, H);
+
+/// Lazy iterator over the values of all the rows (row by row)
+/// to be used as parameters of a multi-row statement without collecting them.
+#[derive(Debug)]
+pub struct RowsValues<'a, T, const N: usize> {
+    rows: slice::Iter<'a, T>,
+    current: Option<array::IntoIter<FieldValue<'a>, N>>,
+}
+
+impl<'a, T, const N: usize> RowsValues<'a, T, N> {
+    pub fn new(rows: &'a [T]) -> Self {
+        Self {
+            rows: rows.iter(),
+            current: None,
+        }
+    }
+}
+
+impl<'a, T, const N: usize> Iterator for RowsValues<'a, T, N>
+where
+    T: InsertableValues<N>,
+{
+    type Item = FieldValue<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(value);
+            }
+            self.current = Some(self.rows.next()?.encoded_values().into_iter());
+        }
+    }
+
+    fn fold<B, F>(self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, Self::Item) -> B,
+    {
+        let init = self.current.into_iter().flatten().fold(init, &mut f);
+        self.rows.fold(init, |acc, row| {
+            row.encoded_values().into_iter().fold(acc, &mut f)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.current.as_ref().map_or(0, ExactSizeIterator::len) + self.rows.len() * N;
+        (len, Some(len))
+    }
 }
 
+impl<'a, T, const N: usize> ExactSizeIterator for RowsValues<'a, T, N> where T: InsertableValues<N> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ColumnBuilder;
+    use crate::{
+        test_helpers::get_client, ColumnBuilder, NullsOrder, Order, OrderBy, PgTableExtension as _,
+    };
 
     use postgres_types::Type;
     use uuid::Uuid;
@@ -199,6 +679,140 @@ mod tests {
                 VALUES ($1, $2, $3, $4, $5), ($6, $7, $8, $9, $10);"
             );
         }
+
+        #[test]
+        fn rows_values() {
+            let buys = [
+                Buy {
+                    buy_id: Uuid::new_v4(),
+                    customer_id: Uuid::new_v4(),
+                    has_discount: None,
+                    total_price: Some(14.56),
+                    details: None,
+                },
+                Buy {
+                    buy_id: Uuid::new_v4(),
+                    customer_id: Uuid::new_v4(),
+                    has_discount: Some(true),
+                    total_price: Some(18899.9),
+                    details: Some("the delivery should be performed".into()),
+                },
+            ];
+
+            let mut values = RowsValues::new(&buys);
+            assert_eq!(values.len(), 10);
+            values.nth(6).unwrap();
+            assert_eq!(values.len(), 3);
+            assert_eq!(values.count(), 3);
+        }
+
+        crate::impl_dynamic_table!(Buy, 5);
+
+        #[test]
+        fn dynamic() {
+            let buy = Buy {
+                buy_id: Uuid::new_v4(),
+                customer_id: Uuid::new_v4(),
+                has_discount: None,
+                total_price: None,
+                details: Some("gift".into()),
+            };
+            assert_eq!(Buy::dynamic_name(), "buys");
+            assert_eq!(Buy::dynamic_columns().len(), 5);
+            assert_eq!(buy.dynamic_values().len(), 5);
+            assert_eq!(
+                Buy::table_def().create_script().first().map(String::as_str),
+                Some(Buy::create_table_sql().trim_end_matches(';'))
+            );
+        }
+
+        #[test]
+        fn check_values() {
+            let buy = Buy {
+                buy_id: Uuid::new_v4(),
+                customer_id: Uuid::new_v4(),
+                has_discount: Some(true),
+                total_price: Some(14.56),
+                details: None,
+            };
+            assert!(Buy::check_values(&buy.encoded_values()).is_ok());
+
+            let mut values = buy.encoded_values();
+            values.swap(2, 3);
+            let err = Buy::check_values(&values).unwrap_err();
+            assert!(err.contains("buys.has_discount"), "{}", err);
+
+            assert!(Buy::check_values(&values[..4]).is_err());
+        }
+
+        #[test]
+        fn select_all() {
+            assert_eq!(
+                Buy::select_sql(None, &SelectOptions::default()).unwrap(),
+                "SELECT * FROM buys"
+            );
+        }
+
+        #[test]
+        fn select_page() {
+            let options = SelectOptions::new()
+                .order_by("total_price", Order::Desc)
+                .order_by("buy_id", Order::Asc)
+                .limit(20)
+                .offset(40);
+            assert_eq!(
+                Buy::select_sql(Some("has_discount"), &options).unwrap(),
+                "SELECT * FROM buys WHERE has_discount \
+                ORDER BY total_price DESC, buy_id ASC LIMIT 20 OFFSET 40"
+            );
+
+            let options = SelectOptions::new()
+                .order_by_key(OrderBy::new("total_price", Order::Desc).nulls(NullsOrder::Last))
+                .order_by_key(OrderBy::new("details", Order::Asc).lower());
+            assert_eq!(
+                Buy::select_sql(None, &options).unwrap(),
+                "SELECT * FROM buys \
+                ORDER BY total_price DESC NULLS LAST, lower(details) ASC"
+            );
+        }
+
+        #[test]
+        fn select_distinct() {
+            assert_eq!(
+                Buy::select_sql(None, &SelectOptions::new().distinct()).unwrap(),
+                "SELECT DISTINCT * FROM buys"
+            );
+
+            let options = SelectOptions::new()
+                .distinct_on(&["customer_id"])
+                .order_by("customer_id", Order::Asc)
+                .order_by("total_price", Order::Desc);
+            assert_eq!(
+                Buy::select_sql(None, &options).unwrap(),
+                "SELECT DISTINCT ON (customer_id) * FROM buys \
+                ORDER BY customer_id ASC, total_price DESC"
+            );
+        }
+
+        #[test]
+        fn select_distinct_on_unknown_column() {
+            let options = SelectOptions::new().distinct_on(&["customer"]);
+            let err = Buy::select_sql(None, &options).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Unknown column to distinct on: \"customer\""
+            );
+        }
+
+        #[test]
+        fn select_order_by_unknown_column() {
+            let options = SelectOptions::new().order_by("price", Order::Asc);
+            let err = Buy::select_sql(None, &options).unwrap_err();
+            assert!(matches!(
+                err,
+                crate::Error::UnknownColumn { column, purpose: "order by" } if column == "price"
+            ));
+        }
     }
 
     mod with_complex_fields {
@@ -389,4 +1003,107 @@ mod tests {
             );
         }
     }
+
+    mod storage {
+        use super::*;
+        use crate::{gen_table, Compression, Storage};
+
+        gen_table!(
+            struct Attachment("attachments") {
+                id: i64 = Type::INT8; [primary_key()],
+                body: Vec<u8> = Type::BYTEA; [storage(Storage::External)],
+                summary: String = Type::TEXT; [storage(Storage::Main), compression(Compression::Lz4)],
+            }
+        );
+
+        #[test]
+        fn alter_storage() {
+            assert_eq!(
+                Attachment::alter_storage_sql(),
+                [
+                    "ALTER TABLE attachments ALTER COLUMN body SET STORAGE EXTERNAL",
+                    "ALTER TABLE attachments ALTER COLUMN summary SET STORAGE MAIN, \
+                    ALTER COLUMN summary SET COMPRESSION lz4",
+                ]
+            );
+        }
+
+        #[test]
+        fn storage_settings() {
+            let Some(mut client) = get_client() else {
+                return;
+            };
+            client
+                .batch_execute("DROP TABLE IF EXISTS attachments")
+                .unwrap();
+            client.create_table::<Attachment, 3>().unwrap();
+
+            let settings: Vec<(String, i8, i8)> = client
+                .query_tuples(
+                    "SELECT attname::text, attstorage, attcompression FROM pg_attribute \
+                    WHERE attrelid = 'attachments'::regclass AND attnum > 0 ORDER BY attnum",
+                    &[],
+                )
+                .unwrap();
+            let settings: Vec<_> = settings
+                .into_iter()
+                .map(|(name, storage, compression)| {
+                    (name, storage as u8 as char, compression as u8)
+                })
+                .collect();
+            assert_eq!(
+                settings,
+                [
+                    ("id".into(), 'p', 0),
+                    ("body".into(), 'e', 0),
+                    ("summary".into(), 'm', b'l'),
+                ]
+            );
+            client.batch_execute("DROP TABLE attachments").unwrap();
+        }
+    }
+
+    mod reflection {
+        use super::*;
+        use crate::{gen_table, Money};
+
+        gen_table!(
+            struct Payment("payments") {
+                id: i64 = Type::INT8; [primary_key()],
+                buy_id: Uuid = Type::UUID; [foreign_key("buys", "buy_id")],
+                comment: Option<String> = Type::TEXT; [nullable()],
+                #[pg(flatten)]
+                price: Money,
+            }
+        );
+
+        #[test]
+        fn columns_meta() {
+            let [id, buy_id, comment, amount, _currency] = Payment::columns_meta();
+            assert_eq!(
+                id,
+                ColumnMeta {
+                    name: "id".into(),
+                    pg_type: Type::INT8,
+                    rust_type_name: Some("i64"),
+                    nullable: false,
+                    unique: true,
+                    primary_key: true,
+                    foreign_key: None,
+                    default: None,
+                }
+            );
+            assert_eq!(buy_id.foreign_key, Some(("buys".into(), "buy_id".into())));
+            assert_eq!(
+                comment.rust_type_name,
+                Some(std::any::type_name::<Option<String>>())
+            );
+            assert!(comment.nullable);
+            assert_eq!(amount.name, "amount");
+            assert_eq!(
+                amount.rust_type_name,
+                Some(std::any::type_name::<crate::Cents>())
+            );
+        }
+    }
 }
@@ -0,0 +1,205 @@
+use std::fmt::{self, Display, Write as _};
+
+use crate::{
+    column::Column,
+    index::Index,
+    partition::{PartitionKey, PartitionStrategy},
+    type_helpers::{storage_params_sql, validate_name},
+};
+
+/// What happens to the [temporary][TableOptions::temporary] table
+/// at the end of the transaction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OnCommit {
+    /// Keep the rows until the end of the session.
+    PreserveRows,
+    DeleteRows,
+    Drop,
+}
+
+impl Display for OnCommit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            OnCommit::PreserveRows => "PRESERVE ROWS",
+            OnCommit::DeleteRows => "DELETE ROWS",
+            OnCommit::Drop => "DROP",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Persistence {
+    Permanent,
+    Temporary(OnCommit),
+    Unlogged,
+}
+
+/// The properties of the table applied on its creation
+/// (see the [`Table::options`][crate::Table::options]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableOptions {
+    persistence: Persistence,
+    partition_key: Option<PartitionKey>,
+    params: Vec<(String, String)>,
+    tablespace: Option<String>,
+    index_tablespace: Option<String>,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        Self {
+            persistence: Persistence::Permanent,
+            partition_key: None,
+            params: vec![],
+            tablespace: None,
+            index_tablespace: None,
+        }
+    }
+}
+
+impl TableOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The table only visible in the current session (e.g. to stage the imported data).
+    ///
+    /// The temporary tables should not be qualified with the schema.
+    pub const fn temporary(mut self, on_commit: OnCommit) -> Self {
+        self.persistence = Persistence::Temporary(on_commit);
+        self
+    }
+
+    /// The table skipping the write-ahead log: faster to write
+    /// but truncated after a crash and not replicated.
+    pub const fn unlogged(mut self) -> Self {
+        self.persistence = Persistence::Unlogged;
+        self
+    }
+
+    /// Split the rows among the partitions by the values of the columns
+    /// (the partitions are created with the
+    /// [`create_partition`][crate::PgTableExtension::create_partition]).
+    ///
+    /// # Panics
+    ///
+    /// If no columns are given.
+    pub fn partition_by(mut self, strategy: PartitionStrategy, columns: &[&Column]) -> Self {
+        self.partition_key = Some(PartitionKey::new(strategy, columns));
+        self
+    }
+
+    pub(crate) fn partition_key(&self) -> Option<&PartitionKey> {
+        self.partition_key.as_ref()
+    }
+
+    /// The percentage of the table pages filled by the inserts
+    /// (the space left lets the updates keep the rows on the same page).
+    ///
+    /// # Panics
+    ///
+    /// If the value is not in the `10..=100` range.
+    pub fn fillfactor(self, percent: u8) -> Self {
+        assert!(
+            (10..=100).contains(&percent),
+            "The fillfactor should be in the range 10..=100, got {}",
+            percent
+        );
+        self.param("fillfactor", percent)
+    }
+
+    /// Any storage parameter of the table
+    /// (e.g. the `autovacuum_enabled = false` or the `toast_tuple_target = 256`).
+    ///
+    /// # Panics
+    ///
+    /// If the name of the parameter is not a valid lowercase identifier
+    /// (the `toast.` prefix is allowed).
+    pub fn param(mut self, name: impl AsRef<str>, value: impl Display) -> Self {
+        let name = name.as_ref();
+        validate_name(
+            "storage parameter",
+            name.strip_prefix("toast.").unwrap_or(name),
+        );
+        self.params.push((name.to_owned(), value.to_string()));
+        self
+    }
+
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    /// Place the table into the tablespace (e.g. on the dedicated storage).
+    ///
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn tablespace(mut self, name: impl AsRef<str>) -> Self {
+        let name = name.as_ref();
+        validate_name("tablespace", name);
+        self.tablespace = Some(name.to_owned());
+        self
+    }
+
+    /// Place the indexes of the table into the tablespace
+    /// (unless the [`IndexOptions`][crate::IndexOptions] of the index define their own one).
+    ///
+    /// # Panics
+    ///
+    /// If the name is not a valid lowercase identifier.
+    pub fn index_tablespace(mut self, name: impl AsRef<str>) -> Self {
+        let name = name.as_ref();
+        validate_name("tablespace", name);
+        self.index_tablespace = Some(name.to_owned());
+        self
+    }
+
+    pub fn get_tablespace(&self) -> Option<&str> {
+        self.tablespace.as_deref()
+    }
+
+    pub fn get_index_tablespace(&self) -> Option<&str> {
+        self.index_tablespace.as_deref()
+    }
+
+    pub const fn is_temporary(&self) -> bool {
+        matches!(self.persistence, Persistence::Temporary(_))
+    }
+
+    pub const fn is_unlogged(&self) -> bool {
+        matches!(self.persistence, Persistence::Unlogged)
+    }
+
+    /// The kind of the table inserted after the `CREATE` (with the trailing space if any).
+    pub(crate) fn kind_sql(&self) -> &'static str {
+        match self.persistence {
+            Persistence::Permanent => "",
+            Persistence::Temporary(_) => "TEMPORARY ",
+            Persistence::Unlogged => "UNLOGGED ",
+        }
+    }
+
+    /// The clauses following the columns of the table (with the leading space if any).
+    pub(crate) fn suffix_sql(&self) -> String {
+        let mut sql = String::new();
+        if let Some(key) = &self.partition_key {
+            write!(sql, " {}", key).unwrap();
+        }
+        sql.push_str(&storage_params_sql(&self.params));
+        if let Persistence::Temporary(on_commit) = self.persistence {
+            write!(sql, " ON COMMIT {}", on_commit).unwrap();
+        }
+        if let Some(tablespace) = &self.tablespace {
+            write!(sql, " TABLESPACE {}", tablespace).unwrap();
+        }
+        sql
+    }
+
+    pub(crate) fn apply_to_index(&self, index: Index) -> Index {
+        match &self.index_tablespace {
+            Some(tablespace) => index.default_tablespace(tablespace),
+            None => index,
+        }
+    }
+}
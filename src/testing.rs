@@ -0,0 +1,120 @@
+//! Helpers to check the `ToSql`/`FromSql` implementations of the custom types.
+
+use std::error::Error;
+
+use postgres::Client;
+use postgres_types::{private::BytesMut, FromSql, IsNull, ToSql, Type as DbType};
+
+/// Encode the value in the binary format for the given type and decode it back
+/// without the database (the way the `postgres` client does it).
+pub fn codec_roundtrip<V>(value: &V, ty: &DbType) -> Result<V, Box<dyn Error + Sync + Send>>
+where
+    V: ToSql + for<'a> FromSql<'a>,
+{
+    let mut out = BytesMut::new();
+    let raw = match value.to_sql_checked(ty, &mut out)? {
+        IsNull::Yes => None,
+        IsNull::No => Some(out.as_ref()),
+    };
+    if !<V as FromSql>::accepts(ty) {
+        return Err(format!("Cannot decode the type {} back", ty).into());
+    }
+    V::from_sql_nullable(ty, raw)
+}
+
+/// Send the value to the server as a parameter cast to the `ty` and receive it back.
+///
+/// The type is looked up by name on the server,
+/// so the custom types (e.g. created with the `struct_type`) should already exist.
+pub fn server_roundtrip<V>(
+    client: &mut Client,
+    value: &V,
+    ty: &DbType,
+) -> Result<V, Box<dyn Error + Sync + Send>>
+where
+    V: ToSql + Sync + for<'a> FromSql<'a>,
+{
+    let statement = client.prepare(&format!("SELECT $1::{}", ty.name()))?;
+    let row = client.query_one(&statement, &[value])?;
+    Ok(row.try_get(0)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::{FromSql, ToSql};
+
+    use super::*;
+    use crate::{array_type, struct_type, test_helpers::get_client, Cents};
+
+    #[derive(Debug, Clone, PartialEq, ToSql, FromSql)]
+    #[postgres(name = "roundtrip_point")]
+    struct Point {
+        x: i32,
+        label: Option<String>,
+    }
+
+    fn point_type() -> DbType {
+        struct_type(
+            "roundtrip_point",
+            &[("x", DbType::INT4), ("label", DbType::TEXT)],
+        )
+    }
+
+    #[test]
+    fn simple_values() {
+        for cents in [Cents(0), Cents(-150), Cents(i64::MAX)] {
+            assert_eq!(codec_roundtrip(&cents, &DbType::NUMERIC).unwrap(), cents);
+        }
+        let values = vec![Some(1), None, Some(-3)];
+        assert_eq!(
+            codec_roundtrip(&values, &DbType::INT4_ARRAY).unwrap(),
+            values
+        );
+        assert_eq!(codec_roundtrip(&None::<i64>, &DbType::INT8).unwrap(), None);
+        assert!(codec_roundtrip(&1_i32, &DbType::TEXT).is_err());
+    }
+
+    #[test]
+    fn composites() {
+        let ty = point_type();
+        let points = vec![
+            Some(Point { x: 1, label: None }),
+            None,
+            Some(Point {
+                x: -1,
+                label: Some("origin".into()),
+            }),
+        ];
+        assert_eq!(codec_roundtrip(&points[2], &ty).unwrap(), points[2].clone());
+        assert_eq!(codec_roundtrip(&points, &array_type(ty)).unwrap(), points);
+    }
+
+    #[test]
+    fn through_server() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP TYPE IF EXISTS roundtrip_point; \
+                CREATE TYPE roundtrip_point AS (x int4, label text)",
+            )
+            .unwrap();
+
+        let points = vec![
+            Some(Point {
+                x: 5,
+                label: Some("five".into()),
+            }),
+            None,
+        ];
+        let received = server_roundtrip(&mut client, &points, &array_type(point_type())).unwrap();
+        assert_eq!(received, points);
+        let cents = Cents(12_345);
+        assert_eq!(
+            server_roundtrip(&mut client, &cents, &DbType::NUMERIC).unwrap(),
+            cents
+        );
+        client.batch_execute("DROP TYPE roundtrip_point").unwrap();
+    }
+}
@@ -0,0 +1,1279 @@
+use std::{fmt, future::Future, pin::Pin, thread, time::Duration};
+
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use postgres::{error::SqlState, Error as DbError, IsolationLevel, Row};
+
+use crate::{
+    error::Error,
+    ext::PgTableExtension as _,
+    ext_async::PgTableExtension as _,
+    projection::Projection,
+    table::{InsertableValues, Table},
+    type_helpers::quote_literal,
+};
+
+/// How many times and how often to repeat the transactions
+/// failed due to the concurrent access.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// # Panics
+    ///
+    /// If the `max_attempts` is zero.
+    pub fn new(max_attempts: usize) -> Self {
+        assert!(max_attempts > 0, "At least one attempt should be allowed");
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// The exponential delay between the attempts:
+    /// `base`, `2 * base`, `4 * base`, ... (but not more than the `max`).
+    pub const fn delay(mut self, base: Duration, max: Duration) -> Self {
+        self.base_delay = base;
+        self.max_delay = max;
+        self
+    }
+
+    pub const fn max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// The delay after the given failed attempt (starting from 1, the 0 is treated as the 1).
+    pub fn delay_after(&self, attempt: usize) -> Duration {
+        let factor = u32::try_from(attempt.saturating_sub(1))
+            .ok()
+            .and_then(|shift| 1_u32.checked_shl(shift))
+            .unwrap_or(u32::MAX);
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    fn should_retry(&self, err: &Error, attempt: usize) -> bool {
+        let retryable = [
+            SqlState::T_R_SERIALIZATION_FAILURE,
+            SqlState::T_R_DEADLOCK_DETECTED,
+        ];
+        attempt < self.max_attempts && matches!(err.code(), Some(code) if retryable.contains(code))
+    }
+}
+
+/// How long the commit waits for the WAL to be flushed
+/// (the `synchronous_commit` setting).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SynchronousCommit {
+    On,
+    /// Do not wait for the WAL flush at all.
+    ///
+    /// The transactions committed just before the server crash
+    /// (up to the `3 * wal_writer_delay`) can be lost,
+    /// but the database stays consistent.
+    /// Suits the high-volume data like telemetry which can tolerate losing a few rows.
+    Off,
+    /// Only wait for the local flush ignoring the synchronous standbys.
+    Local,
+    RemoteWrite,
+    RemoteApply,
+}
+
+impl fmt::Display for SynchronousCommit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Self::On => "on",
+            Self::Off => "off",
+            Self::Local => "local",
+            Self::RemoteWrite => "remote_write",
+            Self::RemoteApply => "remote_apply",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// The characteristics of the transaction to start.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TransactionOptions {
+    isolation_level: Option<IsolationLevel>,
+    read_only: Option<bool>,
+    deferrable: Option<bool>,
+    synchronous_commit: Option<SynchronousCommit>,
+}
+
+impl TransactionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub const fn isolation_level(mut self, level: IsolationLevel) -> Self {
+        self.isolation_level = Some(level);
+        self
+    }
+
+    pub const fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = Some(read_only);
+        self
+    }
+
+    /// Only affects the _SERIALIZABLE_ _READ ONLY_ transactions.
+    pub const fn deferrable(mut self, deferrable: bool) -> Self {
+        self.deferrable = Some(deferrable);
+        self
+    }
+
+    /// Override the durability of the transaction commit (`SET LOCAL synchronous_commit`),
+    /// e.g. trade the durability of the bulk inserts for the speed with the [`SynchronousCommit::Off`].
+    ///
+    /// The setting is reset after the transaction ends.
+    pub const fn synchronous_commit(mut self, mode: SynchronousCommit) -> Self {
+        self.synchronous_commit = Some(mode);
+        self
+    }
+
+    fn settings_sql(self) -> Option<String> {
+        self.synchronous_commit
+            .map(|mode| format!("SET LOCAL synchronous_commit = {}", mode))
+    }
+
+    fn start(self, client: &mut postgres::Client) -> Result<postgres::Transaction<'_>, DbError> {
+        let mut builder = client.build_transaction();
+        if let Some(level) = self.isolation_level {
+            builder = builder.isolation_level(level);
+        }
+        if let Some(read_only) = self.read_only {
+            builder = builder.read_only(read_only);
+        }
+        if let Some(deferrable) = self.deferrable {
+            builder = builder.deferrable(deferrable);
+        }
+        let mut tx = builder.start()?;
+        if let Some(sql) = self.settings_sql() {
+            tx.batch_execute(&sql)?;
+        }
+        Ok(tx)
+    }
+
+    async fn start_async(
+        self,
+        client: &mut tokio_postgres::Client,
+    ) -> Result<tokio_postgres::Transaction<'_>, DbError> {
+        let mut builder = client.build_transaction();
+        if let Some(level) = self.isolation_level {
+            builder = builder.isolation_level(level);
+        }
+        if let Some(read_only) = self.read_only {
+            builder = builder.read_only(read_only);
+        }
+        if let Some(deferrable) = self.deferrable {
+            builder = builder.deferrable(deferrable);
+        }
+        let tx = builder.start().await?;
+        if let Some(sql) = self.settings_sql() {
+            tx.batch_execute(&sql).await?;
+        }
+        Ok(tx)
+    }
+
+    fn serializable() -> Self {
+        Self::new().isolation_level(IsolationLevel::Serializable)
+    }
+
+    /// The _REPEATABLE READ_ _READ ONLY_ transaction
+    /// able to export or import the snapshot.
+    pub fn snapshot() -> Self {
+        Self::new()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .read_only(true)
+    }
+}
+
+/// The identifier of the exported snapshot (see the [`PgSnapshot`]).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SnapshotId(String);
+
+impl SnapshotId {
+    /// The identifier received from another process.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    const fn export_sql() -> &'static str {
+        "SELECT pg_export_snapshot()"
+    }
+
+    fn import_sql(&self) -> String {
+        format!("SET TRANSACTION SNAPSHOT {}", quote_literal(&self.0))
+    }
+}
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Sharing the same consistent view of the data between several connections.
+pub trait PgSnapshot {
+    /// Make the snapshot of the transaction available for the other connections
+    /// until the transaction ends.
+    fn export_snapshot(&mut self) -> Result<SnapshotId, Error>;
+
+    /// Switch the transaction to the snapshot exported by another one.
+    ///
+    /// Should be called before any other statement of the _REPEATABLE READ_ transaction
+    /// (see the [`TransactionOptions::snapshot`]).
+    fn import_snapshot(&mut self, id: &SnapshotId) -> Result<(), Error>;
+}
+
+impl PgSnapshot for postgres::Transaction<'_> {
+    fn export_snapshot(&mut self) -> Result<SnapshotId, Error> {
+        let id = self.query_one(SnapshotId::export_sql(), &[])?.try_get(0)?;
+        Ok(SnapshotId(id))
+    }
+
+    fn import_snapshot(&mut self, id: &SnapshotId) -> Result<(), Error> {
+        Ok(self.batch_execute(&id.import_sql())?)
+    }
+}
+
+/// The async version of the [`PgSnapshot`].
+#[async_trait]
+pub trait PgSnapshotAsync {
+    async fn export_snapshot(&self) -> Result<SnapshotId, Error>;
+
+    async fn import_snapshot(&self, id: &SnapshotId) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl PgSnapshotAsync for tokio_postgres::Transaction<'_> {
+    async fn export_snapshot(&self) -> Result<SnapshotId, Error> {
+        let id = self
+            .query_one(SnapshotId::export_sql(), &[])
+            .await?
+            .try_get(0)?;
+        Ok(SnapshotId(id))
+    }
+
+    async fn import_snapshot(&self, id: &SnapshotId) -> Result<(), Error> {
+        Ok(self.batch_execute(&id.import_sql()).await?)
+    }
+}
+
+pub trait PgTransaction {
+    /// Run the closure in a transaction
+    /// committing it on `Ok` and rolling back on `Err`.
+    fn with_transaction<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut postgres::Transaction<'_>) -> Result<R, E>,
+        E: From<Error>;
+
+    /// The same as [`Self::with_transaction`] with the given isolation level and access mode.
+    fn with_transaction_options<R, E, F>(
+        &mut self,
+        options: &TransactionOptions,
+        f: F,
+    ) -> Result<R, E>
+    where
+        F: FnOnce(&mut postgres::Transaction<'_>) -> Result<R, E>,
+        E: From<Error>;
+
+    /// Run the closure in the [snapshot transaction][TransactionOptions::snapshot]
+    /// exporting its snapshot, so the parallel workers could
+    /// [import][PgSnapshot::import_snapshot] it to read exactly the same data.
+    ///
+    /// The snapshot is only available until the closure returns.
+    fn with_snapshot<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut postgres::Transaction<'_>, &SnapshotId) -> Result<R, E>,
+        E: From<Error>;
+
+    /// Run the closure in a transaction and commit it,
+    /// repeating the whole transaction on the serialization failures (`40001`)
+    /// and deadlocks (`40P01`).
+    ///
+    /// The closure should not have the side effects outside of the transaction
+    /// since it could be called several times.
+    fn retrying_transaction<R, F>(&mut self, retry: &RetryPolicy, f: F) -> Result<R, Error>
+    where
+        F: FnMut(&mut postgres::Transaction<'_>) -> Result<R, Error>;
+
+    /// The same as [`Self::retrying_transaction`] with the _SERIALIZABLE_ isolation level.
+    fn run_serializable<R, F>(&mut self, retry: &RetryPolicy, f: F) -> Result<R, Error>
+    where
+        F: FnMut(&mut postgres::Transaction<'_>) -> Result<R, Error>;
+
+    /// Create the [`PROJECTIONS_TABLE`][crate::PROJECTIONS_TABLE] (if not yet)
+    /// to be called once before running the [projections][Self::project].
+    fn create_projections_table(&mut self) -> Result<(), Error>;
+
+    /// Process the next batch of the `Src` rows not yet seen by the `projection`,
+    /// inserting the transformed rows into the `Dst` table.
+    ///
+    /// Returns the number of processed source rows (zero when caught up).
+    /// See the [`Projection`] for the requirements on the source table.
+    /// Fails if the source table has no cursor column of the projection
+    /// or the [progress table][Self::create_projections_table] is not created.
+    fn project<Src, Dst, F, const N: usize, const M: usize>(
+        &mut self,
+        projection: &Projection,
+        transform: F,
+    ) -> Result<usize, Error>
+    where
+        Src: Table<N> + TryFrom<Row, Error = DbError>,
+        Dst: InsertableValues<M>,
+        F: FnMut(Src) -> Option<Dst>;
+}
+
+impl PgTransaction for postgres::Client {
+    fn with_transaction<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut postgres::Transaction<'_>) -> Result<R, E>,
+        E: From<Error>,
+    {
+        self.with_transaction_options(&TransactionOptions::default(), f)
+    }
+
+    fn with_transaction_options<R, E, F>(
+        &mut self,
+        options: &TransactionOptions,
+        f: F,
+    ) -> Result<R, E>
+    where
+        F: FnOnce(&mut postgres::Transaction<'_>) -> Result<R, E>,
+        E: From<Error>,
+    {
+        let mut tx = options.start(self).map_err(Error::from)?;
+        match f(&mut tx) {
+            Ok(res) => {
+                tx.commit().map_err(Error::from)?;
+                Ok(res)
+            }
+            Err(err) => {
+                if let Err(rollback_err) = tx.rollback() {
+                    warn!("Failed to rollback the transaction: {}", rollback_err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    fn with_snapshot<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut postgres::Transaction<'_>, &SnapshotId) -> Result<R, E>,
+        E: From<Error>,
+    {
+        self.with_transaction_options(&TransactionOptions::snapshot(), |tx| {
+            let id = tx.export_snapshot()?;
+            debug!("Exported the snapshot {}", id);
+            f(tx, &id)
+        })
+    }
+
+    fn retrying_transaction<R, F>(&mut self, retry: &RetryPolicy, f: F) -> Result<R, Error>
+    where
+        F: FnMut(&mut postgres::Transaction<'_>) -> Result<R, Error>,
+    {
+        retrying(self, &TransactionOptions::default(), retry, f)
+    }
+
+    fn run_serializable<R, F>(&mut self, retry: &RetryPolicy, f: F) -> Result<R, Error>
+    where
+        F: FnMut(&mut postgres::Transaction<'_>) -> Result<R, Error>,
+    {
+        retrying(self, &TransactionOptions::serializable(), retry, f)
+    }
+
+    fn create_projections_table(&mut self) -> Result<(), Error> {
+        self.batch_execute(&Projection::create_progress_table_sql())?;
+        Ok(())
+    }
+
+    fn project<Src, Dst, F, const N: usize, const M: usize>(
+        &mut self,
+        projection: &Projection,
+        transform: F,
+    ) -> Result<usize, Error>
+    where
+        Src: Table<N> + TryFrom<Row, Error = DbError>,
+        Dst: InsertableValues<M>,
+        F: FnMut(Src) -> Option<Dst>,
+    {
+        let query = projection.select_changes_sql::<Src, N>()?;
+        debug!("Changes for projection {}: {}", projection.name(), query);
+
+        self.with_transaction(|tx| {
+            let [register, lock] = Projection::lock_position_sql();
+            let _ = tx.execute(&register, &[&projection.name()])?;
+            let position: i64 = tx.query_one(&lock, &[&projection.name()])?.get(0);
+
+            let rows = tx.query(&query, &[&position])?;
+            let Some(batch) = projection.transform::<Src, Dst, _, N, M>(rows, transform)? else {
+                return Ok(0);
+            };
+            if !batch.derived.is_empty() {
+                let _ = tx.insert_rows(&batch.derived)?;
+            }
+
+            let _ = tx.execute(
+                &Projection::save_position_sql(),
+                &[&projection.name(), &batch.last_position],
+            )?;
+            Ok(batch.processed)
+        })
+    }
+}
+
+fn retrying<R, F>(
+    client: &mut postgres::Client,
+    options: &TransactionOptions,
+    retry: &RetryPolicy,
+    mut f: F,
+) -> Result<R, Error>
+where
+    F: FnMut(&mut postgres::Transaction<'_>) -> Result<R, Error>,
+{
+    let mut attempt = 1;
+    loop {
+        let mut tx = options.start(client)?;
+        let res = match f(&mut tx) {
+            Ok(res) => tx.commit().map(|()| res).map_err(Error::from),
+            Err(err) => {
+                if let Err(rollback_err) = tx.rollback() {
+                    warn!("Failed to rollback the transaction: {}", rollback_err);
+                }
+                Err(err)
+            }
+        };
+        match res {
+            Err(err) if retry.should_retry(&err, attempt) => {
+                let delay = retry.delay_after(attempt);
+                info!("Retry the transaction in {:?} after: {}", delay, err);
+                thread::sleep(delay);
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// Nested transactions to roll back only a part of the work.
+pub trait PgSavepoint {
+    /// Run the closure under the savepoint `name`
+    /// releasing it on `Ok` and rolling back to it on `Err`
+    /// (the enclosing transaction remains usable in both cases).
+    fn with_savepoint<R, E, F>(&mut self, name: &str, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut postgres::Transaction<'_>) -> Result<R, E>,
+        E: From<Error>;
+}
+
+impl PgSavepoint for postgres::Transaction<'_> {
+    fn with_savepoint<R, E, F>(&mut self, name: &str, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut postgres::Transaction<'_>) -> Result<R, E>,
+        E: From<Error>,
+    {
+        let mut sp = self.savepoint(name).map_err(Error::from)?;
+        match f(&mut sp) {
+            Ok(res) => {
+                sp.commit().map_err(Error::from)?;
+                Ok(res)
+            }
+            Err(err) => {
+                sp.rollback().map_err(Error::from)?;
+                Err(err)
+            }
+        }
+    }
+}
+
+/// The future returned from the closure running in the async transaction.
+pub type TransactionFuture<'a, R, E = Error> =
+    Pin<Box<dyn Future<Output = Result<R, E>> + Send + 'a>>;
+
+#[async_trait]
+pub trait PgTransactionAsync {
+    /// Run the closure in a transaction
+    /// committing it on `Ok` and rolling back on `Err`.
+    async fn with_transaction<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        F: for<'a> FnOnce(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R, E>
+            + Send,
+        R: Send,
+        E: From<Error> + Send;
+
+    /// The same as [`Self::with_transaction`] with the given isolation level and access mode.
+    async fn with_transaction_options<R, E, F>(
+        &mut self,
+        options: &TransactionOptions,
+        f: F,
+    ) -> Result<R, E>
+    where
+        F: for<'a> FnOnce(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R, E>
+            + Send,
+        R: Send,
+        E: From<Error> + Send;
+
+    /// Run the closure in the [snapshot transaction][TransactionOptions::snapshot]
+    /// exporting its snapshot, so the parallel workers could
+    /// [import][PgSnapshotAsync::import_snapshot] it to read exactly the same data.
+    ///
+    /// The snapshot is only available until the future completes.
+    async fn with_snapshot<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        F: for<'a> FnOnce(
+                &'a tokio_postgres::Transaction<'a>,
+                SnapshotId,
+            ) -> TransactionFuture<'a, R, E>
+            + Send,
+        R: Send,
+        E: From<Error> + Send;
+
+    /// Run the closure in a transaction and commit it,
+    /// repeating the whole transaction on the serialization failures (`40001`)
+    /// and deadlocks (`40P01`).
+    ///
+    /// The closure should not have the side effects outside of the transaction
+    /// since it could be called several times.
+    async fn retrying_transaction<R, F>(&mut self, retry: &RetryPolicy, f: F) -> Result<R, Error>
+    where
+        F: for<'a> FnMut(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R> + Send,
+        R: Send;
+
+    /// The same as [`Self::retrying_transaction`] with the _SERIALIZABLE_ isolation level.
+    async fn run_serializable<R, F>(&mut self, retry: &RetryPolicy, f: F) -> Result<R, Error>
+    where
+        F: for<'a> FnMut(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R> + Send,
+        R: Send;
+
+    /// Create the [`PROJECTIONS_TABLE`][crate::PROJECTIONS_TABLE] (if not yet)
+    /// to be called once before running the [projections][Self::project].
+    async fn create_projections_table(&mut self) -> Result<(), Error>;
+
+    /// Process the next batch of the `Src` rows not yet seen by the `projection`,
+    /// inserting the transformed rows into the `Dst` table.
+    ///
+    /// Returns the number of processed source rows (zero when caught up).
+    /// See the [`Projection`] for the requirements on the source table.
+    /// Fails if the source table has no cursor column of the projection
+    /// or the [progress table][Self::create_projections_table] is not created.
+    async fn project<Src, Dst, F, const N: usize, const M: usize>(
+        &mut self,
+        projection: &Projection,
+        transform: F,
+    ) -> Result<usize, Error>
+    where
+        Src: Table<N> + TryFrom<Row, Error = DbError>,
+        Dst: InsertableValues<M> + Sync + Send,
+        F: FnMut(Src) -> Option<Dst> + Send;
+}
+
+#[async_trait]
+impl PgTransactionAsync for tokio_postgres::Client {
+    async fn with_transaction<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        F: for<'a> FnOnce(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R, E>
+            + Send,
+        R: Send,
+        E: From<Error> + Send,
+    {
+        self.with_transaction_options(&TransactionOptions::default(), f)
+            .await
+    }
+
+    async fn with_transaction_options<R, E, F>(
+        &mut self,
+        options: &TransactionOptions,
+        f: F,
+    ) -> Result<R, E>
+    where
+        F: for<'a> FnOnce(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R, E>
+            + Send,
+        R: Send,
+        E: From<Error> + Send,
+    {
+        let tx = options.start_async(self).await.map_err(Error::from)?;
+        let res = f(&tx).await;
+        match res {
+            Ok(res) => {
+                tx.commit().await.map_err(Error::from)?;
+                Ok(res)
+            }
+            Err(err) => {
+                if let Err(rollback_err) = tx.rollback().await {
+                    warn!("Failed to rollback the transaction: {}", rollback_err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    async fn with_snapshot<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        F: for<'a> FnOnce(
+                &'a tokio_postgres::Transaction<'a>,
+                SnapshotId,
+            ) -> TransactionFuture<'a, R, E>
+            + Send,
+        R: Send,
+        E: From<Error> + Send,
+    {
+        let tx = TransactionOptions::snapshot()
+            .start_async(self)
+            .await
+            .map_err(Error::from)?;
+        let id = tx.export_snapshot().await?;
+        debug!("Exported the snapshot {}", id);
+        let res = f(&tx, id).await;
+        match res {
+            Ok(res) => {
+                tx.commit().await.map_err(Error::from)?;
+                Ok(res)
+            }
+            Err(err) => {
+                if let Err(rollback_err) = tx.rollback().await {
+                    warn!("Failed to rollback the transaction: {}", rollback_err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    async fn retrying_transaction<R, F>(&mut self, retry: &RetryPolicy, f: F) -> Result<R, Error>
+    where
+        F: for<'a> FnMut(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R> + Send,
+        R: Send,
+    {
+        retrying_async(self, &TransactionOptions::default(), retry, f).await
+    }
+
+    async fn run_serializable<R, F>(&mut self, retry: &RetryPolicy, f: F) -> Result<R, Error>
+    where
+        F: for<'a> FnMut(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R> + Send,
+        R: Send,
+    {
+        retrying_async(self, &TransactionOptions::serializable(), retry, f).await
+    }
+
+    async fn create_projections_table(&mut self) -> Result<(), Error> {
+        self.batch_execute(&Projection::create_progress_table_sql())
+            .await?;
+        Ok(())
+    }
+
+    async fn project<Src, Dst, F, const N: usize, const M: usize>(
+        &mut self,
+        projection: &Projection,
+        transform: F,
+    ) -> Result<usize, Error>
+    where
+        Src: Table<N> + TryFrom<Row, Error = DbError>,
+        Dst: InsertableValues<M> + Sync + Send,
+        F: FnMut(Src) -> Option<Dst> + Send,
+    {
+        let query = projection.select_changes_sql::<Src, N>()?;
+        debug!("Changes for projection {}: {}", projection.name(), query);
+
+        let tx = self.transaction().await?;
+        let [register, lock] = Projection::lock_position_sql();
+        let _ = tx.execute(&register, &[&projection.name()]).await?;
+        let position: i64 = tx.query_one(&lock, &[&projection.name()]).await?.get(0);
+
+        let rows = tx.query(&query, &[&position]).await?;
+        let Some(batch) = projection.transform::<Src, Dst, _, N, M>(rows, transform)? else {
+            return Ok(0);
+        };
+        if !batch.derived.is_empty() {
+            let _ = tx.insert_rows(&batch.derived).await?;
+        }
+
+        let _ = tx
+            .execute(
+                &Projection::save_position_sql(),
+                &[&projection.name(), &batch.last_position],
+            )
+            .await?;
+        tx.commit().await?;
+        Ok(batch.processed)
+    }
+}
+
+async fn retrying_async<R, F>(
+    client: &mut tokio_postgres::Client,
+    options: &TransactionOptions,
+    retry: &RetryPolicy,
+    mut f: F,
+) -> Result<R, Error>
+where
+    F: for<'a> FnMut(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R> + Send,
+    R: Send,
+{
+    let mut attempt = 1;
+    loop {
+        let tx = options.start_async(client).await?;
+        let res = match f(&tx).await {
+            Ok(res) => tx.commit().await.map(|()| res).map_err(Error::from),
+            Err(err) => {
+                if let Err(rollback_err) = tx.rollback().await {
+                    warn!("Failed to rollback the transaction: {}", rollback_err);
+                }
+                Err(err)
+            }
+        };
+        match res {
+            Err(err) if retry.should_retry(&err, attempt) => {
+                let delay = retry.delay_after(attempt);
+                info!("Retry the transaction in {:?} after: {}", delay, err);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+#[async_trait]
+pub trait PgSavepointAsync {
+    /// Run the closure under the savepoint `name`
+    /// releasing it on `Ok` and rolling back to it on `Err`
+    /// (the enclosing transaction remains usable in both cases).
+    async fn with_savepoint<R, E, F>(&mut self, name: &str, f: F) -> Result<R, E>
+    where
+        F: for<'a> FnOnce(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R, E>
+            + Send,
+        R: Send,
+        E: From<Error> + Send;
+}
+
+#[async_trait]
+impl PgSavepointAsync for tokio_postgres::Transaction<'_> {
+    async fn with_savepoint<R, E, F>(&mut self, name: &str, f: F) -> Result<R, E>
+    where
+        F: for<'a> FnOnce(&'a tokio_postgres::Transaction<'a>) -> TransactionFuture<'a, R, E>
+            + Send,
+        R: Send,
+        E: From<Error> + Send,
+    {
+        let sp = self.savepoint(name).await.map_err(Error::from)?;
+        let res = f(&sp).await;
+        match res {
+            Ok(res) => {
+                sp.commit().await.map_err(Error::from)?;
+                Ok(res)
+            }
+            Err(err) => {
+                sp.rollback().await.map_err(Error::from)?;
+                Err(err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Barrier,
+    };
+
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{
+        gen_table,
+        test_helpers::{get_async_client, get_client},
+        Order, SelectOptions, PROJECTIONS_TABLE,
+    };
+
+    gen_table!(
+        #[derive(Debug, PartialEq)]
+        struct Account("tx_accounts") {
+            id: i32 = Type::INT4; [primary_key()],
+            balance: i64 = Type::INT8,
+        }
+    );
+
+    #[test]
+    fn exponential_delay() {
+        let retry =
+            RetryPolicy::new(10).delay(Duration::from_millis(100), Duration::from_millis(500));
+        let delays: Vec<_> = (1..=5).map(|i| retry.delay_after(i).as_millis()).collect();
+        assert_eq!(delays, [100, 200, 400, 500, 500]);
+        assert_eq!(retry.delay_after(100), Duration::from_millis(500));
+        assert_eq!(retry.delay_after(usize::MAX), Duration::from_millis(500));
+        assert_eq!(retry.delay_after(0), Duration::from_millis(100));
+    }
+
+    /// Two transactions read the sum and insert it as a new row,
+    /// so one of them has to fail on commit and be repeated.
+    #[test]
+    fn retry_serialization_failure() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS serial_sums; \
+                CREATE TABLE serial_sums (value int8 NOT NULL); \
+                INSERT INTO serial_sums VALUES (1);",
+            )
+            .unwrap();
+
+        let barrier = Arc::new(Barrier::new(2));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                let (barrier, calls) = (barrier.clone(), calls.clone());
+                thread::spawn(move || {
+                    let mut client = get_client().unwrap();
+                    client
+                        .run_serializable(&RetryPolicy::new(5), |tx| {
+                            let sum: i64 = tx
+                                .query_one("SELECT sum(value)::int8 FROM serial_sums", &[])?
+                                .get(0);
+                            // only the first attempts are synchronized to make them conflict
+                            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                                let _ = barrier.wait();
+                            }
+                            Ok(tx.execute("INSERT INTO serial_sums VALUES ($1)", &[&sum])?)
+                        })
+                        .unwrap()
+                })
+            })
+            .collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), 1);
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        let values: Vec<i64> = client
+            .query("SELECT value FROM serial_sums ORDER BY value", &[])
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(values, [1, 1, 2]);
+        client.batch_execute("DROP TABLE serial_sums").unwrap();
+    }
+
+    /// Two transactions update the same rows in the opposite order,
+    /// so one of them is aborted as a deadlock victim and repeated.
+    #[test]
+    fn retry_deadlock() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS deadlock_counters; \
+                CREATE TABLE deadlock_counters (id int4 PRIMARY KEY, value int4 NOT NULL); \
+                INSERT INTO deadlock_counters VALUES (1, 0), (2, 0);",
+            )
+            .unwrap();
+
+        let barrier = Arc::new(Barrier::new(2));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let workers: Vec<_> = [[1, 2], [2, 1]]
+            .into_iter()
+            .map(|ids| {
+                let (barrier, calls) = (barrier.clone(), calls.clone());
+                thread::spawn(move || {
+                    let mut client = get_client().unwrap();
+                    client
+                        .retrying_transaction(&RetryPolicy::new(3), |tx| {
+                            let first_attempt = calls.fetch_add(1, Ordering::SeqCst) < 2;
+                            for (i, id) in ids.iter().enumerate() {
+                                let _ = tx.execute(
+                                    "UPDATE deadlock_counters SET value = value + 1 WHERE id = $1",
+                                    &[id],
+                                )?;
+                                if i == 0 && first_attempt {
+                                    let _ = barrier.wait();
+                                }
+                            }
+                            Ok(())
+                        })
+                        .unwrap();
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        let values: Vec<(i32, i32)> = client
+            .query_tuples("SELECT id, value FROM deadlock_counters ORDER BY id", &[])
+            .unwrap();
+        assert_eq!(values, [(1, 2), (2, 2)]);
+        client
+            .batch_execute("DROP TABLE deadlock_counters")
+            .unwrap();
+    }
+
+    #[test]
+    fn commit_or_rollback() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS tx_accounts")
+            .unwrap();
+        client.create_table::<Account, 2>().unwrap();
+
+        let inserted = client
+            .with_transaction(|tx| {
+                tx.insert_row(&Account {
+                    id: 1,
+                    balance: 100,
+                })
+            })
+            .unwrap();
+        assert_eq!(inserted, 1);
+
+        let res: Result<(), Box<dyn std::error::Error>> = client.with_transaction(|tx| {
+            let _ = tx.insert_row(&Account { id: 2, balance: 0 })?;
+            Err("Insufficient funds".into())
+        });
+        assert_eq!(res.unwrap_err().to_string(), "Insufficient funds");
+
+        let accounts: Vec<Account> = client.select_all().unwrap();
+        assert_eq!(
+            accounts,
+            [Account {
+                id: 1,
+                balance: 100
+            }]
+        );
+        client.batch_execute("DROP TABLE tx_accounts").unwrap();
+    }
+
+    gen_table!(
+        struct Deposit("tx_deposits") {
+            id: i64 = Type::INT8; [primary_key()],
+            account: i32 = Type::INT4,
+            amount: i64 = Type::INT8,
+        }
+    );
+
+    gen_table!(
+        struct BigDeposit("tx_big_deposits") {
+            id: i64 = Type::INT8,
+            account: i32 = Type::INT4,
+        }
+    );
+
+    #[test]
+    fn projection() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(&format!(
+                "DROP TABLE IF EXISTS tx_deposits, tx_big_deposits; \
+                DROP TABLE IF EXISTS {}",
+                PROJECTIONS_TABLE
+            ))
+            .unwrap();
+        client.create_projections_table().unwrap();
+        client.create_projections_table().unwrap();
+        client.create_table::<Deposit, 3>().unwrap();
+        client.create_table::<BigDeposit, 2>().unwrap();
+
+        let deposits: Vec<_> = (1..=5)
+            .map(|id| Deposit {
+                id,
+                account: (id % 2) as i32,
+                amount: id * 100,
+            })
+            .collect();
+        let _ = client.insert_rows(&deposits[..3]).unwrap();
+
+        let projection = Projection::new("big_deposits", "id").batch_size(2);
+        let big = |deposit: Deposit| {
+            (deposit.amount >= 200).then_some(BigDeposit {
+                id: deposit.id,
+                account: deposit.account,
+            })
+        };
+        assert_eq!(client.project(&projection, big).unwrap(), 2);
+        assert_eq!(client.project(&projection, big).unwrap(), 1);
+        assert_eq!(client.project(&projection, big).unwrap(), 0);
+
+        let _ = client.insert_rows(&deposits[3..]).unwrap();
+        assert_eq!(client.project(&projection, big).unwrap(), 2);
+        assert_eq!(client.project(&projection, big).unwrap(), 0);
+
+        let projected: Vec<BigDeposit> = client
+            .select_with_options(None, &[], &SelectOptions::new().order_by("id", Order::Asc))
+            .unwrap();
+        let ids: Vec<_> = projected.iter().map(|d| d.id).collect();
+        assert_eq!(ids, [2, 3, 4, 5]);
+        client
+            .batch_execute("DROP TABLE tx_deposits, tx_big_deposits")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn commit_or_rollback_async() {
+        let Some(mut client) = get_async_client().await else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS tx_accounts")
+            .await
+            .unwrap();
+        client.create_table::<Account, 2>().await.unwrap();
+
+        let res: Result<u64, Error> = client
+            .with_transaction(|tx| {
+                Box::pin(async move {
+                    let _ = tx
+                        .insert_row(&Account {
+                            id: 1,
+                            balance: 100,
+                        })
+                        .await?;
+                    // the duplicate key fails the whole transaction
+                    tx.insert_row(&Account {
+                        id: 1,
+                        balance: 200,
+                    })
+                    .await
+                })
+            })
+            .await;
+        assert!(res.is_err());
+
+        let count: i64 = client
+            .query_scalar("SELECT count(*) FROM tx_accounts", &[])
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+        client
+            .batch_execute("DROP TABLE tx_accounts")
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn partial_rollback() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS tx_accounts")
+            .unwrap();
+        client.create_table::<Account, 2>().unwrap();
+
+        client
+            .with_transaction(|tx| {
+                let _ = tx.insert_row(&Account {
+                    id: 1,
+                    balance: 100,
+                })?;
+                let duplicate = tx.with_savepoint("batch", |sp| {
+                    let accounts = [Account { id: 2, balance: 0 }, Account { id: 1, balance: 0 }];
+                    sp.insert_rows(&accounts)
+                });
+                assert!(duplicate.is_err());
+                tx.with_savepoint("batch", |sp| sp.insert_row(&Account { id: 3, balance: 0 }))
+            })
+            .unwrap();
+
+        let ids: Vec<(i32, i64)> = client
+            .query_tuples("SELECT id, balance FROM tx_accounts ORDER BY id", &[])
+            .unwrap();
+        assert_eq!(ids, [(1, 100), (3, 0)]);
+        client.batch_execute("DROP TABLE tx_accounts").unwrap();
+    }
+
+    #[tokio::test]
+    async fn partial_rollback_async() {
+        let Some(mut client) = get_async_client().await else {
+            return;
+        };
+
+        let mut tx = client.transaction().await.unwrap();
+        let res: Result<(), Error> = tx
+            .with_savepoint("failing", |sp| {
+                Box::pin(async move { Ok(sp.batch_execute("SELECT 1 / 0").await?) })
+            })
+            .await;
+        assert!(res.is_err());
+        let one: i32 = tx.query_scalar("SELECT 1", &[]).await.unwrap();
+        assert_eq!(one, 1);
+        tx.rollback().await.unwrap();
+    }
+
+    #[test]
+    fn read_only_transaction() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        let options = TransactionOptions::new()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .read_only(true);
+        let settings: (String, String) = client
+            .with_transaction_options(&options, |tx| {
+                tx.query_tuple(
+                    "SELECT current_setting('transaction_isolation'), \
+                    current_setting('transaction_read_only')",
+                    &[],
+                )
+            })
+            .unwrap();
+        assert_eq!(settings, ("repeatable read".into(), "on".into()));
+
+        let res: Result<(), Error> = client.with_transaction_options(&options, |tx| {
+            Ok(tx.batch_execute("CREATE TEMPORARY TABLE read_only_tmp (id int4)")?)
+        });
+        assert_eq!(
+            res.unwrap_err().code(),
+            Some(&SqlState::READ_ONLY_SQL_TRANSACTION)
+        );
+    }
+
+    #[test]
+    fn relaxed_commit() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        let options = TransactionOptions::new().synchronous_commit(SynchronousCommit::Off);
+        let inside: String = client
+            .with_transaction_options(&options, |tx| {
+                tx.query_scalar("SHOW synchronous_commit", &[])
+            })
+            .unwrap();
+        assert_eq!(inside, "off");
+
+        let after: String = client.query_scalar("SHOW synchronous_commit", &[]).unwrap();
+        assert_eq!(after, "on");
+    }
+
+    /// The workers importing the snapshot do not see the rows inserted after the export.
+    #[test]
+    fn shared_snapshot() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS snapshot_items; \
+                CREATE TABLE snapshot_items (id int4 NOT NULL); \
+                INSERT INTO snapshot_items SELECT generate_series(1, 10);",
+            )
+            .unwrap();
+
+        let counts: Vec<i64> = client
+            .with_snapshot(|tx, id| {
+                let mut writer = get_client().unwrap();
+                writer.batch_execute("INSERT INTO snapshot_items VALUES (11)")?;
+
+                let workers: Vec<_> = (0..2)
+                    .map(|_| {
+                        let id = id.clone();
+                        thread::spawn(move || {
+                            let mut worker = get_client().unwrap();
+                            worker
+                                .with_transaction_options(&TransactionOptions::snapshot(), |tx| {
+                                    tx.import_snapshot(&id)?;
+                                    tx.query_scalar::<i64>(
+                                        "SELECT count(*) FROM snapshot_items",
+                                        &[],
+                                    )
+                                })
+                                .unwrap()
+                        })
+                    })
+                    .collect();
+                let mut counts: Vec<i64> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+                counts.push(tx.query_scalar("SELECT count(*) FROM snapshot_items", &[])?);
+                Ok::<_, Error>(counts)
+            })
+            .unwrap();
+        assert_eq!(counts, [10, 10, 10]);
+
+        let err = client
+            .with_transaction_options(&TransactionOptions::snapshot(), |tx| {
+                tx.import_snapshot(&SnapshotId::new("00000003-0000001B-1"))
+            })
+            .unwrap_err();
+        assert_eq!(err.code(), Some(&SqlState::INVALID_PARAMETER_VALUE));
+        client.batch_execute("DROP TABLE snapshot_items").unwrap();
+    }
+
+    #[tokio::test]
+    async fn shared_snapshot_async() {
+        let Some(mut client) = get_async_client().await else {
+            return;
+        };
+        let mut worker = get_async_client().await.unwrap();
+
+        let res: Result<bool, Error> = client
+            .with_snapshot(|_tx, id| {
+                Box::pin(async move {
+                    worker
+                        .with_transaction_options(&TransactionOptions::snapshot(), |tx| {
+                            Box::pin(async move {
+                                tx.import_snapshot(&id).await?;
+                                let row = tx.query_one("SHOW transaction_read_only", &[]).await?;
+                                Ok(row.get::<_, String>(0) == "on")
+                            })
+                        })
+                        .await
+                })
+            })
+            .await;
+        assert!(res.unwrap());
+    }
+
+    #[tokio::test]
+    async fn run_serializable_async() {
+        let Some(mut client) = get_async_client().await else {
+            return;
+        };
+
+        let level: String = client
+            .run_serializable(&RetryPolicy::default(), |tx| {
+                Box::pin(async move {
+                    let row = tx.query_one("SHOW transaction_isolation", &[]).await?;
+                    Ok(row.get(0))
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(level, "serializable");
+
+        let options = TransactionOptions::new().synchronous_commit(SynchronousCommit::Local);
+        let mode: Result<String, Error> = client
+            .with_transaction_options(&options, |tx| {
+                Box::pin(async move {
+                    let row = tx.query_one("SHOW synchronous_commit", &[]).await?;
+                    Ok(row.get(0))
+                })
+            })
+            .await;
+        assert_eq!(mode.unwrap(), "local");
+    }
+}
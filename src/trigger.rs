@@ -0,0 +1,238 @@
+use std::fmt::{self, Display, Write as _};
+
+use itertools::Itertools as _;
+
+use crate::{column::Column, type_helpers::validate_name};
+
+/// When the [`Trigger`] fires relative to the statement.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TriggerTiming {
+    Before,
+    After,
+    /// Instead of the statement (only for the views).
+    InsteadOf,
+}
+
+impl Display for TriggerTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            TriggerTiming::Before => "BEFORE",
+            TriggerTiming::After => "AFTER",
+            TriggerTiming::InsteadOf => "INSTEAD OF",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+/// The statement firing the [`Trigger`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriggerEvent {
+    Insert,
+    Update,
+    /// The update of any of the columns.
+    UpdateOf(Vec<String>),
+    Delete,
+    Truncate,
+}
+
+impl TriggerEvent {
+    pub fn update_of(columns: &[&Column]) -> Self {
+        Self::UpdateOf(columns.iter().map(|col| col.name().to_owned()).collect())
+    }
+}
+
+impl Display for TriggerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Insert => write!(f, "INSERT"),
+            Self::Update => write!(f, "UPDATE"),
+            Self::UpdateOf(columns) => write!(f, "UPDATE OF {}", columns.iter().join(", ")),
+            Self::Delete => write!(f, "DELETE"),
+            Self::Truncate => write!(f, "TRUNCATE"),
+        }
+    }
+}
+
+/// The function invoked on the changes of the table (see the [`Table::triggers`][crate::Table::triggers]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trigger {
+    name: String,
+    timing: TriggerTiming,
+    events: Vec<TriggerEvent>,
+    for_each_row: bool,
+    condition: Option<String>,
+    function: String,
+}
+
+impl Trigger {
+    /// The trigger invoking the function (returning the `trigger`) for every changed row.
+    ///
+    /// # Panics
+    ///
+    /// If no events are given or the name of the trigger or the function
+    /// is not a valid lowercase identifier.
+    pub fn new(
+        name: impl AsRef<str>,
+        timing: TriggerTiming,
+        events: impl IntoIterator<Item = TriggerEvent>,
+        function: impl AsRef<str>,
+    ) -> Self {
+        let (name, function) = (name.as_ref(), function.as_ref());
+        validate_name("trigger", name);
+        for part in function.splitn(2, '.') {
+            validate_name("function", part);
+        }
+        let events: Vec<_> = events.into_iter().collect();
+        assert!(
+            !events.is_empty(),
+            "The trigger {} should have at least one event",
+            name
+        );
+        Self {
+            name: name.to_owned(),
+            timing,
+            events,
+            for_each_row: true,
+            condition: None,
+            function: function.to_owned(),
+        }
+    }
+
+    /// Invoke the function once per statement rather than for every row.
+    pub const fn for_each_statement(mut self) -> Self {
+        self.for_each_row = false;
+        self
+    }
+
+    /// Only fire if the condition on the `OLD` and `NEW` rows holds
+    /// (e.g. `OLD.* IS DISTINCT FROM NEW.*`).
+    pub fn when(mut self, condition: impl AsRef<str>) -> Self {
+        self.condition = Some(condition.as_ref().to_owned());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    /// The trigger is replaced if it exists (requires PostgreSQL 14).
+    pub fn create_sql(&self, table_name: &str) -> String {
+        let mut sql = format!(
+            "CREATE OR REPLACE TRIGGER {} {} {} ON {} FOR EACH {}",
+            self.name,
+            self.timing,
+            self.events.iter().join(" OR "),
+            table_name,
+            if self.for_each_row {
+                "ROW"
+            } else {
+                "STATEMENT"
+            }
+        );
+        if let Some(condition) = &self.condition {
+            write!(sql, " WHEN ({})", condition).unwrap();
+        }
+        write!(sql, " EXECUTE FUNCTION {}()", self.function).unwrap();
+        sql
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client, PgTableExtension as _, Table};
+
+    gen_table!(
+        struct Article("trigger_articles") {
+            id: i32 = Type::INT4; [primary_key()],
+            title: String = Type::TEXT,
+            revision: i32 = Type::INT4,
+            => triggers = [
+                Trigger::new(
+                    "trigger_articles_revision",
+                    TriggerTiming::Before,
+                    [TriggerEvent::update_of(&[&Self::columns()[1]])],
+                    "trigger_bump_revision",
+                )
+                .when("OLD.title IS DISTINCT FROM NEW.title"),
+            ]
+        }
+    );
+
+    #[test]
+    fn statements() {
+        assert_eq!(
+            Article::create_triggers_sql(),
+            ["CREATE OR REPLACE TRIGGER trigger_articles_revision \
+            BEFORE UPDATE OF title ON trigger_articles FOR EACH ROW \
+            WHEN (OLD.title IS DISTINCT FROM NEW.title) \
+            EXECUTE FUNCTION trigger_bump_revision()"]
+        );
+        let audit = Trigger::new(
+            "audit",
+            TriggerTiming::After,
+            [TriggerEvent::Insert, TriggerEvent::Delete],
+            "audit.log_change",
+        )
+        .for_each_statement();
+        assert_eq!(
+            audit.create_sql("trigger_articles"),
+            "CREATE OR REPLACE TRIGGER audit AFTER INSERT OR DELETE ON trigger_articles \
+            FOR EACH STATEMENT EXECUTE FUNCTION audit.log_change()"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The trigger empty should have at least one event")]
+    fn no_events() {
+        let _ = Trigger::new("empty", TriggerTiming::After, vec![], "noop");
+    }
+
+    #[test]
+    fn bumped_revision() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS trigger_articles; \
+                CREATE OR REPLACE FUNCTION trigger_bump_revision() RETURNS trigger \
+                LANGUAGE plpgsql AS $$ \
+                BEGIN NEW.revision := OLD.revision + 1; RETURN NEW; END $$",
+            )
+            .unwrap();
+        client.create_table::<Article, 3>().unwrap();
+        // the existing trigger is replaced
+        client.create_table::<Article, 3>().unwrap();
+        client
+            .insert_row(&Article {
+                id: 1,
+                title: "draft".into(),
+                revision: 0,
+            })
+            .unwrap();
+        client
+            .batch_execute(
+                "UPDATE trigger_articles SET title = 'final'; \
+                UPDATE trigger_articles SET title = 'final'",
+            )
+            .unwrap();
+        let revision: i32 = client
+            .query_scalar("SELECT revision FROM trigger_articles", &[])
+            .unwrap();
+        assert_eq!(revision, 1);
+
+        client
+            .batch_execute(
+                "DROP TABLE trigger_articles; \
+                DROP FUNCTION trigger_bump_revision()",
+            )
+            .unwrap();
+    }
+}
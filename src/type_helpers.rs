@@ -5,6 +5,8 @@ use postgres_types::{Field, Kind, Type};
 pub struct ObjectAndCreateSql {
     name: String,
     create_sql: String,
+    /// The labels of the enum type (empty for the other objects).
+    enum_labels: Vec<String>,
 }
 
 impl ObjectAndCreateSql {
@@ -12,9 +14,18 @@ impl ObjectAndCreateSql {
         Self {
             name: name.as_ref().to_owned(),
             create_sql: create_sql.as_ref().to_owned(),
+            enum_labels: vec![],
         }
     }
 
+    pub(crate) fn with_enum_labels(mut self, labels: &[impl AsRef<str>]) -> Self {
+        self.enum_labels = labels
+            .iter()
+            .map(|label| label.as_ref().to_owned())
+            .collect();
+        self
+    }
+
     /// Construct _CREATE_ statement for a type if it is not a standard type.
     /// Returns `Vec` of statements to include all the nested types also.
     pub(crate) fn from_type(ty: &Type) -> Vec<Self> {
@@ -31,12 +42,13 @@ impl ObjectAndCreateSql {
                 prev_defs.push(def);
                 prev_defs
             }
-            Kind::Enum(fields) => {
-                let fields = fields.iter().map(|f| format!("'{}'", f)).join(", ");
+            Kind::Enum(labels) => {
+                let fields = labels.iter().map(|f| quote_literal(f)).join(", ");
                 let def = Self::new(
                     ty.name(),
                     format!("CREATE TYPE \"{}\" AS ENUM ({})", ty, fields),
-                );
+                )
+                .with_enum_labels(labels);
                 vec![def]
             }
             Kind::Composite(fields) => {
@@ -69,12 +81,117 @@ impl ObjectAndCreateSql {
     pub fn create_sql(&self) -> &str {
         &self.create_sql
     }
+
+    pub(crate) fn enum_labels(&self) -> &[String] {
+        &self.enum_labels
+    }
+
+    /// Add the labels of the enum type missing in the existing one
+    /// keeping them in the order of the definition.
+    ///
+    /// The existing labels are never removed or reordered.
+    /// The added values can only be used after the transaction is committed.
+    pub(crate) fn add_enum_values_sql(&self, existing: &[String]) -> Vec<String> {
+        let labels = &self.enum_labels;
+        labels
+            .iter()
+            .enumerate()
+            .filter(|(_, label)| !existing.contains(label))
+            .map(|(i, label)| {
+                let position = if i > 0 {
+                    format!(" AFTER {}", quote_literal(&labels[i - 1]))
+                } else {
+                    labels[1..]
+                        .iter()
+                        .find(|next| existing.contains(next))
+                        .map(|next| format!(" BEFORE {}", quote_literal(next)))
+                        .unwrap_or_default()
+                };
+                format!(
+                    "ALTER TYPE \"{}\" ADD VALUE IF NOT EXISTS {}{}",
+                    self.name,
+                    quote_literal(label),
+                    position
+                )
+            })
+            .collect()
+    }
+}
+
+/// The longest identifier allowed by the server (`NAMEDATALEN - 1`).
+const MAX_IDENTIFIER_LEN: usize = 63;
+
+/// Check that the name can be used in the statements without quoting
+/// (so it refers to the same object whether it is quoted or not).
+///
+/// # Panics
+///
+/// If the name is empty, too long or has the characters other than
+/// the lowercase ASCII letters, digits, `_` and `$` (or starts with a digit or `$`).
+pub(crate) fn validate_name(what: &str, name: &str) {
+    let mut chars = name.chars();
+    let valid_start = matches!(chars.next(), Some('a'..='z' | '_'));
+    let valid_rest = chars.all(|c| matches!(c, 'a'..='z' | '0'..='9' | '_' | '$'));
+    assert!(
+        valid_start && valid_rest && name.len() <= MAX_IDENTIFIER_LEN,
+        "Invalid {} name {:?}: only the lowercase identifiers up to {} bytes are supported",
+        what,
+        name,
+        MAX_IDENTIFIER_LEN
+    );
+}
+
+/// The generated name fitting into the [`MAX_IDENTIFIER_LEN`]:
+/// the longer ones are truncated and suffixed with the hash of the full name
+/// (so the names sharing the long prefix do not collide).
+pub(crate) fn shorten_identifier(name: String) -> String {
+    if name.len() <= MAX_IDENTIFIER_LEN {
+        return name;
+    }
+    // FNV-1a is stable across the compiler versions unlike the `DefaultHasher`
+    let hash = name.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+    let mut end = MAX_IDENTIFIER_LEN - 9;
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}_{:08x}", &name[..end], hash)
+}
+
+/// The `WITH (name = value, ...)` clause of the storage parameters
+/// (with the leading space, empty if there are no parameters).
+pub(crate) fn storage_params_sql(params: &[(String, String)]) -> String {
+    if params.is_empty() {
+        return String::new();
+    }
+    let params = params
+        .iter()
+        .map(|(name, value)| format!("{} = {}", name, value));
+    format!(" WITH ({})", params.format(", "))
+}
+
+/// The quoted identifier (its case is preserved).
+pub(crate) fn quote_ident(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
 }
 
+/// The string literal with the quotes escaped.
+pub(crate) fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// # Panics
+///
+/// If the name of the type or any of its fields is not a valid lowercase identifier.
 pub fn struct_type(name: impl AsRef<str>, fields: &[(impl AsRef<str>, Type)]) -> Type {
+    validate_name("type", name.as_ref());
     let fields = fields
         .iter()
-        .map(|(name, type_)| Field::new(name.as_ref().to_owned(), type_.clone()))
+        .map(|(name, type_)| {
+            validate_name("field", name.as_ref());
+            Field::new(name.as_ref().to_owned(), type_.clone())
+        })
         .collect();
     Type::new(
         name.as_ref().to_owned(),
@@ -84,7 +201,13 @@ pub fn struct_type(name: impl AsRef<str>, fields: &[(impl AsRef<str>, Type)]) ->
     )
 }
 
+/// The variants are arbitrary strings.
+///
+/// # Panics
+///
+/// If the name of the type is not a valid lowercase identifier.
 pub fn enum_type(name: impl AsRef<str>, variants: &[impl AsRef<str>]) -> Type {
+    validate_name("type", name.as_ref());
     let variants = variants
         .iter()
         .map(|name| name.as_ref().to_owned())
@@ -97,7 +220,84 @@ pub fn enum_type(name: impl AsRef<str>, variants: &[impl AsRef<str>]) -> Type {
     )
 }
 
+/// The Rust enum mapped to the PostgreSQL enum type (see the [`pg_enum`][crate::pg_enum]).
+pub trait PgEnum: Sized {
+    /// The name of the PostgreSQL type.
+    const TYPE_NAME: &'static str;
+    /// The labels of the variants in the order of their declaration.
+    const LABELS: &'static [&'static str];
+
+    /// The label of the variant.
+    fn label(&self) -> &'static str;
+
+    /// The type to use in the column definition
+    /// (so the type is created along with the table).
+    fn sql_type() -> Type {
+        enum_type(Self::TYPE_NAME, Self::LABELS)
+    }
+}
+
 pub fn array_type(of: Type) -> Type {
     let plural = format!("{}[]", of.name());
     Type::new(plural, 0, Kind::Array(of), "public".into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortened() {
+        let short = "id_idx_users".to_owned();
+        assert_eq!(shorten_identifier(short.clone()), short);
+
+        let long = format!("{}_idx_events", "a".repeat(60));
+        let shortened = shorten_identifier(long.clone());
+        assert_eq!(shortened.len(), MAX_IDENTIFIER_LEN);
+        assert!(shortened.starts_with(&"a".repeat(54)));
+        assert_eq!(shortened, shorten_identifier(long));
+        let other = shorten_identifier(format!("{}_idx_events2", "a".repeat(60)));
+        assert_ne!(shortened, other);
+    }
+
+    #[test]
+    fn escaped_variants() {
+        let ty = enum_type("quote_mood", &["it's ok", "fine"]);
+        assert_eq!(
+            ObjectAndCreateSql::from_type(&ty),
+            [ObjectAndCreateSql::new(
+                "quote_mood",
+                "CREATE TYPE \"quote_mood\" AS ENUM ('it''s ok', 'fine')"
+            )
+            .with_enum_labels(&["it's ok", "fine"])]
+        );
+    }
+
+    #[test]
+    fn added_variants() {
+        let ty = enum_type("mood", &["sad", "ok", "meh", "happy"]);
+        let def = &ObjectAndCreateSql::from_type(&ty)[0];
+        assert_eq!(
+            def.add_enum_values_sql(&["ok".into(), "happy".into()]),
+            [
+                "ALTER TYPE \"mood\" ADD VALUE IF NOT EXISTS 'sad' BEFORE 'ok'",
+                "ALTER TYPE \"mood\" ADD VALUE IF NOT EXISTS 'meh' AFTER 'ok'",
+            ]
+        );
+        assert!(def
+            .add_enum_values_sql(&["sad".into(), "ok".into(), "meh".into(), "happy".into()])
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid type name \"mood'; DROP TABLE users; --\"")]
+    fn injected_type_name() {
+        let _ = enum_type("mood'; DROP TABLE users; --", &["ok"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid field name \"Label\"")]
+    fn uppercase_field_name() {
+        let _ = struct_type("labeled", &[("Label", Type::TEXT)]);
+    }
+}
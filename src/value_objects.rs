@@ -0,0 +1,424 @@
+//! Ready-made multi-column values to embed into the tables with `#[pg(flatten)]`.
+
+use std::{
+    any::type_name,
+    error::Error,
+    fmt::{self, Display},
+};
+
+use postgres_protocol::types as protocol;
+use postgres_types::{private::BytesMut, to_sql_checked, FromSql, IsNull, ToSql, Type as DbType};
+use tokio_postgres::{Error as DbError, Row};
+
+use crate::{
+    column::{Column, ColumnBuilder},
+    constraint::CheckConstraint,
+    macros::{__ColumnName, __prefixed},
+    table::{FieldValue, Flatten, FromRow},
+};
+
+/// Amount of money in the minor units (with the scale of 2 decimal digits)
+/// stored as `numeric`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cents(pub i64);
+
+const NUMERIC_NEG: u16 = 0x4000;
+const NUMERIC_SCALE: u16 = 2;
+const NUMERIC_BASE: i128 = 10_000;
+
+impl ToSql for Cents {
+    fn to_sql(&self, _: &DbType, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>>
+    where
+        Self: Sized,
+    {
+        let abs = self.0.unsigned_abs();
+        let (mut units, fraction) = (abs / 100, abs % 100);
+
+        let mut digits = Vec::new();
+        while units > 0 {
+            digits.push((units % 10_000) as i16);
+            units /= 10_000;
+        }
+        digits.reverse();
+        let weight = digits.len() as i16 - 1;
+        // the fraction is the first two decimal digits of the next base-10000 digit
+        digits.push((fraction * 100) as i16);
+        while digits.last() == Some(&0) {
+            let _ = digits.pop();
+        }
+
+        let sign = if self.0 < 0 { NUMERIC_NEG } else { 0 };
+        let weight = if digits.is_empty() { 0 } else { weight };
+        out.extend_from_slice(&(digits.len() as i16).to_be_bytes());
+        out.extend_from_slice(&weight.to_be_bytes());
+        out.extend_from_slice(&sign.to_be_bytes());
+        out.extend_from_slice(&NUMERIC_SCALE.to_be_bytes());
+        for digit in digits {
+            out.extend_from_slice(&digit.to_be_bytes());
+        }
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &DbType) -> bool
+    where
+        Self: Sized,
+    {
+        *ty == DbType::NUMERIC
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for Cents {
+    fn from_sql(_: &DbType, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let mut header = [0_u16; 4];
+        if raw.len() < header.len() * 2 {
+            return Err("Invalid numeric: too short".into());
+        }
+        let (head, mut digits) = raw.split_at(header.len() * 2);
+        for (value, bytes) in header.iter_mut().zip(head.chunks_exact(2)) {
+            *value = u16::from_be_bytes([bytes[0], bytes[1]]);
+        }
+        let [count, weight, sign, _scale] = header;
+        if sign != 0 && sign != NUMERIC_NEG {
+            return Err("Invalid numeric: NaN or infinity".into());
+        }
+
+        let mut cents: i128 = 0;
+        let mut exponent = i32::from(weight as i16);
+        for _ in 0..count {
+            let digit = digits.get(..2).ok_or("Invalid numeric: too short")?;
+            let digit = i128::from(protocol::int2_from_sql(digit)?);
+            digits = &digits[2..];
+            let value = match exponent {
+                // the digit covers 4 decimal places: only 2 of them are allowed
+                -1 if digit % 100 == 0 => digit / 100,
+                e if e < 0 && digit == 0 => 0,
+                e if e < 0 => return Err("Too many decimal digits for the amount".into()),
+                e => NUMERIC_BASE
+                    .checked_pow(e as u32)
+                    .and_then(|base| base.checked_mul(digit * 100))
+                    .ok_or("The amount of money is too big")?,
+            };
+            cents = cents
+                .checked_add(value)
+                .ok_or("The amount of money is too big")?;
+            exponent -= 1;
+        }
+
+        if sign == NUMERIC_NEG {
+            cents = -cents;
+        }
+        Ok(Self(i64::try_from(cents)?))
+    }
+
+    fn accepts(ty: &DbType) -> bool {
+        *ty == DbType::NUMERIC
+    }
+}
+
+/// Three-letter uppercase ISO 4217 code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Currency([u8; 3]);
+
+impl Currency {
+    pub fn new(code: &str) -> Result<Self, String> {
+        let code: [u8; 3] = code
+            .as_bytes()
+            .try_into()
+            .map_err(|_| format!("Currency code should have 3 letters: {:?}", code))?;
+        if !code.iter().all(u8::is_ascii_uppercase) {
+            return Err(format!(
+                "Currency code should be in uppercase: {:?}",
+                String::from_utf8_lossy(&code)
+            ));
+        }
+        Ok(Self(code))
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.0).expect("Currency is always ASCII")
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl ToSql for Currency {
+    fn to_sql(
+        &self,
+        ty: &DbType,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>>
+    where
+        Self: Sized,
+    {
+        self.as_str().to_sql(ty, out)
+    }
+
+    fn accepts(ty: &DbType) -> bool
+    where
+        Self: Sized,
+    {
+        <&str as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for Currency {
+    fn from_sql(ty: &DbType, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let code = <&str as FromSql>::from_sql(ty, raw)?;
+        Ok(Self::new(code)?)
+    }
+
+    fn accepts(ty: &DbType) -> bool {
+        <&str as FromSql>::accepts(ty)
+    }
+}
+
+/// Amount with its currency stored in two columns:
+/// `amount numeric` and `currency bpchar`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Money {
+    amount: Cents,
+    currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: Cents, currency: &str) -> Result<Self, String> {
+        let currency = Currency::new(currency)?;
+        Ok(Self { amount, currency })
+    }
+
+    pub const fn amount(&self) -> Cents {
+        self.amount
+    }
+
+    pub const fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Database-side validation of the columns having the `prefix`.
+    pub fn check_constraint(name: impl AsRef<str>, prefix: &str) -> CheckConstraint {
+        CheckConstraint::new(name, format!("{}currency ~ '^[A-Z]{{3}}$'", prefix))
+    }
+}
+
+impl Flatten for Money {
+    const COLUMNS: usize = 2;
+    const NAMES: &'static [__ColumnName] = &[
+        __ColumnName::Plain("amount"),
+        __ColumnName::Plain("currency"),
+    ];
+
+    fn flat_columns(prefix: &str) -> Vec<Column> {
+        vec![
+            ColumnBuilder::new(__prefixed(prefix, "amount"), DbType::NUMERIC)
+                .rust_type(type_name::<Cents>())
+                .finish(),
+            ColumnBuilder::new(__prefixed(prefix, "currency"), DbType::BPCHAR)
+                .rust_type(type_name::<Currency>())
+                .finish(),
+        ]
+    }
+
+    fn flat_values<'a, F>(&'a self, out: &mut F)
+    where
+        F: FnMut(FieldValue<'a>),
+    {
+        out((&self.amount).into());
+        out((&self.currency).into());
+    }
+
+    fn from_prefixed_row(row: &Row, prefix: &str) -> Result<Self, DbError> {
+        Ok(Self {
+            amount: row.try_get(&*__prefixed(prefix, "amount"))?,
+            currency: row.try_get(&*__prefixed(prefix, "currency"))?,
+        })
+    }
+}
+
+impl FromRow for Money {
+    fn from_row(row: &Row, offset: usize) -> Result<Self, DbError> {
+        Ok(Self {
+            amount: row.try_get(offset)?,
+            currency: row.try_get(offset + 1)?,
+        })
+    }
+}
+
+/// Postal address stored in the `street`, `city`,
+/// nullable `postal_code` and `country` text columns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Address {
+    street: String,
+    city: String,
+    postal_code: Option<String>,
+    country: String,
+}
+
+impl Address {
+    pub fn new(
+        street: impl Into<String>,
+        city: impl Into<String>,
+        postal_code: Option<String>,
+        country: impl Into<String>,
+    ) -> Result<Self, String> {
+        let address = Self {
+            street: street.into(),
+            city: city.into(),
+            postal_code,
+            country: country.into(),
+        };
+        for (name, value) in [
+            ("street", &address.street),
+            ("city", &address.city),
+            ("country", &address.country),
+        ] {
+            if value.trim().is_empty() {
+                return Err(format!("The {} of the address should not be empty", name));
+            }
+        }
+        Ok(address)
+    }
+
+    pub fn street(&self) -> &str {
+        &self.street
+    }
+
+    pub fn city(&self) -> &str {
+        &self.city
+    }
+
+    pub fn postal_code(&self) -> Option<&str> {
+        self.postal_code.as_deref()
+    }
+
+    pub fn country(&self) -> &str {
+        &self.country
+    }
+
+    /// Database-side validation of the columns having the `prefix`.
+    pub fn check_constraint(name: impl AsRef<str>, prefix: &str) -> CheckConstraint {
+        let condition = ["street", "city", "country"]
+            .iter()
+            .map(|col| format!("btrim({}{}) <> ''", prefix, col))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        CheckConstraint::new(name, condition)
+    }
+}
+
+impl Flatten for Address {
+    const COLUMNS: usize = 4;
+    const NAMES: &'static [__ColumnName] = &[
+        __ColumnName::Plain("street"),
+        __ColumnName::Plain("city"),
+        __ColumnName::Plain("postal_code"),
+        __ColumnName::Plain("country"),
+    ];
+
+    fn flat_columns(prefix: &str) -> Vec<Column> {
+        vec![
+            ColumnBuilder::new(__prefixed(prefix, "street"), DbType::TEXT)
+                .rust_type(type_name::<String>())
+                .finish(),
+            ColumnBuilder::new(__prefixed(prefix, "city"), DbType::TEXT)
+                .rust_type(type_name::<String>())
+                .finish(),
+            ColumnBuilder::new(__prefixed(prefix, "postal_code"), DbType::TEXT)
+                .rust_type(type_name::<Option<String>>())
+                .nullable()
+                .finish(),
+            ColumnBuilder::new(__prefixed(prefix, "country"), DbType::TEXT)
+                .rust_type(type_name::<String>())
+                .finish(),
+        ]
+    }
+
+    fn flat_values<'a, F>(&'a self, out: &mut F)
+    where
+        F: FnMut(FieldValue<'a>),
+    {
+        out((&self.street).into());
+        out((&self.city).into());
+        out((&self.postal_code).into());
+        out((&self.country).into());
+    }
+
+    fn from_prefixed_row(row: &Row, prefix: &str) -> Result<Self, DbError> {
+        Ok(Self {
+            street: row.try_get(&*__prefixed(prefix, "street"))?,
+            city: row.try_get(&*__prefixed(prefix, "city"))?,
+            postal_code: row.try_get(&*__prefixed(prefix, "postal_code"))?,
+            country: row.try_get(&*__prefixed(prefix, "country"))?,
+        })
+    }
+}
+
+impl FromRow for Address {
+    fn from_row(row: &Row, offset: usize) -> Result<Self, DbError> {
+        Ok(Self {
+            street: row.try_get(offset)?,
+            city: row.try_get(offset + 1)?,
+            postal_code: row.try_get(offset + 2)?,
+            country: row.try_get(offset + 3)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Constraint as _;
+
+    fn roundtrip(cents: i64) -> i64 {
+        let mut out = BytesMut::new();
+        let _ = Cents(cents).to_sql(&DbType::NUMERIC, &mut out).unwrap();
+        Cents::from_sql(&DbType::NUMERIC, &out).unwrap().0
+    }
+
+    #[test]
+    fn numeric_encoding() {
+        let mut out = BytesMut::new();
+        let _ = Cents(-1_000_012)
+            .to_sql(&DbType::NUMERIC, &mut out)
+            .unwrap();
+        // -10000.12 = -(1 * 10000^1 + 0 * 10000^0 + 1200 * 10000^-1)
+        let expected: Vec<u8> = [3_u16, 1, 0x4000, 2, 1, 0, 1200]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        assert_eq!(out.as_ref(), expected);
+
+        for cents in [0, 1, 99, 100, 10_000, 1_000_000, -5, i64::MAX, i64::MIN] {
+            assert_eq!(roundtrip(cents), cents);
+        }
+    }
+
+    #[test]
+    fn too_precise_numeric() {
+        // 0.001
+        let raw: Vec<u8> = [1_u16, (-1_i16) as u16, 0, 3, 10]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        assert!(Cents::from_sql(&DbType::NUMERIC, &raw).is_err());
+    }
+
+    #[test]
+    fn validation() {
+        assert!(Money::new(Cents(100), "EUR").is_ok());
+        assert!(Money::new(Cents(100), "eur").is_err());
+        assert!(Money::new(Cents(100), "EURO").is_err());
+        assert!(Address::new("Main St. 1", " ", None, "US").is_err());
+        assert_eq!(
+            Money::check_constraint("price_currency", "price_").as_sql(),
+            "CONSTRAINT price_currency CHECK (price_currency ~ '^[A-Z]{3}$')"
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use std::fmt;
+
+use itertools::Itertools as _;
+
+use crate::{
+    diff::TableDef,
+    introspect::{ConstraintKind, TableInfo},
+    table::Table,
+};
+
+/// The difference between the table definition and the live database schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    MissingTable,
+    MissingColumn(String),
+    /// The column exists in the database but not in the definition.
+    ExtraColumn(String),
+    ColumnType {
+        column: String,
+        expected: String,
+        actual: String,
+    },
+    Nullability {
+        column: String,
+        expected_nullable: bool,
+    },
+    PrimaryKey {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    MissingUnique(String),
+    MissingForeignKey {
+        column: String,
+        references: (String, String),
+    },
+    /// No check constraint on the column with the inline `CHECK`.
+    MissingCheck(String),
+    /// The table-level constraint with such a name is not found.
+    MissingConstraint(String),
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingTable => write!(f, "table does not exist"),
+            Self::MissingColumn(column) => write!(f, "column {} is missing", column),
+            Self::ExtraColumn(column) => write!(f, "column {} is not defined", column),
+            Self::ColumnType {
+                column,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "column {} has type {} instead of {}",
+                column, actual, expected
+            ),
+            Self::Nullability {
+                column,
+                expected_nullable,
+            } => {
+                let expected = if *expected_nullable {
+                    "NULL"
+                } else {
+                    "NOT NULL"
+                };
+                write!(f, "column {} should be {}", column, expected)
+            }
+            Self::PrimaryKey { expected, actual } => write!(
+                f,
+                "primary key is ({}) instead of ({})",
+                actual.join(", "),
+                expected.join(", ")
+            ),
+            Self::MissingUnique(column) => write!(f, "column {} is not unique", column),
+            Self::MissingForeignKey {
+                column,
+                references: (table, ref_column),
+            } => write!(
+                f,
+                "column {} does not reference {}({})",
+                column, table, ref_column
+            ),
+            Self::MissingCheck(column) => write!(f, "column {} has no check", column),
+            Self::MissingConstraint(name) => write!(f, "constraint {} is missing", name),
+        }
+    }
+}
+
+/// The result of comparing the table definition with the live database schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaReport {
+    table: String,
+    mismatches: Vec<Mismatch>,
+}
+
+impl SchemaReport {
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    pub fn mismatches(&self) -> &[Mismatch] {
+        &self.mismatches
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    pub(crate) fn compare<T, const N: usize>(live: Option<&TableInfo>) -> Self
+    where
+        T: Table<N>,
+    {
+        Self::compare_def(&TableDef::of::<T, N>(), live)
+    }
+
+    pub(crate) fn compare_def(def: &TableDef, live: Option<&TableInfo>) -> Self {
+        let mut report = Self {
+            table: def.name().to_owned(),
+            mismatches: vec![],
+        };
+        let Some(live) = live else {
+            report.mismatches.push(Mismatch::MissingTable);
+            return report;
+        };
+        let live_constraints = &live.constraints;
+
+        let columns = def.columns();
+        for col in columns {
+            let Some(live_col) = live.column(col.name()) else {
+                report
+                    .mismatches
+                    .push(Mismatch::MissingColumn(col.name().to_owned()));
+                continue;
+            };
+            let expected = col.catalog_type_name();
+            let actual = live_col.catalog_type_name();
+            if actual != expected {
+                report.mismatches.push(Mismatch::ColumnType {
+                    column: col.name().to_owned(),
+                    expected,
+                    actual,
+                });
+            }
+            if live_col.is_nullable() != col.is_nullable() {
+                report.mismatches.push(Mismatch::Nullability {
+                    column: col.name().to_owned(),
+                    expected_nullable: col.is_nullable(),
+                });
+            }
+        }
+        for live_col in &live.columns {
+            if !columns.iter().any(|col| col.name() == live_col.name()) {
+                report
+                    .mismatches
+                    .push(Mismatch::ExtraColumn(live_col.name().to_owned()));
+            }
+        }
+
+        let primary_key: Vec<_> = columns
+            .iter()
+            .filter(|col| col.is_primary_key())
+            .map(|col| col.name().to_owned())
+            .collect();
+        if !primary_key.is_empty() {
+            let actual = live_constraints
+                .iter()
+                .find(|c| c.kind == ConstraintKind::PrimaryKey)
+                .map(|c| c.columns.clone())
+                .unwrap_or_default();
+            if actual.iter().sorted().ne(primary_key.iter().sorted()) {
+                report.mismatches.push(Mismatch::PrimaryKey {
+                    expected: primary_key,
+                    actual,
+                });
+            }
+        }
+
+        for col in columns {
+            let unique = live_constraints.iter().any(|c| {
+                c.is_on_single(ConstraintKind::Unique, col.name())
+                    || c.is_on_single(ConstraintKind::PrimaryKey, col.name())
+            });
+            if col.is_unique() && !col.is_primary_key() && !unique {
+                report
+                    .mismatches
+                    .push(Mismatch::MissingUnique(col.name().to_owned()));
+            }
+            if let Some((table, ref_column)) = col.foreign_key() {
+                let found = live_constraints.iter().any(|c| {
+                    c.is_on_single(ConstraintKind::ForeignKey, col.name())
+                        && matches!(&c.references, Some((t, cols)) if *t == table && *cols == [ref_column.as_str()])
+                });
+                if !found {
+                    report.mismatches.push(Mismatch::MissingForeignKey {
+                        column: col.name().to_owned(),
+                        references: (table, ref_column),
+                    });
+                }
+            }
+            let checked = live_constraints
+                .iter()
+                .any(|c| c.is_on_single(ConstraintKind::Check, col.name()));
+            if col.check().is_some() && !checked {
+                report
+                    .mismatches
+                    .push(Mismatch::MissingCheck(col.name().to_owned()));
+            }
+        }
+
+        for constraint in def.constraints() {
+            // unquoted identifiers are folded to lower case
+            let name = constraint.name().to_lowercase();
+            if !live_constraints.iter().any(|c| c.name == name) {
+                report
+                    .mismatches
+                    .push(Mismatch::MissingConstraint(constraint.name().to_owned()));
+            }
+        }
+        report
+    }
+}
+
+impl fmt::Display for SchemaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "Table {} matches the definition", self.table);
+        }
+        write!(f, "Table {} differs from the definition:", self.table)?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n- {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{
+        array_type, gen_table, introspect::ConstraintInfo, CheckConstraint, Column, ColumnBuilder,
+    };
+
+    gen_table!(
+        struct Order("orders") {
+            id: i32 = Type::INT4; [primary_key()],
+            customer_id: i32 = Type::INT4; [foreign_key("customers", "id")],
+            code: String = Type::TEXT; [unique()],
+            tags: Vec<String> = array_type(Type::TEXT),
+            note: Option<String> = Type::TEXT; [nullable()],
+            => constraints = [
+                CheckConstraint::new("Positive_Id", "id > 0"),
+            ]
+        }
+    );
+
+    fn live_column(name: &str, ty: Type, nullable: bool) -> Column {
+        let col = ColumnBuilder::new(name, ty);
+        if nullable {
+            col.nullable().finish()
+        } else {
+            col.finish()
+        }
+    }
+
+    fn live_constraint(
+        name: &str,
+        kind: ConstraintKind,
+        columns: &[&str],
+        references: Option<(&str, &str)>,
+    ) -> ConstraintInfo {
+        ConstraintInfo {
+            name: name.into(),
+            kind,
+            columns: columns.iter().map(|&c| c.into()).collect(),
+            references: references.map(|(table, col)| (table.into(), vec![col.into()])),
+            definition: String::new(),
+        }
+    }
+
+    fn live_table(columns: Vec<Column>, constraints: Vec<ConstraintInfo>) -> TableInfo {
+        TableInfo {
+            name: "orders".into(),
+            columns,
+            constraints,
+            indexes: vec![],
+        }
+    }
+
+    #[test]
+    fn matching_schema() {
+        let columns = vec![
+            live_column("id", Type::INT4, false),
+            live_column("customer_id", Type::INT4, false),
+            live_column("code", Type::TEXT, false),
+            live_column("tags", Type::TEXT_ARRAY, false),
+            live_column("note", Type::TEXT, true),
+        ];
+        let constraints = vec![
+            live_constraint("orders_pkey", ConstraintKind::PrimaryKey, &["id"], None),
+            live_constraint("orders_code_key", ConstraintKind::Unique, &["code"], None),
+            live_constraint(
+                "orders_customer_id_fkey",
+                ConstraintKind::ForeignKey,
+                &["customer_id"],
+                Some(("customers", "id")),
+            ),
+            live_constraint("positive_id", ConstraintKind::Check, &["id"], None),
+        ];
+        let report = SchemaReport::compare::<Order, 5>(Some(&live_table(columns, constraints)));
+        assert!(report.is_ok(), "{}", report);
+    }
+
+    #[test]
+    fn drifted_schema() {
+        let columns = vec![
+            live_column("id", Type::INT8, false),
+            live_column("customer_id", Type::INT4, true),
+            live_column("tags", Type::TEXT_ARRAY, false),
+            live_column("note", Type::TEXT, true),
+            live_column("legacy", Type::BOOL, true),
+        ];
+        let constraints = vec![live_constraint(
+            "orders_pkey",
+            ConstraintKind::PrimaryKey,
+            &["id", "note"],
+            None,
+        )];
+        let report = SchemaReport::compare::<Order, 5>(Some(&live_table(columns, constraints)));
+        assert_eq!(
+            report.mismatches(),
+            [
+                Mismatch::ColumnType {
+                    column: "id".into(),
+                    expected: "int4".into(),
+                    actual: "int8".into()
+                },
+                Mismatch::Nullability {
+                    column: "customer_id".into(),
+                    expected_nullable: false
+                },
+                Mismatch::MissingColumn("code".into()),
+                Mismatch::ExtraColumn("legacy".into()),
+                Mismatch::PrimaryKey {
+                    expected: vec!["id".into()],
+                    actual: vec!["id".into(), "note".into()]
+                },
+                Mismatch::MissingForeignKey {
+                    column: "customer_id".into(),
+                    references: ("customers".into(), "id".into())
+                },
+                Mismatch::MissingUnique("code".into()),
+                Mismatch::MissingConstraint("Positive_Id".into()),
+            ]
+        );
+        assert_eq!(
+            report.to_string().lines().nth(1),
+            Some("- column id has type int8 instead of int4")
+        );
+    }
+
+    #[test]
+    fn column_check() {
+        gen_table!(
+            struct Product("products") {
+                id: i32 = Type::INT4; [primary_key()],
+                price: i64 = Type::INT8; [check("price > 0")],
+            }
+        );
+
+        let columns = || {
+            vec![
+                live_column("id", Type::INT4, false),
+                live_column("price", Type::INT8, false),
+            ]
+        };
+        let mut constraints = vec![live_constraint(
+            "products_pkey",
+            ConstraintKind::PrimaryKey,
+            &["id"],
+            None,
+        )];
+        let report =
+            SchemaReport::compare::<Product, 2>(Some(&live_table(columns(), constraints.clone())));
+        assert_eq!(
+            report.mismatches(),
+            [Mismatch::MissingCheck("price".into())]
+        );
+
+        constraints.push(live_constraint(
+            "products_price_check",
+            ConstraintKind::Check,
+            &["price"],
+            None,
+        ));
+        let report = SchemaReport::compare::<Product, 2>(Some(&live_table(columns(), constraints)));
+        assert!(report.is_ok(), "{}", report);
+    }
+
+    #[test]
+    fn missing_table() {
+        let report = SchemaReport::compare::<Order, 5>(None);
+        assert_eq!(report.mismatches(), [Mismatch::MissingTable]);
+    }
+}
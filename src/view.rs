@@ -0,0 +1,192 @@
+use std::fmt::Write as _;
+
+/// The read model defined with a query on top of the tables.
+///
+/// The rows are mapped with the `TryFrom<Row>` of the implementing type.
+pub trait View {
+    /// The name of the view (qualified with the schema if it is not in the `search_path`).
+    fn name() -> &'static str;
+
+    /// The defining _SELECT_ statement.
+    fn query() -> String;
+
+    /// Store the result of the query (refreshed explicitly with the
+    /// [`refresh_view`][crate::PgTableExtension::refresh_view]).
+    fn materialized() -> bool {
+        false
+    }
+
+    fn create_view_sql() -> String {
+        if Self::materialized() {
+            format!(
+                "CREATE MATERIALIZED VIEW IF NOT EXISTS {} AS {}",
+                Self::name(),
+                Self::query()
+            )
+        } else {
+            format!(
+                "CREATE OR REPLACE VIEW {} AS {}",
+                Self::name(),
+                Self::query()
+            )
+        }
+    }
+
+    fn select_sql(condition: Option<&str>) -> String {
+        let mut query = format!("SELECT * FROM {}", Self::name());
+        if let Some(condition) = condition {
+            write!(query, " WHERE {}", condition).unwrap();
+        }
+        query
+    }
+}
+
+/// # Panics
+///
+/// If the view is not materialized.
+pub(crate) fn refresh_view_sql<V>(concurrently: bool) -> String
+where
+    V: View,
+{
+    assert!(V::materialized(), "View {} is not materialized", V::name());
+    format!(
+        "REFRESH MATERIALIZED VIEW {}{}",
+        if concurrently { "CONCURRENTLY " } else { "" },
+        V::name()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres::Row;
+    use postgres_types::Type;
+
+    use super::*;
+    use crate::{gen_table, test_helpers::get_client, PgTableExtension as _};
+
+    gen_table!(
+        struct Order("view_orders") {
+            id: i32 = Type::INT4; [primary_key()],
+            customer: String = Type::TEXT,
+            total: i64 = Type::INT8,
+        }
+    );
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct CustomerTotal {
+        customer: String,
+        total: i64,
+    }
+
+    impl TryFrom<Row> for CustomerTotal {
+        type Error = postgres::Error;
+
+        fn try_from(row: Row) -> Result<Self, Self::Error> {
+            Ok(Self {
+                customer: row.try_get("customer")?,
+                total: row.try_get("total")?,
+            })
+        }
+    }
+
+    impl View for CustomerTotal {
+        fn name() -> &'static str {
+            "view_customer_totals"
+        }
+
+        fn query() -> String {
+            "SELECT customer, sum(total)::int8 AS total FROM view_orders GROUP BY customer"
+                .to_owned()
+        }
+    }
+
+    struct Snapshot;
+
+    impl View for Snapshot {
+        fn name() -> &'static str {
+            "view_totals_snapshot"
+        }
+
+        fn query() -> String {
+            CustomerTotal::query()
+        }
+
+        fn materialized() -> bool {
+            true
+        }
+    }
+
+    impl TryFrom<Row> for Snapshot {
+        type Error = postgres::Error;
+
+        fn try_from(_: Row) -> Result<Self, Self::Error> {
+            Ok(Self)
+        }
+    }
+
+    #[test]
+    fn statements() {
+        assert_eq!(
+            CustomerTotal::create_view_sql(),
+            "CREATE OR REPLACE VIEW view_customer_totals AS \
+            SELECT customer, sum(total)::int8 AS total FROM view_orders GROUP BY customer"
+        );
+        assert!(Snapshot::create_view_sql()
+            .starts_with("CREATE MATERIALIZED VIEW IF NOT EXISTS view_totals_snapshot AS"));
+        assert_eq!(
+            refresh_view_sql::<Snapshot>(true),
+            "REFRESH MATERIALIZED VIEW CONCURRENTLY view_totals_snapshot"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "View view_customer_totals is not materialized")]
+    fn refresh_plain() {
+        let _ = refresh_view_sql::<CustomerTotal>(false);
+    }
+
+    #[test]
+    fn read_model() {
+        let Some(mut client) = get_client() else {
+            return;
+        };
+        client
+            .batch_execute("DROP TABLE IF EXISTS view_orders CASCADE")
+            .unwrap();
+        client.create_table::<Order, 3>().unwrap();
+        client.create_view::<CustomerTotal>().unwrap();
+        client.create_view::<Snapshot>().unwrap();
+        // the plain view is replaced, the materialized one is kept
+        client.create_view::<CustomerTotal>().unwrap();
+        client.create_view::<Snapshot>().unwrap();
+
+        let orders =
+            [(1, "ann", 10), (2, "bob", 5), (3, "ann", 7)].map(|(id, customer, total)| Order {
+                id,
+                customer: customer.into(),
+                total,
+            });
+        client.insert_rows(&orders).unwrap();
+
+        let totals: Vec<CustomerTotal> = client
+            .select_view("customer = $1".to_owned(), &[&"ann"])
+            .unwrap();
+        assert_eq!(
+            totals,
+            [CustomerTotal {
+                customer: "ann".into(),
+                total: 17
+            }]
+        );
+
+        let stale: Vec<Snapshot> = client.select_view(None, &[]).unwrap();
+        assert!(stale.is_empty());
+        client.refresh_view::<Snapshot>(false).unwrap();
+        let fresh: Vec<Snapshot> = client.select_view(None, &[]).unwrap();
+        assert_eq!(fresh.len(), 2);
+
+        client
+            .batch_execute("DROP TABLE view_orders CASCADE")
+            .unwrap();
+    }
+}